@@ -1,13 +1,32 @@
 import * as vscode from "vscode";
 import {
+	CargoManifestProvider,
 	RustCodeActionProvider,
 	RustDecorationProvider,
 	RustHoverProvider,
 	SmartDiagnosticProvider,
 } from "./providers";
-import { type ProjectContext, RuleEngine } from "./rules";
-import { CargoAnalyzerService, CompilerErrorLinker, PatternTracker } from "./services";
-import { LearnPanel } from "./webview";
+import {
+	DEFAULT_MIN_CONFIDENCE,
+	isCargoManifest,
+	type ProjectContext,
+	type Rule,
+	RuleEngine,
+	type RuleSettings,
+	type RuleUserSettings,
+} from "./rules";
+import {
+	CargoAnalyzerService,
+	CompilerErrorLinker,
+	EditionMigration,
+	MIGRATION_EDITION,
+	PatternTracker,
+	ProjectProfileService,
+	RuleLoadReporter,
+	RulePackWatcher,
+	suggestProjectContext,
+} from "./services";
+import { EditionReportPanel, LearnPanel } from "./webview";
 
 let decorationProvider: RustDecorationProvider;
 let smartDiagnosticProvider: SmartDiagnosticProvider;
@@ -19,14 +38,23 @@ let patternTracker: PatternTracker;
 export function activate(context: vscode.ExtensionContext) {
 	console.log("Rust Compass is now active!");
 
-	// Initialize rule engine
-	const ruleEngine = new RuleEngine(context.extensionPath);
+	// Initialize rule engine (bundled rules plus workspace rule packs)
+	const rulePackWatcher = new RulePackWatcher();
+	context.subscriptions.push(rulePackWatcher);
+	const ruleEngine = new RuleEngine(context.extensionPath, () =>
+		RulePackWatcher.getRuleDirectories(),
+	);
+	ruleEngine.setRuleSettings(readRuleSettings());
 
 	// Initialize Cargo analyzer
 	cargoAnalyzer = CargoAnalyzerService.getInstance();
+	const projectProfiles = ProjectProfileService.getInstance();
 	cargoAnalyzer.initialize().then(async () => {
-		// Check if we should suggest a project context
-		const suggestion = await cargoAnalyzer.suggestProjectContext();
+		// Check if we should suggest a project context, going by the crate being edited
+		const profile = await projectProfiles.getProfile(
+			vscode.window.activeTextEditor?.document.uri,
+		);
+		const suggestion = suggestProjectContext(profile);
 		if (suggestion) {
 			const config = vscode.workspace.getConfiguration("rustCompass");
 			const currentSetting = config.get<ProjectContext>("projectContext");
@@ -44,7 +72,7 @@ export function activate(context: vscode.ExtensionContext) {
 						suggestion.context,
 						vscode.ConfigurationTarget.Workspace,
 					);
-					currentContext = suggestion.context as ProjectContext;
+					currentContext = suggestion.context;
 					vscode.window.showInformationMessage(
 						`Project context set to: ${suggestion.context}`,
 					);
@@ -52,7 +80,7 @@ export function activate(context: vscode.ExtensionContext) {
 			}
 		}
 	});
-	context.subscriptions.push({ dispose: () => cargoAnalyzer.dispose() });
+	context.subscriptions.push({ dispose: () => cargoAnalyzer.dispose() }, projectProfiles);
 
 	// Context getter
 	const getContext = (): ProjectContext => {
@@ -64,6 +92,13 @@ export function activate(context: vscode.ExtensionContext) {
 	const hoverProvider = new RustHoverProvider(ruleEngine, getContext);
 	context.subscriptions.push(vscode.languages.registerHoverProvider("rust", hoverProvider));
 
+	// Dependency details and manifest rules in Cargo.toml
+	const manifestProvider = new CargoManifestProvider(ruleEngine, getContext);
+	context.subscriptions.push(
+		manifestProvider,
+		vscode.languages.registerHoverProvider({ pattern: "**/Cargo.toml" }, manifestProvider),
+	);
+
 	// Initialize Pattern Tracker
 	patternTracker = PatternTracker.getInstance();
 	patternTracker.initialize(context.globalState);
@@ -91,6 +126,9 @@ export function activate(context: vscode.ExtensionContext) {
 		hoverProvider.onRuleHovered((rule) => {
 			patternTracker.recordPattern(rule.id);
 		}),
+		manifestProvider.onRuleHovered((rule) => {
+			patternTracker.recordPattern(rule.id);
+		}),
 	);
 
 	// Register Decoration Provider
@@ -111,9 +149,61 @@ export function activate(context: vscode.ExtensionContext) {
 	context.subscriptions.push(compilerErrorLinker);
 
 	// Initialize Smart Diagnostic Provider (intent-based squiggly hints)
-	smartDiagnosticProvider = new SmartDiagnosticProvider(ruleEngine);
+	smartDiagnosticProvider = new SmartDiagnosticProvider(ruleEngine, getContext);
 	context.subscriptions.push(smartDiagnosticProvider);
 
+	// Report rule files that failed validation, and which rules workspace packs override
+	const ruleLoadReporter = new RuleLoadReporter(ruleEngine);
+	context.subscriptions.push(ruleLoadReporter);
+	ruleLoadReporter.update();
+
+	// Re-run everything that shows hints after the active rule set changes
+	const refreshHints = () => {
+		if (vscode.window.activeTextEditor) {
+			decorationProvider.triggerUpdate(vscode.window.activeTextEditor);
+		}
+		smartDiagnosticProvider.refresh();
+	};
+
+	// Hot-reload workspace rule packs
+	context.subscriptions.push(
+		rulePackWatcher.onDidChange(() => {
+			ruleEngine.reloadRules();
+			ruleLoadReporter.update();
+			refreshHints();
+		}),
+	);
+
+	// Per-rule overrides (`rustCompass.rules`), the decoration threshold and the
+	// `cargo metadata` switch apply immediately
+	context.subscriptions.push(
+		vscode.workspace.onDidChangeConfiguration((e) => {
+			if (
+				e.affectsConfiguration("rustCompass.rules") ||
+				e.affectsConfiguration("rustCompass.minConfidence")
+			) {
+				ruleEngine.setRuleSettings(readRuleSettings());
+				refreshHints();
+			}
+			if (e.affectsConfiguration("rustCompass.cargoMetadata")) {
+				cargoAnalyzer.invalidateCache();
+			}
+		}),
+	);
+
+	// cargo only runs once the workspace is trusted
+	context.subscriptions.push(
+		vscode.workspace.onDidGrantWorkspaceTrust(() => cargoAnalyzer.invalidateCache()),
+	);
+
+	// Dependency-gated rules (requiresDependency / requiresCategory) follow Cargo.toml
+	context.subscriptions.push(
+		cargoAnalyzer.onDependenciesChanged(() => {
+			ruleEngine.setDependencies((uri) => cargoAnalyzer.getProjectDependencies(uri));
+			refreshHints();
+		}),
+	);
+
 	// When a compiler error is linked to a hint, offer to show help
 	compilerErrorLinker.onErrorLinked(({ error, rules }) => {
 		if (rules.length > 0) {
@@ -137,13 +227,100 @@ export function activate(context: vscode.ExtensionContext) {
 	});
 
 	// Register Code Action Provider
-	const codeActionProvider = new RustCodeActionProvider(ruleEngine, getContext);
+	const codeActionProvider = new RustCodeActionProvider(
+		ruleEngine,
+		getContext,
+		(document, diagnostic) => smartDiagnosticProvider.getFix(document, diagnostic),
+	);
 	context.subscriptions.push(
 		vscode.languages.registerCodeActionsProvider("rust", codeActionProvider, {
 			providedCodeActionKinds: RustCodeActionProvider.providedCodeActionKinds,
 		}),
 	);
 
+	// Apply one rule's fix at every match in the current file or the whole workspace
+	context.subscriptions.push(
+		vscode.commands.registerCommand(
+			"rust-compass.applyFixForRule",
+			async (args?: { ruleId: string }) => {
+				const ruleId = args?.ruleId ?? (await pickFixableRule(ruleEngine));
+				if (!ruleId) {
+					return;
+				}
+
+				const editor = vscode.window.activeTextEditor;
+				const scopes = [
+					...(editor?.document.languageId === "rust"
+						? [{ label: "Current File", workspace: false }]
+						: []),
+					{ label: "Workspace", description: "Every .rs file", workspace: true },
+				];
+				const scope =
+					scopes.length === 1
+						? scopes[0]
+						: await vscode.window.showQuickPick(scopes, {
+								placeHolder: "Where should the fix be applied?",
+							});
+				if (!scope) {
+					return;
+				}
+
+				const documents =
+					scope.workspace || !editor
+						? await vscode.window.withProgress(
+								{
+									location: vscode.ProgressLocation.Notification,
+									title: "Rust Compass: Finding matches…",
+								},
+								async () => {
+									const uris = await vscode.workspace.findFiles(
+										"**/*.rs",
+										"**/target/**",
+									);
+									return Promise.all(
+										uris.map((uri) => vscode.workspace.openTextDocument(uri)),
+									);
+								},
+							)
+						: [editor.document];
+
+				const { edit, fixes } = codeActionProvider.createFixAllEdit(
+					documents,
+					(rule) => rule.id === ruleId,
+					true,
+				);
+				if (fixes === 0) {
+					const title = ruleEngine.getRuleById(ruleId)?.title ?? ruleId;
+					vscode.window.showInformationMessage(`No fixable matches of "${title}" found.`);
+					return;
+				}
+				// Every change needs confirming, so this opens the refactor preview first
+				await vscode.workspace.applyEdit(edit);
+			},
+		),
+	);
+
+	// Replace an unwrap with `?`, changing the function to return `Result` if needed
+	context.subscriptions.push(
+		vscode.commands.registerCommand(
+			"rust-compass.propagateError",
+			async (args: { uri: string; offset: number }) => {
+				const uri = vscode.Uri.parse(args.uri);
+				const document = await vscode.workspace.openTextDocument(uri);
+				const dependencies = await cargoAnalyzer.getDependenciesFor(uri);
+				const style = dependencies.error.includes("anyhow") ? "anyhow" : "box";
+				const edit = await codeActionProvider.createPropagationEdit(
+					document,
+					args.offset,
+					style,
+				);
+				if (edit) {
+					await vscode.workspace.applyEdit(edit);
+				}
+			},
+		),
+	);
+
 	// Register Commands
 	context.subscriptions.push(
 		vscode.commands.registerCommand("rust-compass.toggleHints", () => {
@@ -216,11 +393,33 @@ export function activate(context: vscode.ExtensionContext) {
 	// Show detected dependencies
 	context.subscriptions.push(
 		vscode.commands.registerCommand("rust-compass.showDependencies", async () => {
-			const summary = await cargoAnalyzer.getDependencySummary();
-			vscode.window.showInformationMessage(summary, { modal: false });
+			const uri = vscode.window.activeTextEditor?.document.uri;
+			const summary = await cargoAnalyzer.getDependencySummary(uri);
+			// What the crate looks like, with the evidence for each trait
+			const traits = (await projectProfiles.getProfile(uri)).traits.map(
+				(t) => `🧭 ${t.trait} (${Math.round(t.confidence * 100)}%): ${t.reason}`,
+			);
+			vscode.window.showInformationMessage([summary, ...traits].join("\n"), {
+				modal: false,
+			});
 		}),
 	);
 
+	// Show what the last rule load loaded, skipped and overrode
+	context.subscriptions.push(
+		vscode.commands.registerCommand("rust-compass.showRuleLoadReport", () =>
+			ruleLoadReporter.showReport(),
+		),
+	);
+
+	// Report code whose meaning changes when crates move to the latest edition
+	const editionMigration = new EditionMigration(ruleEngine);
+	context.subscriptions.push(
+		vscode.commands.registerCommand("rust-compass.checkEditionMigration", () =>
+			EditionReportPanel.show(editionMigration, MIGRATION_EDITION),
+		),
+	);
+
 	// Restore all dismissed rules
 	context.subscriptions.push(
 		vscode.commands.registerCommand("rust-compass.restoreAllHints", async () => {
@@ -303,13 +502,36 @@ export function activate(context: vscode.ExtensionContext) {
 	);
 
 	// Learn more command - opens the learn panel for a rule
+	// (code actions use it under the name showRuleDetails)
+	const showRuleDetails = (args: { ruleId: string }) => {
+		const rule = ruleEngine.getRuleById(args.ruleId);
+		if (rule) {
+			LearnPanel.show(context.extensionUri, rule);
+		}
+	};
 	context.subscriptions.push(
-		vscode.commands.registerCommand("rust-compass.learnMore", (args: { ruleId: string }) => {
-			const rule = ruleEngine.getRuleById(args.ruleId);
-			if (rule) {
-				LearnPanel.show(context.extensionUri, rule);
-			}
-		}),
+		vscode.commands.registerCommand("rust-compass.learnMore", showRuleDetails),
+		vscode.commands.registerCommand("rust-compass.showRuleDetails", showRuleDetails),
+	);
+
+	// Refactor actions follow up with the rules that explain the rewritten code
+	context.subscriptions.push(
+		vscode.commands.registerCommand(
+			"rust-compass.explainRefactoring",
+			async (args: { title: string; ruleIds: string[] }) => {
+				const rules = args.ruleIds
+					.map((id) => ruleEngine.getRuleById(id))
+					.filter((rule): rule is Rule => rule !== undefined);
+				const choice = await vscode.window.showInformationMessage(
+					`🦀 ${args.title}`,
+					...rules.map((rule) => `Learn: ${rule.title}`),
+				);
+				const rule = rules.find((r) => `Learn: ${r.title}` === choice);
+				if (rule) {
+					LearnPanel.show(context.extensionUri, rule);
+				}
+			},
+		),
 	);
 
 	// Ask AI - opens GitHub Copilot Chat with context about the Rust pattern
@@ -347,11 +569,21 @@ export function activate(context: vscode.ExtensionContext) {
 		),
 	);
 
+	// Rust files and Cargo.toml both get decorations
+	const updateDecorations = (editor: vscode.TextEditor) => {
+		if (editor.document.languageId === "rust") {
+			decorationProvider.triggerUpdate(editor);
+		} else if (isCargoManifest(editor.document.uri)) {
+			decorationProvider.triggerUpdate(editor);
+			manifestProvider.triggerUpdate(editor);
+		}
+	};
+
 	// Listen for editor changes to update decorations
 	context.subscriptions.push(
 		vscode.window.onDidChangeActiveTextEditor((editor) => {
-			if (editor && editor.document.languageId === "rust") {
-				decorationProvider.triggerUpdate(editor);
+			if (editor) {
+				updateDecorations(editor);
 			}
 		}),
 	);
@@ -359,19 +591,15 @@ export function activate(context: vscode.ExtensionContext) {
 	context.subscriptions.push(
 		vscode.workspace.onDidChangeTextDocument((event) => {
 			const editor = vscode.window.activeTextEditor;
-			if (
-				editor &&
-				event.document === editor.document &&
-				editor.document.languageId === "rust"
-			) {
-				decorationProvider.triggerUpdate(editor);
+			if (editor && event.document === editor.document) {
+				updateDecorations(editor);
 			}
 		}),
 	);
 
 	// Initial decoration update
-	if (vscode.window.activeTextEditor?.document.languageId === "rust") {
-		decorationProvider.triggerUpdate(vscode.window.activeTextEditor);
+	if (vscode.window.activeTextEditor) {
+		updateDecorations(vscode.window.activeTextEditor);
 	}
 
 	// Show welcome message on first activation
@@ -391,6 +619,30 @@ export function activate(context: vscode.ExtensionContext) {
 	}
 }
 
+async function pickFixableRule(ruleEngine: RuleEngine): Promise<string | undefined> {
+	const choice = await vscode.window.showQuickPick(
+		ruleEngine
+			.getAllRules()
+			.filter((rule) => rule.suggestedFix && !rule.editionChange)
+			.map((rule) => ({
+				label: rule.title,
+				description: rule.id,
+				detail: rule.suggestedFix?.description,
+				ruleId: rule.id,
+			})),
+		{ placeHolder: "Select the rule whose fix to apply", matchOnDescription: true },
+	);
+	return choice?.ruleId;
+}
+
+function readRuleSettings(): RuleSettings {
+	const config = vscode.workspace.getConfiguration("rustCompass");
+	return {
+		minConfidence: config.get<number>("minConfidence", DEFAULT_MIN_CONFIDENCE),
+		rules: config.get<Record<string, RuleUserSettings>>("rules", {}),
+	};
+}
+
 function extractErrorCode(diagnostic: vscode.Diagnostic): string | null {
 	if (diagnostic.code) {
 		if (typeof diagnostic.code === "string") {
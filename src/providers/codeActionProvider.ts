@@ -1,66 +1,398 @@
 import * as vscode from "vscode";
-import type { ProjectContext, RuleEngine } from "../rules";
+import {
+	type ErrorStyle,
+	findCallerEdit,
+	findCombinatorRefactorings,
+	findLoopRefactorings,
+	findPropagation,
+	mergeFixes,
+	type ProjectContext,
+	type Refactoring,
+	type ResolvedFix,
+	type Rule,
+	type RuleEngine,
+	type RuleMatch,
+	withoutPlaceholders,
+} from "../rules";
+import type { TeachableFix } from "../services/intentAnalyzer";
 
 export class RustCodeActionProvider implements vscode.CodeActionProvider {
+	/** `source.fixAll.rustCompass`, e.g. for `editor.codeActionsOnSave` */
+	public static readonly fixAllKind = vscode.CodeActionKind.SourceFixAll.append("rustCompass");
+
 	public static readonly providedCodeActionKinds = [
 		vscode.CodeActionKind.QuickFix,
 		vscode.CodeActionKind.Refactor,
+		vscode.CodeActionKind.RefactorRewrite,
+		RustCodeActionProvider.fixAllKind,
 	];
 
 	constructor(
 		private ruleEngine: RuleEngine,
 		private getContext: () => ProjectContext,
+		private getDiagnosticFix?: (
+			document: vscode.TextDocument,
+			diagnostic: vscode.Diagnostic,
+		) => TeachableFix | undefined,
 	) {}
 
 	provideCodeActions(
 		document: vscode.TextDocument,
 		range: vscode.Range | vscode.Selection,
-		_context: vscode.CodeActionContext,
+		context: vscode.CodeActionContext,
 		_token: vscode.CancellationToken,
 	): vscode.ProviderResult<vscode.CodeAction[]> {
-		const actions: vscode.CodeAction[] = [];
+		if (context.only?.contains(RustCodeActionProvider.fixAllKind)) {
+			return this.createFixAllActions(document);
+		}
+
+		const matches = this.ruleEngine.findMatchesAtPosition(
+			document,
+			range.start,
+			this.getContext(),
+		);
+		const match = matches[0];
 
-		const match = this.ruleEngine.findMatchAtPosition(document, range.start, this.getContext());
+		// Actions for the Rust Compass diagnostics the user is looking at come first
+		const actions = context.diagnostics
+			.filter((d) => d.source === "Rust Compass")
+			.flatMap((d) => this.createDiagnosticActions(document, d, matches));
 
-		if (!match || !match.rule.suggestedFix) {
+		if (!context.only || context.only.contains(vscode.CodeActionKind.RefactorRewrite)) {
+			const tree = this.ruleEngine.getSyntaxTree(document);
+			const offset = document.offsetAt(range.start);
+			for (const refactoring of [
+				...findLoopRefactorings(tree, offset),
+				...findCombinatorRefactorings(tree, offset),
+			]) {
+				actions.push(this.createRefactorAction(document, refactoring));
+			}
+			// The error type depends on the project's dependencies, so the command works it out
+			if (findPropagation(tree, offset, "box")) {
+				const action = new vscode.CodeAction(
+					"Rust Compass: Propagate with ?",
+					vscode.CodeActionKind.RefactorRewrite,
+				);
+				action.command = {
+					command: "rust-compass.propagateError",
+					title: "Propagate with ?",
+					arguments: [{ uri: document.uri.toString(), offset }],
+				};
+				actions.push(action);
+			}
+		}
+
+		if (!match) {
 			return actions;
 		}
 
-		const fix = match.rule.suggestedFix;
+		if (match.rule.suggestedFix) {
+			const fix = this.ruleEngine.resolveFix(document, match);
+			if (fix) {
+				const action = new vscode.CodeAction(
+					`Rust Compass: ${fix.description}`,
+					vscode.CodeActionKind.QuickFix,
+				);
+				action.edit = toWorkspaceEdit(document, fix);
+				action.isPreferred = true;
+				actions.push(action);
+			}
 
-		// Create action
-		const action = new vscode.CodeAction(
-			`Rust Compass: ${fix.description}`,
-			vscode.CodeActionKind.QuickFix,
-		);
+			// Add "Learn more" action
+			const learnAction = new vscode.CodeAction(
+				`📖 Learn about ${match.rule.rustTerm}`,
+				vscode.CodeActionKind.Empty,
+			);
+			learnAction.command = {
+				command: "rust-compass.showRuleDetails",
+				title: "Show Details",
+				arguments: [{ ruleId: match.rule.id }],
+			};
+			actions.push(learnAction);
+		}
 
-		// Find the text to replace (look backwards from match)
-		const lineText = document.lineAt(match.range.line).text;
-		const beforeIndex = lineText.indexOf(fix.before);
+		// Every rule at this spot can be silenced with a comment
+		for (const { rule } of matches) {
+			actions.push(
+				this.createLineSuppressionAction(document, rule, match.range.line),
+				this.createFileSuppressionAction(document, rule),
+			);
+		}
 
-		if (beforeIndex !== -1) {
-			const startPos = new vscode.Position(match.range.line, beforeIndex);
-			const endPos = new vscode.Position(match.range.line, beforeIndex + fix.before.length);
+		return actions;
+	}
+
+	/**
+	 * The teachable moment's fix (if it has one), "Learn more" and "Ignore here" for a diagnostic
+	 */
+	private createDiagnosticActions(
+		document: vscode.TextDocument,
+		diagnostic: vscode.Diagnostic,
+		matches: RuleMatch[],
+	): vscode.CodeAction[] {
+		const ruleId = typeof diagnostic.code === "object" ? String(diagnostic.code.value) : "";
+		const rule = this.ruleEngine.getRuleById(ruleId);
+		if (!rule) {
+			return [];
+		}
+		const actions: vscode.CodeAction[] = [];
 
+		const fix = this.getDiagnosticFix?.(document, diagnostic);
+		if (fix) {
+			const action = new vscode.CodeAction(
+				`Rust Compass: ${fix.title}`,
+				vscode.CodeActionKind.QuickFix,
+			);
 			action.edit = new vscode.WorkspaceEdit();
-			action.edit.replace(document.uri, new vscode.Range(startPos, endPos), fix.after);
+			action.edit.set(document.uri, fix.edits);
+			action.diagnostics = [diagnostic];
 			action.isPreferred = true;
-
 			actions.push(action);
 		}
 
-		// Add "Learn more" action
 		const learnAction = new vscode.CodeAction(
-			`📖 Learn about ${match.rule.rustTerm}`,
-			vscode.CodeActionKind.Empty,
+			`📖 Learn about ${rule.rustTerm}`,
+			vscode.CodeActionKind.QuickFix,
 		);
 		learnAction.command = {
-			command: "rust-compass.showRuleDetails",
-			title: "Show Details",
-			arguments: [{ ruleId: match.rule.id }],
+			command: "rust-compass.learnMore",
+			title: "Learn More",
+			arguments: [{ ruleId: rule.id }],
 		};
+		learnAction.diagnostics = [diagnostic];
 		actions.push(learnAction);
 
+		// The match-based actions already offer this when the rule matches on the same line
+		const line = diagnostic.range.start.line;
+		if (!matches.some((m) => m.rule.id === rule.id && m.range.line === line)) {
+			const ignoreAction = this.createLineSuppressionAction(document, rule, line);
+			ignoreAction.diagnostics = [diagnostic];
+			actions.push(ignoreAction);
+		}
+
 		return actions;
 	}
+
+	/**
+	 * A refactoring as a `refactor.rewrite` action that afterwards points to the rules
+	 * explaining the new code
+	 */
+	private createRefactorAction(
+		document: vscode.TextDocument,
+		refactoring: Refactoring,
+	): vscode.CodeAction {
+		const { title, ruleIds, edits } = refactoring;
+		const action = new vscode.CodeAction(
+			`Rust Compass: ${title}`,
+			vscode.CodeActionKind.RefactorRewrite,
+		);
+		action.edit = toWorkspaceEdit(document, { description: title, snippet: false, edits });
+		action.command = {
+			command: "rust-compass.explainRefactoring",
+			title: "Explain Refactoring",
+			arguments: [{ title, ruleIds }],
+		};
+		return action;
+	}
+
+	/**
+	 * Apply the fixes of every rule opted in with `"fixAll": true` in `rustCompass.rules`
+	 */
+	private createFixAllActions(document: vscode.TextDocument): vscode.CodeAction[] {
+		const { edit, fixes } = this.createFixAllEdit(
+			[document],
+			(rule) => this.ruleEngine.getRuleSettings(rule.id).fixAll === true,
+		);
+		if (fixes === 0) {
+			return [];
+		}
+		const action = new vscode.CodeAction(
+			`Rust Compass: Fix all (${fixes} ${fixes === 1 ? "fix" : "fixes"})`,
+			RustCodeActionProvider.fixAllKind,
+		);
+		action.edit = edit;
+		return [action];
+	}
+
+	/**
+	 * One edit applying the suggested fix of every match of the included rules in these
+	 * documents. Snippet placeholders get their defaults; fixes that would clash are left out.
+	 * With `preview`, each change needs confirming in the refactor preview.
+	 */
+	public createFixAllEdit(
+		documents: readonly vscode.TextDocument[],
+		include: (rule: Rule) => boolean,
+		preview = false,
+	): { edit: vscode.WorkspaceEdit; fixes: number } {
+		const edit = new vscode.WorkspaceEdit();
+		let fixes = 0;
+
+		for (const document of documents) {
+			const resolved = this.ruleEngine
+				.findMatches(document, this.getContext())
+				.filter((match) => match.rule.suggestedFix && include(match.rule))
+				.map((match) => this.ruleEngine.resolveFix(document, match))
+				.filter((fix): fix is ResolvedFix => fix !== undefined)
+				.map(withoutPlaceholders);
+
+			for (const fix of mergeFixes(resolved)) {
+				const metadata = preview
+					? { label: fix.description, needsConfirmation: true }
+					: undefined;
+				for (const e of fix.edits) {
+					const range = new vscode.Range(
+						document.positionAt(e.start),
+						document.positionAt(e.end),
+					);
+					edit.replace(document.uri, range, e.text, metadata);
+				}
+				fixes++;
+			}
+		}
+
+		return { edit, fixes };
+	}
+
+	/**
+	 * Replace the `.unwrap()` / `.expect(..)` at an offset with `?`. When that changes the
+	 * function's return type, calls found through the reference provider (rust-analyzer)
+	 * get `?` or `.unwrap()` appended.
+	 */
+	public async createPropagationEdit(
+		document: vscode.TextDocument,
+		offset: number,
+		style: ErrorStyle,
+	): Promise<vscode.WorkspaceEdit | undefined> {
+		const propagation = findPropagation(this.ruleEngine.getSyntaxTree(document), offset, style);
+		if (!propagation) {
+			return undefined;
+		}
+		const edit = toWorkspaceEdit(document, {
+			description: propagation.title,
+			snippet: false,
+			edits: propagation.edits,
+		});
+		if (propagation.changedFunction === undefined) {
+			return edit;
+		}
+
+		const locations =
+			(await vscode.commands.executeCommand<vscode.Location[] | undefined>(
+				"vscode.executeReferenceProvider",
+				document.uri,
+				document.positionAt(propagation.changedFunction),
+			)) ?? [];
+		const { start, end } = propagation.functionSpan;
+		for (const location of locations) {
+			const caller = await vscode.workspace.openTextDocument(location.uri);
+			const callerOffset = caller.offsetAt(location.range.start);
+			// Recursive calls already return what the new signature does
+			const sameFile = caller.uri.toString() === document.uri.toString();
+			if (sameFile && callerOffset >= start && callerOffset < end) {
+				continue;
+			}
+			const callerEdit = findCallerEdit(
+				this.ruleEngine.getSyntaxTree(caller),
+				callerOffset,
+				style,
+			);
+			if (callerEdit) {
+				edit.insert(caller.uri, caller.positionAt(callerEdit.start), callerEdit.text);
+			}
+		}
+		return edit;
+	}
+
+	/**
+	 * "Ignore on this line": inserts a `compass-ignore-next-line` comment above the line
+	 */
+	private createLineSuppressionAction(
+		document: vscode.TextDocument,
+		rule: Rule,
+		line: number,
+	): vscode.CodeAction {
+		const action = new vscode.CodeAction(
+			`Rust Compass: Ignore "${rule.title}" on this line`,
+			vscode.CodeActionKind.QuickFix,
+		);
+		const indent = document.lineAt(line).text.match(/^\s*/)?.[0] ?? "";
+		action.edit = new vscode.WorkspaceEdit();
+		action.edit.insert(
+			document.uri,
+			new vscode.Position(line, 0),
+			`${indent}// compass-ignore-next-line ${rule.id}\n`,
+		);
+		return action;
+	}
+
+	/**
+	 * "Ignore in this file": adds the rule to the file's `compass-disable` comment
+	 */
+	private createFileSuppressionAction(
+		document: vscode.TextDocument,
+		rule: Rule,
+	): vscode.CodeAction {
+		const action = new vscode.CodeAction(
+			`Rust Compass: Ignore "${rule.title}" in this file`,
+			vscode.CodeActionKind.QuickFix,
+		);
+		action.edit = new vscode.WorkspaceEdit();
+		const existing = this.findDisableDirective(document);
+		if (existing) {
+			// Add the id to the directive that is already there
+			action.edit.insert(document.uri, existing, ` ${rule.id}`);
+		} else {
+			action.edit.insert(
+				document.uri,
+				new vscode.Position(0, 0),
+				`//! compass-disable ${rule.id}\n`,
+			);
+		}
+		return action;
+	}
+
+	/**
+	 * Where to append a rule id to an existing `compass-disable` comment (before any reason)
+	 */
+	private findDisableDirective(document: vscode.TextDocument): vscode.Position | undefined {
+		for (let line = 0; line < document.lineCount; line++) {
+			const text = document.lineAt(line).text;
+			const directive = text.match(/^\s*\/\/[/!]?\s*compass-disable\b/);
+			if (directive) {
+				const reason = text.indexOf(" --", directive[0].length);
+				return new vscode.Position(line, reason === -1 ? text.trimEnd().length : reason);
+			}
+		}
+		return undefined;
+	}
+}
+
+/**
+ * Apply a resolved fix to a document, as snippet edits when the fix has tab stops
+ */
+export function toWorkspaceEdit(
+	document: vscode.TextDocument,
+	fix: ResolvedFix,
+): vscode.WorkspaceEdit {
+	const edit = new vscode.WorkspaceEdit();
+	const range = (start: number, end: number) =>
+		new vscode.Range(document.positionAt(start), document.positionAt(end));
+
+	if (fix.snippet) {
+		edit.set(
+			document.uri,
+			fix.edits.map(
+				(e) =>
+					new vscode.SnippetTextEdit(
+						range(e.start, e.end),
+						new vscode.SnippetString(e.text),
+					),
+			),
+		);
+	} else {
+		for (const e of fix.edits) {
+			edit.replace(document.uri, range(e.start, e.end), e.text);
+		}
+	}
+	return edit;
 }
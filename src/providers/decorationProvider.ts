@@ -1,5 +1,5 @@
 import * as vscode from "vscode";
-import type { ProjectContext, RuleEngine } from "../rules";
+import { isCargoManifest, type ProjectContext, type RuleEngine } from "../rules";
 
 export class RustDecorationProvider {
 	private decorationType: vscode.TextEditorDecorationType;
@@ -91,7 +91,7 @@ export class RustDecorationProvider {
 	}
 
 	private updateDecorations(editor: vscode.TextEditor): void {
-		if (editor.document.languageId !== "rust") {
+		if (editor.document.languageId !== "rust" && !isCargoManifest(editor.document.uri)) {
 			return;
 		}
 
@@ -103,9 +103,9 @@ export class RustDecorationProvider {
 
 		const matches = this.ruleEngine.findMatches(editor.document, this.getContext());
 
-		// Filter by confidence threshold AND not dismissed
+		// Filter by confidence threshold / showDecoration AND not dismissed
 		const visibleMatches = matches.filter(
-			(m) => m.rule.confidence >= 0.7 && !this.dismissedRules.has(m.rule.id),
+			(m) => this.ruleEngine.shouldDecorate(m.rule) && !this.dismissedRules.has(m.rule.id),
 		);
 
 		const decorations: vscode.DecorationOptions[] = visibleMatches.map((match) => {
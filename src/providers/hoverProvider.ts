@@ -1,10 +1,28 @@
 import * as vscode from "vscode";
-import type { ProjectContext, Rule, RuleEngine } from "../rules";
-import { CargoAnalyzerService, type DetectedDependencies, PatternTracker } from "../services";
+import {
+	type DependencyRequirement,
+	hasDependency,
+	type ProjectContext,
+	type ReceiverType,
+	type Rule,
+	type RuleEngine,
+	type RuleMatch,
+	unmetDependency,
+} from "../rules";
+import {
+	CargoAnalyzerService,
+	type DetectedDependencies,
+	describeProfile,
+	PatternTracker,
+	type ProjectProfile,
+	ProjectProfileService,
+	RustAnalyzerService,
+} from "../services";
 
 interface AlternativeOption {
 	method: string;
 	useWhen: string;
+	requires?: DependencyRequirement; // Only shown when the crate uses this dependency version
 }
 
 interface DecisionGuide {
@@ -22,6 +40,10 @@ export class RustHoverProvider implements vscode.HoverProvider {
 	public readonly onRuleHovered = this._onRuleHovered.event;
 	private cargoAnalyzer: CargoAnalyzerService;
 	private patternTracker: PatternTracker;
+	private rustAnalyzer: RustAnalyzerService;
+	private projectProfiles: ProjectProfileService;
+	/** `uri#offset` of the receiver hovers we're asking rust-analyzer for */
+	private resolvingReceivers = new Set<string>();
 
 	constructor(
 		private ruleEngine: RuleEngine,
@@ -29,6 +51,8 @@ export class RustHoverProvider implements vscode.HoverProvider {
 	) {
 		this.cargoAnalyzer = CargoAnalyzerService.getInstance();
 		this.patternTracker = PatternTracker.getInstance();
+		this.rustAnalyzer = RustAnalyzerService.getInstance();
+		this.projectProfiles = ProjectProfileService.getInstance();
 	}
 
 	async provideHover(
@@ -36,22 +60,32 @@ export class RustHoverProvider implements vscode.HoverProvider {
 		position: vscode.Position,
 		_token: vscode.CancellationToken,
 	): Promise<vscode.Hover | null> {
-		const match = this.ruleEngine.findMatchAtPosition(document, position, this.getContext());
-
-		if (!match) {
+		// Hovers we request from rust-analyzer while resolving a receiver come back through here
+		const key = `${document.uri.toString()}#${document.offsetAt(position)}`;
+		if (this.resolvingReceivers.has(key)) {
 			return null;
 		}
 
-		const { rule } = match;
+		let matches = this.ruleEngine.findMatchesAtPosition(document, position, this.getContext());
+		if (matches.length === 0) {
+			return null;
+		}
+		if (matches.length > 1) {
+			matches = await this.narrowByReceiverType(document, matches);
+		}
 
 		// Emit event to update the panel automatically
-		this._onRuleHovered.fire(rule);
+		for (const { rule } of matches) {
+			this._onRuleHovered.fire(rule);
+		}
 
-		// Check for loop context synchronously (simple heuristic)
-		const inLoop = this.isInsideLoopSync(document, position);
+		// Loop context from the parsed syntax tree
+		const inLoop = this.ruleEngine.getScopeAt(document, position).inLoop;
 
-		// Get dependencies for smart hints
-		const deps = await this.cargoAnalyzer.getDependencies();
+		// Dependencies of the crate this file belongs to, for smart hints
+		const deps = await this.cargoAnalyzer.getDependenciesFor(document.uri);
+		// What kind of crate it is, for AI prompts
+		const profile = await this.projectProfiles.getProfile(document.uri);
 
 		// Build the decision guide hover
 		const content = new vscode.MarkdownString();
@@ -61,6 +95,43 @@ export class RustHoverProvider implements vscode.HoverProvider {
 		// Header
 		content.appendMarkdown(`**🦀 Rust Compass**\n\n`);
 
+		// Several rules cover this spot: one section each, most specific first
+		matches.forEach(({ rule }, i) => {
+			if (matches.length > 1) {
+				content.appendMarkdown(`${i > 0 ? "\n\n---\n\n" : ""}#### ${rule.title}\n\n`);
+			}
+			this.appendRuleSection(content, rule, document, position, inLoop, deps, profile);
+		});
+
+		const range = new vscode.Range(
+			document.positionAt(Math.min(...matches.map((m) => m.range.start))),
+			document.positionAt(Math.max(...matches.map((m) => m.range.end))),
+		);
+
+		return new vscode.Hover(content, range);
+	}
+
+	/**
+	 * Decision guide (or plain explanation) plus action links for one rule
+	 */
+	private appendRuleSection(
+		content: vscode.MarkdownString,
+		rule: Rule,
+		document: vscode.TextDocument,
+		position: vscode.Position,
+		inLoop: boolean,
+		deps: DetectedDependencies,
+		profile: ProjectProfile,
+	): void {
+		const projectDeps = this.cargoAnalyzer.getProjectDependencies(document.uri);
+		// The code won't compile without something from Cargo.toml, e.g. a crate feature
+		if (rule.expectsDependency && projectDeps.crates.length > 0) {
+			const problem = unmetDependency(projectDeps, rule.expectsDependency);
+			if (problem) {
+				content.appendMarkdown(`⚠️ ${problem}\n\n`);
+			}
+		}
+
 		// Get the decision guide for this pattern
 		const guide = this.getDecisionGuide(rule.id, inLoop, deps);
 
@@ -73,6 +144,9 @@ export class RustHoverProvider implements vscode.HoverProvider {
 			if (guide.depAware) {
 				alternatives = [...alternatives, ...guide.depAware(deps)];
 			}
+			alternatives = alternatives.filter(
+				(alt) => !alt.requires || hasDependency(projectDeps, alt.requires),
+			);
 
 			for (const alt of alternatives) {
 				content.appendMarkdown(`\`${alt.method}\` → ${alt.useWhen}\n\n`);
@@ -96,7 +170,7 @@ export class RustHoverProvider implements vscode.HoverProvider {
 
 			// Ask AI with context about this specific pattern and code
 			const aiPrompt = encodeURIComponent(
-				this.buildAIPrompt(rule, guide, document, position, deps),
+				this.buildAIPrompt(rule, guide, document, position, deps, profile),
 			);
 			links.push(
 				`[🤖 Ask AI](command:rust-compass.askAI?${encodeURIComponent(JSON.stringify({ prompt: aiPrompt, ruleId: rule.id }))})`,
@@ -119,20 +193,73 @@ export class RustHoverProvider implements vscode.HoverProvider {
 				`[📚 Learn more](command:rust-compass.learnMore?${learnMoreArgs}) · `,
 			);
 
+			const about = describeProfile(profile);
 			const aiPrompt = encodeURIComponent(
-				`Explain this Rust pattern and when to use it: ${rule.rustTerm}`,
+				`Explain this Rust pattern and when to use it: ${rule.rustTerm}${about ? `\n\n${about}` : ""}`,
 			);
 			content.appendMarkdown(
 				`[🤖 Ask AI](command:rust-compass.askAI?${encodeURIComponent(JSON.stringify({ prompt: aiPrompt, ruleId: rule.id }))})`,
 			);
 		}
+	}
 
-		const range = new vscode.Range(
-			document.positionAt(match.range.start),
-			document.positionAt(match.range.end),
-		);
+	/**
+	 * Drop rules whose `receiverTypes` don't fit the receiver's type according to
+	 * rust-analyzer. Keeps every match when the type is unknown or nothing would be left.
+	 */
+	private async narrowByReceiverType(
+		document: vscode.TextDocument,
+		matches: RuleMatch[],
+	): Promise<RuleMatch[]> {
+		if (!matches.some((m) => m.rule.receiverTypes)) {
+			return matches;
+		}
 
-		return new vscode.Hover(content, range);
+		const receiverTypes = new Map<number, ReceiverType | undefined>();
+		const narrowed: RuleMatch[] = [];
+		for (const match of matches) {
+			const wanted = match.rule.receiverTypes;
+			if (!wanted) {
+				narrowed.push(match);
+				continue;
+			}
+			const start = match.range.start;
+			if (!receiverTypes.has(start)) {
+				receiverTypes.set(start, await this.resolveReceiverType(document, start));
+			}
+			const type = receiverTypes.get(start);
+			if (!type || wanted.includes(type)) {
+				narrowed.push(match);
+			}
+		}
+
+		return narrowed.length > 0 ? narrowed : matches;
+	}
+
+	/**
+	 * Type of the receiver of the method call starting (at its `.`) at an offset
+	 */
+	private async resolveReceiverType(
+		document: vscode.TextDocument,
+		callStart: number,
+	): Promise<ReceiverType | undefined> {
+		const call = this.ruleEngine
+			.getSyntaxTree(document)
+			.nodes.find((n) => n.kind === "method_call" && n.start === callStart);
+		if (!call?.receiverName) {
+			return undefined;
+		}
+
+		const key = `${document.uri.toString()}#${call.receiverName.start}`;
+		this.resolvingReceivers.add(key);
+		try {
+			return await this.rustAnalyzer.getReceiverType(
+				document,
+				document.positionAt(call.receiverName.start),
+			);
+		} finally {
+			this.resolvingReceivers.delete(key);
+		}
 	}
 
 	dispose() {
@@ -148,6 +275,7 @@ export class RustHoverProvider implements vscode.HoverProvider {
 		document: vscode.TextDocument,
 		position: vscode.Position,
 		deps: DetectedDependencies,
+		profile: ProjectProfile,
 	): string {
 		// Get surrounding code for context (5 lines before and after)
 		const startLine = Math.max(0, position.line - 5);
@@ -159,8 +287,8 @@ export class RustHoverProvider implements vscode.HoverProvider {
 			),
 		);
 
-		// Build dependency context
-		const depContext = this.buildDependencyContext(deps);
+		// Build project and dependency context
+		const depContext = this.buildDependencyContext(deps, profile);
 
 		return `I'm learning Rust and working on this code:
 
@@ -177,9 +305,10 @@ Based on my specific code and project setup, which approach would be best and wh
 	}
 
 	/**
-	 * Build dependency context string for AI prompt
+	 * Build the project and dependency context string for AI prompt
 	 */
-	private buildDependencyContext(deps: DetectedDependencies): string {
+	private buildDependencyContext(deps: DetectedDependencies, profile: ProjectProfile): string {
+		const about = describeProfile(profile);
 		const parts: string[] = [];
 
 		if (deps.async.length > 0) {
@@ -198,11 +327,12 @@ Based on my specific code and project setup, which approach would be best and wh
 			parts.push(`parsing: ${deps.parsing.join(", ")}`);
 		}
 
-		if (parts.length === 0) {
+		const uses = parts.length > 0 ? `My project uses: ${parts.join(", ")}.` : "";
+		if (!about && !uses) {
 			return "";
 		}
 
-		return `\nMy project uses: ${parts.join(", ")}.\n`;
+		return `\n${[about, uses].filter(Boolean).join(" ")}\n`;
 	}
 
 	/**
@@ -371,6 +501,11 @@ Based on my specific code and project setup, which approach would be best and wh
 							method: "nom combinators",
 							useWhen: "you have nom — use its parser combinators instead",
 						});
+						extra.push({
+							method: "(a, b).parse(input)",
+							useWhen: "nom 8 — combinators return `Parser`s, run them with .parse()",
+							requires: { name: "nom", version: ">=8" },
+						});
 					}
 					if (d.parsing.includes("pest")) {
 						extra.push({
@@ -428,22 +563,4 @@ Based on my specific code and project setup, which approach would be best and wh
 
 		return guides[ruleId] || null;
 	}
-
-	/**
-	 * Simple synchronous loop detection using text analysis
-	 */
-	private isInsideLoopSync(document: vscode.TextDocument, position: vscode.Position): boolean {
-		const startLine = Math.max(0, position.line - 20);
-		const text = document.getText(
-			new vscode.Range(new vscode.Position(startLine, 0), position),
-		);
-
-		// Count loop openings vs closings
-		const loopMatches = text.match(/\b(for|while|loop)\b[^{]*\{/g) || [];
-		const openBraces = (text.match(/\{/g) || []).length;
-		const closeBraces = (text.match(/\}/g) || []).length;
-
-		// Simple heuristic: if there's a loop and more opens than closes
-		return loopMatches.length > 0 && openBraces > closeBraces;
-	}
 }
@@ -1,4 +1,5 @@
 export { RustCodeActionProvider } from "./codeActionProvider";
+export { CargoManifestProvider } from "./manifestProvider";
 export { RustDecorationProvider } from "./decorationProvider";
 export { RustHoverProvider } from "./hoverProvider";
 export { SmartDiagnosticProvider } from "./smartDiagnosticProvider";
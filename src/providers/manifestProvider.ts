@@ -0,0 +1,245 @@
+import * as vscode from "vscode";
+import { isCargoManifest, type ProjectContext, type Rule, type RuleEngine } from "../rules";
+import {
+	CargoAnalyzerService,
+	type CargoDependency,
+	type CargoManifest,
+	type DependencyLocation,
+	type DetectedDependencies,
+	describeProfile,
+	enabledFeatures,
+	locateDependencies,
+	parseManifest,
+	ProjectProfileService,
+} from "../services";
+
+const CATEGORY_LABELS: Record<keyof DetectedDependencies, string> = {
+	async: "async",
+	web: "web",
+	serialization: "serialization",
+	cli: "CLI",
+	parsing: "parsing",
+	error: "errors",
+	database: "database",
+	testing: "testing",
+	other: "",
+};
+
+/**
+ * Hovers and inline hints for Cargo.toml: what each dependency is and which version and
+ * features Cargo resolved, plus the `cargo-toml` rules that match
+ */
+export class CargoManifestProvider implements vscode.HoverProvider, vscode.Disposable {
+	// Event emitter for when a rule is hovered
+	private _onRuleHovered = new vscode.EventEmitter<Rule>();
+	public readonly onRuleHovered = this._onRuleHovered.event;
+	private cargoAnalyzer: CargoAnalyzerService;
+	private projectProfiles: ProjectProfileService;
+	private decorationType: vscode.TextEditorDecorationType;
+	private timeout: NodeJS.Timeout | undefined;
+	private disposables: vscode.Disposable[] = [];
+
+	constructor(
+		private ruleEngine: RuleEngine,
+		private getContext: () => ProjectContext,
+	) {
+		this.cargoAnalyzer = CargoAnalyzerService.getInstance();
+		this.projectProfiles = ProjectProfileService.getInstance();
+		this.decorationType = vscode.window.createTextEditorDecorationType({
+			after: {
+				margin: "0 0 0 2em",
+				color: new vscode.ThemeColor("editorCodeLens.foreground"),
+			},
+		});
+
+		// Resolved versions arrive after a rescan, e.g. once Cargo.lock changes
+		this.disposables.push(
+			this.cargoAnalyzer.onDependenciesChanged(() => {
+				const editor = vscode.window.activeTextEditor;
+				if (editor) {
+					this.triggerUpdate(editor);
+				}
+			}),
+		);
+	}
+
+	async provideHover(
+		document: vscode.TextDocument,
+		position: vscode.Position,
+		_token: vscode.CancellationToken,
+	): Promise<vscode.Hover | null> {
+		const context = this.getContext();
+		const matches = this.ruleEngine.findMatchesAtPosition(document, position, context);
+		const location = locateDependencies(document.getText()).find(
+			(l) => position.line >= l.startLine && position.line <= l.endLine,
+		);
+		const dep =
+			location &&
+			(await this.getDependency(document, location, () => parseManifest(document.getText())));
+		if (matches.length === 0 && !dep) {
+			return null;
+		}
+
+		const content = new vscode.MarkdownString();
+		// Only our own command links may run; the manifest's values are shown as plain text
+		content.isTrusted = { enabledCommands: ["rust-compass.learnMore", "rust-compass.askAI"] };
+		content.appendMarkdown(`**🦀 Rust Compass**\n\n`);
+
+		if (dep) {
+			this.appendDependencySection(content, dep);
+		}
+
+		// What kind of crate it is, for AI prompts
+		const about = describeProfile(await this.projectProfiles.getProfile(document.uri));
+		for (const { rule } of matches) {
+			this._onRuleHovered.fire(rule);
+			content.appendMarkdown(`\n\n---\n\n#### ${rule.title}\n\n${rule.explanation}\n\n`);
+
+			const learnMoreArgs = encodeURIComponent(JSON.stringify({ ruleId: rule.id }));
+			const aiPrompt = encodeURIComponent(
+				`Explain this Cargo.toml setting and when to change it: ${rule.rustTerm}${about ? `\n\n${about}` : ""}`,
+			);
+			content.appendMarkdown(
+				`[📚 Learn more](command:rust-compass.learnMore?${learnMoreArgs}) · [🤖 Ask AI](command:rust-compass.askAI?${encodeURIComponent(JSON.stringify({ prompt: aiPrompt, ruleId: rule.id }))})`,
+			);
+		}
+
+		return new vscode.Hover(content);
+	}
+
+	/**
+	 * Category, description, resolved version and features of a dependency. Everything read
+	 * from Cargo.toml, Cargo.lock or cargo metadata is appended as escaped text.
+	 */
+	private appendDependencySection(content: vscode.MarkdownString, dep: CargoDependency): void {
+		const appendLine = (label: string, value: string) => {
+			content.appendMarkdown(label);
+			content.appendText(value);
+			content.appendMarkdown("\n\n");
+		};
+
+		const category = CATEGORY_LABELS[this.cargoAnalyzer.categoryOf(dep.package)];
+		content.appendMarkdown("**");
+		content.appendText(dep.package);
+		content.appendMarkdown(`**${category ? ` · ${category}` : ""}\n\n`);
+
+		if (dep.description) {
+			appendLine("", dep.description);
+		} else {
+			const description = this.cargoAnalyzer.describeDependency(dep);
+			if (description) {
+				content.appendMarkdown(`${description}\n\n`);
+			}
+		}
+
+		if (dep.version) {
+			const resolved = dep.resolvedVersion ? ` → ${dep.resolvedVersion}` : "";
+			appendLine("Version: ", `${dep.version}${resolved}`);
+		}
+		const features = enabledFeatures(dep);
+		if (features.length > 0) {
+			appendLine("Features: ", features.join(", "));
+		}
+		if (dep.path) {
+			appendLine("Path: ", dep.path);
+		} else if (dep.git) {
+			appendLine("Git: ", dep.git);
+		} else {
+			const crate = encodeURIComponent(dep.package);
+			const version = encodeURIComponent(dep.resolvedVersion ?? "latest");
+			content.appendMarkdown(`[📖 docs.rs](https://docs.rs/${crate}/${version})`);
+		}
+	}
+
+	/**
+	 * The dependency at a location, from the workspace scan when it covers this manifest and
+	 * from the text alone otherwise, e.g. for a Cargo.toml outside the workspace.
+	 * `parsed` gives the document's manifest, so callers looking up many locations parse it once.
+	 */
+	private async getDependency(
+		document: vscode.TextDocument,
+		location: DependencyLocation,
+		parsed: () => CargoManifest,
+	): Promise<CargoDependency | undefined> {
+		if (!document.isDirty) {
+			const scanned = await this.cargoAnalyzer.getDeclaredDependency(document.uri, location);
+			if (scanned) {
+				return scanned;
+			}
+		}
+		const manifest = parsed();
+		const declared = location.workspaceTable
+			? manifest.workspaceDependencies
+			: manifest.dependencies;
+		return declared.find(
+			(dep) =>
+				dep.name === location.name &&
+				dep.kind === location.kind &&
+				dep.target === location.target,
+		);
+	}
+
+	public triggerUpdate(editor: vscode.TextEditor): void {
+		if (this.timeout) {
+			clearTimeout(this.timeout);
+		}
+		this.timeout = setTimeout(() => this.updateDecorations(editor), 300);
+	}
+
+	/**
+	 * Resolved version and category after each dependency, e.g. `1.38.0 · async`
+	 */
+	private async updateDecorations(editor: vscode.TextEditor): Promise<void> {
+		const document = editor.document;
+		if (!isCargoManifest(document.uri)) {
+			return;
+		}
+
+		const config = vscode.workspace.getConfiguration("rustCompass");
+		if (!config.get("enabled") || !config.get("showDecorations")) {
+			editor.setDecorations(this.decorationType, []);
+			return;
+		}
+
+		const decorations: vscode.DecorationOptions[] = [];
+		const text = document.getText();
+		let manifest: CargoManifest | undefined;
+		const parsed = () => {
+			if (!manifest) {
+				manifest = parseManifest(text);
+			}
+			return manifest;
+		};
+		for (const location of locateDependencies(text)) {
+			const dep = await this.getDependency(document, location, parsed);
+			if (!dep) {
+				continue;
+			}
+			const category = CATEGORY_LABELS[this.cargoAnalyzer.categoryOf(dep.package)];
+			const hint = [dep.resolvedVersion, category].filter(Boolean).join(" · ");
+			if (hint) {
+				const line = document.lineAt(location.startLine);
+				decorations.push({
+					range: new vscode.Range(line.range.end, line.range.end),
+					renderOptions: { after: { contentText: hint } },
+				});
+			}
+		}
+
+		// The file may have been closed while the scan was awaited
+		if (!document.isClosed) {
+			editor.setDecorations(this.decorationType, decorations);
+		}
+	}
+
+	public dispose(): void {
+		this._onRuleHovered.dispose();
+		this.decorationType.dispose();
+		if (this.timeout) {
+			clearTimeout(this.timeout);
+		}
+		for (const disposable of this.disposables) {
+			disposable.dispose();
+		}
+	}
+}
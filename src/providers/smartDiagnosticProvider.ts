@@ -1,6 +1,15 @@
 import * as vscode from "vscode";
-import type { RuleEngine } from "../rules";
-import { intentAnalyzer } from "../services/intentAnalyzer";
+import {
+	type ProjectContext,
+	type RuleDiagnosticSeverity,
+	type RuleEngine,
+	type RuleMatch,
+	normalizeCrateName,
+	unmetDependency,
+} from "../rules";
+import { CargoAnalyzerService } from "../services/cargoAnalyzer";
+import { intentAnalyzer, type TeachableFix } from "../services/intentAnalyzer";
+import { ProjectProfileService } from "../services/projectProfile";
 
 /**
  * Provides diagnostic squiggly lines for teachable moments
@@ -10,7 +19,12 @@ export class SmartDiagnosticProvider implements vscode.Disposable {
 	private diagnosticCollection: vscode.DiagnosticCollection;
 	private disposables: vscode.Disposable[] = [];
 	private debounceTimer: NodeJS.Timeout | null = null;
-	constructor(_ruleEngine: RuleEngine) {
+	// Fixes of the published teachable moments, per document URI
+	private fixes = new Map<string, { diagnostic: vscode.Diagnostic; fix: TeachableFix }[]>();
+	constructor(
+		private ruleEngine: RuleEngine,
+		private getContext: () => ProjectContext,
+	) {
 		this.diagnosticCollection = vscode.languages.createDiagnosticCollection("rust-compass");
 
 		// Analyze on document change (debounced)
@@ -44,6 +58,7 @@ export class SmartDiagnosticProvider implements vscode.Disposable {
 		this.disposables.push(
 			vscode.workspace.onDidCloseTextDocument((doc) => {
 				this.diagnosticCollection.delete(doc.uri);
+				this.fixes.delete(doc.uri.toString());
 			}),
 		);
 
@@ -64,16 +79,36 @@ export class SmartDiagnosticProvider implements vscode.Disposable {
 	}
 
 	private async analyzeDocument(document: vscode.TextDocument) {
-		const moments = intentAnalyzer.findTeachableMoments(document);
+		// What kind of crate this is decides which moments are worth pointing out
+		const profile = await ProjectProfileService.getInstance().getProfile(document.uri);
+		const moments = intentAnalyzer.findTeachableMoments(
+			document,
+			(position) => this.ruleEngine.getScopeAt(document, position),
+			profile,
+		);
+		const suppressions = this.ruleEngine.getSuppressions(document);
 		const diagnostics: vscode.Diagnostic[] = [];
+		const fixes: { diagnostic: vscode.Diagnostic; fix: TeachableFix }[] = [];
 
 		for (const moment of moments) {
+			if (suppressions.isSuppressed(moment.ruleId, moment.range.start.line)) {
+				continue;
+			}
+
+			// The user can re-grade or hide a rule's teachable moments
+			const configured = this.ruleEngine.getRuleSettings(moment.ruleId).diagnosticSeverity;
+			if (configured === "none") {
+				continue;
+			}
+
 			const diagnostic = new vscode.Diagnostic(
 				moment.range,
 				`💡 ${moment.suggestion}`,
-				moment.severity === "hint"
-					? vscode.DiagnosticSeverity.Hint
-					: vscode.DiagnosticSeverity.Information,
+				configured
+					? toDiagnosticSeverity(configured)
+					: moment.severity === "hint"
+						? vscode.DiagnosticSeverity.Hint
+						: vscode.DiagnosticSeverity.Information,
 			);
 
 			diagnostic.source = "Rust Compass";
@@ -96,9 +131,137 @@ export class SmartDiagnosticProvider implements vscode.Disposable {
 			diagnostic.tags = [vscode.DiagnosticTag.Unnecessary];
 
 			diagnostics.push(diagnostic);
+			if (moment.fix) {
+				fixes.push({ diagnostic, fix: moment.fix });
+			}
 		}
 
+		const matches = this.ruleEngine.findMatches(document, this.getContext());
+		diagnostics.push(...this.createRuleDiagnostics(document, matches, diagnostics));
+		diagnostics.push(...this.createDependencyDiagnostics(document, matches));
+
 		this.diagnosticCollection.set(document.uri, diagnostics);
+		this.fixes.set(document.uri.toString(), fixes);
+	}
+
+	/**
+	 * The fix of the teachable moment a diagnostic was published for. Code actions get copies
+	 * of the diagnostics, so they are matched by rule and range.
+	 */
+	public getFix(
+		document: vscode.TextDocument,
+		diagnostic: vscode.Diagnostic,
+	): TeachableFix | undefined {
+		const ruleId = typeof diagnostic.code === "object" ? diagnostic.code.value : undefined;
+		return this.fixes
+			.get(document.uri.toString())
+			?.find(
+				(f) =>
+					typeof f.diagnostic.code === "object" &&
+					f.diagnostic.code.value === ruleId &&
+					f.diagnostic.range.isEqual(diagnostic.range),
+			)?.fix;
+	}
+
+	/**
+	 * Matches of rules the user gave a `diagnosticSeverity`, unless a teachable moment
+	 * already covers the same spot
+	 */
+	private createRuleDiagnostics(
+		document: vscode.TextDocument,
+		matches: RuleMatch[],
+		existing: vscode.Diagnostic[],
+	): vscode.Diagnostic[] {
+		const diagnostics: vscode.Diagnostic[] = [];
+
+		for (const match of matches) {
+			const { rule } = match;
+			const severity = this.ruleEngine.getRuleSettings(rule.id).diagnosticSeverity;
+			if (!severity || severity === "none") {
+				continue;
+			}
+
+			const range = new vscode.Range(
+				document.positionAt(match.range.start),
+				document.positionAt(match.range.end),
+			);
+			const covered = existing.some(
+				(d) =>
+					typeof d.code === "object" &&
+					d.code.value === rule.id &&
+					d.range.intersection(range) !== undefined,
+			);
+			if (covered) {
+				continue;
+			}
+
+			const diagnostic = new vscode.Diagnostic(
+				range,
+				`${rule.title}: ${rule.explanation}`,
+				toDiagnosticSeverity(severity),
+			);
+			diagnostic.source = "Rust Compass";
+			diagnostic.code = {
+				value: rule.id,
+				target: vscode.Uri.parse(
+					`command:rust-compass.learnMore?${encodeURIComponent(JSON.stringify({ ruleId: rule.id }))}`,
+				),
+			};
+			diagnostics.push(diagnostic);
+		}
+
+		return diagnostics;
+	}
+
+	/**
+	 * Warnings for matches of rules whose `expectsDependency` the crate doesn't meet, e.g. a
+	 * serde derive without serde's `derive` feature. Missing features are only information
+	 * when `cargo metadata` didn't resolve them.
+	 */
+	private createDependencyDiagnostics(
+		document: vscode.TextDocument,
+		matches: RuleMatch[],
+	): vscode.Diagnostic[] {
+		const cargoAnalyzer = CargoAnalyzerService.getInstance();
+		const dependencies = cargoAnalyzer.getProjectDependencies(document.uri);
+		if (dependencies.crates.length === 0) {
+			return []; // No Cargo.toml to check against
+		}
+		const diagnostics: vscode.Diagnostic[] = [];
+
+		for (const { rule, range } of matches) {
+			const expected = rule.expectsDependency;
+			const problem = expected && unmetDependency(dependencies, expected);
+			const severity = this.ruleEngine.getRuleSettings(rule.id).diagnosticSeverity;
+			if (!problem || severity === "none") {
+				continue;
+			}
+			// Without `cargo metadata`, features other crates turn on for this one aren't known
+			const unresolved =
+				typeof expected !== "string" &&
+				!dependencies.resolvedFeatures?.has(normalizeCrateName(expected.name)) &&
+				unmetDependency(dependencies, { ...expected, features: [] }) === undefined;
+			const message = unresolved
+				? `${problem} in Cargo.toml (unresolved: cargo metadata didn't run)`
+				: problem;
+			const diagnostic = new vscode.Diagnostic(
+				new vscode.Range(document.positionAt(range.start), document.positionAt(range.end)),
+				`${rule.title}: ${message}`,
+				unresolved
+					? vscode.DiagnosticSeverity.Information
+					: vscode.DiagnosticSeverity.Warning,
+			);
+			diagnostic.source = "Rust Compass";
+			diagnostic.code = {
+				value: rule.id,
+				target: vscode.Uri.parse(
+					`command:rust-compass.learnMore?${encodeURIComponent(JSON.stringify({ ruleId: rule.id }))}`,
+				),
+			};
+			diagnostics.push(diagnostic);
+		}
+
+		return diagnostics;
 	}
 
 	/**
@@ -115,6 +278,22 @@ export class SmartDiagnosticProvider implements vscode.Disposable {
 			clearTimeout(this.debounceTimer);
 		}
 		this.diagnosticCollection.dispose();
+		this.fixes.clear();
 		this.disposables.forEach((d) => d.dispose());
 	}
 }
+
+function toDiagnosticSeverity(
+	severity: Exclude<RuleDiagnosticSeverity, "none">,
+): vscode.DiagnosticSeverity {
+	switch (severity) {
+		case "error":
+			return vscode.DiagnosticSeverity.Error;
+		case "warning":
+			return vscode.DiagnosticSeverity.Warning;
+		case "information":
+			return vscode.DiagnosticSeverity.Information;
+		case "hint":
+			return vscode.DiagnosticSeverity.Hint;
+	}
+}
@@ -0,0 +1,402 @@
+import { getScope, type Span, type SyntaxNode, type SyntaxTree, type Token } from "./syntax";
+import type { RuleFixEdit, RuleMatch, RuleSuggestedFix } from "./types";
+
+/**
+ * A text edit in document offsets
+ */
+export interface FixEdit {
+	start: number;
+	end: number;
+	text: string;
+}
+
+/**
+ * A rule's suggested fix worked out for one match
+ */
+export interface ResolvedFix {
+	description: string;
+	/** `text` of every edit is a snippet with tab stops */
+	snippet: boolean;
+	/** Non-overlapping edits, in document order */
+	edits: FixEdit[];
+}
+
+/** `$$`, `$name`, `${name}`, `$1` */
+export const TEMPLATE_REFERENCE = /\$(?:\{(\d+|[A-Za-z_]\w*)\}|(\d|[A-Za-z_]\w*)|\$)/g;
+
+/** Values templates can reference besides capture groups */
+export const TEMPLATE_VALUES = ["0", "match", "target", "method", "receiver", "args"];
+
+/** Spans `RuleFixEdit.target` accepts besides capture groups */
+export const FIX_TARGETS = ["match", "call", "receiver", "args", "receiverDefinition", "imports"];
+
+/**
+ * Turn a match's `suggestedFix` into concrete edits. Returns `undefined` when the fix
+ * doesn't apply here: a referenced capture group or span is missing, or nothing would change.
+ */
+export function resolveFix(tree: SyntaxTree, match: RuleMatch): ResolvedFix | undefined {
+	const fix = match.rule.suggestedFix;
+	if (!fix) {
+		return undefined;
+	}
+
+	const templates: RuleFixEdit[] = [
+		...(fix.replacement !== undefined ? [{ text: fix.replacement }] : []),
+		...(fix.edits ?? []),
+	];
+	if (templates.length === 0) {
+		const edit = resolveLiteralFix(tree.text, match, fix);
+		return edit ? { description: fix.description, snippet: false, edits: [edit] } : undefined;
+	}
+
+	const context = new FixContext(tree, match);
+	const snippet = fix.snippet === true;
+	const edits: FixEdit[] = [];
+
+	for (const template of templates) {
+		const target = template.target ?? "match";
+
+		if (target === "imports") {
+			const line = context.expand(template.text, "", snippet);
+			if (line === undefined) {
+				return undefined;
+			}
+			// Leave files that already import it alone
+			if (tree.text.split("\n").some((l) => l.trim() === line.trim())) {
+				continue;
+			}
+			edits.push(importEdit(tree, line));
+			continue;
+		}
+
+		const span = context.span(target);
+		if (!span) {
+			return undefined;
+		}
+		const current = tree.text.slice(span.start, span.end);
+		const text = context.expand(template.text, current, snippet);
+		if (text === undefined) {
+			return undefined;
+		}
+		if (text !== current) {
+			edits.push({ start: span.start, end: span.end, text });
+		}
+	}
+
+	edits.sort((a, b) => a.start - b.start);
+	const overlapping = edits.some((edit, i) => i > 0 && edit.start < edits[i - 1].end);
+	if (edits.length === 0 || overlapping) {
+		return undefined;
+	}
+	return { description: fix.description, snippet, edits };
+}
+
+/**
+ * Combine the fixes for many matches in one document: edits another fix already makes
+ * (e.g. the same import) are dropped, and fixes that would clash with an earlier one are skipped
+ */
+export function mergeFixes(fixes: ResolvedFix[]): ResolvedFix[] {
+	const accepted: FixEdit[] = [];
+	const merged: ResolvedFix[] = [];
+
+	for (const fix of fixes) {
+		const edits = fix.edits.filter(
+			(edit) =>
+				!accepted.some(
+					(a) => a.start === edit.start && a.end === edit.end && a.text === edit.text,
+				),
+		);
+		const clashes = edits.some((edit) =>
+			accepted.some((a) =>
+				edit.start === edit.end || a.start === a.end
+					? edit.start > a.start && edit.start < a.end
+					: edit.start < a.end && edit.end > a.start,
+			),
+		);
+		if (clashes || edits.length === 0) {
+			continue;
+		}
+		accepted.push(...edits);
+		merged.push({ ...fix, edits });
+	}
+
+	return merged;
+}
+
+/**
+ * Plain-text version of a snippet fix, with each placeholder's default filled in
+ */
+export function withoutPlaceholders(fix: ResolvedFix): ResolvedFix {
+	if (!fix.snippet) {
+		return fix;
+	}
+	const toText = (snippet: string): string => {
+		let text = snippet;
+		// Innermost placeholders first, so nested defaults resolve
+		for (let previous = ""; previous !== text; ) {
+			previous = text;
+			text = text.replace(/\$\{\d+:((?:\\.|[^$}\\])*)\}/g, "$1");
+		}
+		return text.replace(/\$(\d+|\{\d+\})/g, "").replace(/\\([$}\\])/g, "$1");
+	};
+	return {
+		...fix,
+		snippet: false,
+		edits: fix.edits.map((edit) => ({ ...edit, text: toText(edit.text) })),
+	};
+}
+
+/**
+ * `before` → `after` on the match line: the occurrence overlapping the match,
+ * otherwise the one closest to it
+ */
+function resolveLiteralFix(
+	text: string,
+	match: RuleMatch,
+	fix: RuleSuggestedFix,
+): FixEdit | undefined {
+	if (fix.before === undefined || fix.after === undefined || fix.before === "") {
+		return undefined;
+	}
+	const { start, end } = match.range;
+	const lineStart = text.lastIndexOf("\n", start - 1) + 1;
+	const newline = text.indexOf("\n", start);
+	const line = text.slice(lineStart, newline === -1 ? text.length : newline);
+
+	let best: number | undefined;
+	let bestDistance = Number.POSITIVE_INFINITY;
+	for (let i = line.indexOf(fix.before); i !== -1; i = line.indexOf(fix.before, i + 1)) {
+		const from = lineStart + i;
+		const to = from + fix.before.length;
+		const distance =
+			from < end && to > start ? 0 : Math.min(Math.abs(from - end), Math.abs(to - start));
+		if (distance < bestDistance) {
+			best = from;
+			bestDistance = distance;
+		}
+	}
+
+	if (best === undefined) {
+		return undefined;
+	}
+	return { start: best, end: best + fix.before.length, text: fix.after };
+}
+
+/**
+ * Spans and values templates can refer to for one match
+ */
+class FixContext {
+	private call: SyntaxNode | undefined;
+
+	constructor(
+		private tree: SyntaxTree,
+		private match: RuleMatch,
+	) {
+		this.call = findMethodCall(tree, match);
+	}
+
+	public span(target: string): Span | undefined {
+		switch (target) {
+			case "match":
+				return { start: this.match.range.start, end: this.match.range.end };
+			case "call":
+				return this.call && { start: this.call.start, end: this.call.end };
+			case "receiver":
+				return this.call?.receiver;
+			case "args":
+				return this.call?.arguments;
+			case "receiverDefinition":
+				return this.call && findReceiverDefinition(this.tree, this.call);
+		}
+		const group = /^\$\{?(\w+)\}?$/.exec(target);
+		return group ? this.match.captures?.[group[1]] : undefined;
+	}
+
+	/**
+	 * Fill in a template; `undefined` if it references something this match doesn't have
+	 */
+	public expand(template: string, target: string, snippet: boolean): string | undefined {
+		let missing = false;
+		const text = template.replace(TEMPLATE_REFERENCE, (reference, braced, bare) => {
+			const name: string | undefined = braced ?? bare;
+			if (name === undefined) {
+				return snippet ? "\\$" : "$";
+			}
+			// In snippets, numbers are tab stops
+			if (snippet && /^\d/.test(name)) {
+				return reference;
+			}
+			const value = this.value(name, target);
+			if (value === undefined) {
+				missing = true;
+				return reference;
+			}
+			return snippet ? value.replace(/[$}\\]/g, "\\$&") : value;
+		});
+		return missing ? undefined : text;
+	}
+
+	private value(name: string, target: string): string | undefined {
+		const capture = this.match.captures?.[name];
+		if (capture) {
+			return this.tree.text.slice(capture.start, capture.end);
+		}
+		switch (name) {
+			case "0":
+			case "match":
+				return this.match.matchedText;
+			case "target":
+				return target;
+			case "method":
+				return this.call?.name;
+			case "receiver":
+			case "args": {
+				const span = this.span(name);
+				return span && this.tree.text.slice(span.start, span.end);
+			}
+		}
+		return undefined;
+	}
+}
+
+/**
+ * The method call a match is on: one starting inside the match, else one enclosing it
+ */
+function findMethodCall(tree: SyntaxTree, match: RuleMatch): SyntaxNode | undefined {
+	const { start, end } = match.range;
+	const inside = tree.nodes.find(
+		(n) => n.kind === "method_call" && n.start >= start && n.start < end,
+	);
+	if (inside) {
+		return inside;
+	}
+	for (let node: SyntaxNode | undefined = tree.nodeAt(start); node; node = node.parent) {
+		if (node.kind === "method_call") {
+			return node;
+		}
+	}
+	return undefined;
+}
+
+/**
+ * Initializer of the closest `let` before the call (in the same function) that binds
+ * the receiver, e.g. `input.chars()` in `let mut it = input.chars();`
+ */
+function findReceiverDefinition(tree: SyntaxTree, call: SyntaxNode): Span | undefined {
+	if (!call.receiver) {
+		return undefined;
+	}
+	const name = tree.text.slice(call.receiver.start, call.receiver.end);
+	if (!/^[A-Za-z_]\w*$/.test(name)) {
+		return undefined;
+	}
+
+	const from = getScope(tree, call.start).function?.body?.start ?? 0;
+	const tokens = tree.tokens.filter(
+		(t) => t.kind !== "comment" && t.start >= from && t.end <= call.start,
+	);
+
+	for (let i = tokens.length - 1; i >= 0; i--) {
+		if (!isToken(tokens[i], "ident", "let")) {
+			continue;
+		}
+		let k = i + 1;
+		if (isToken(tokens[k], "ident", "mut")) {
+			k++;
+		}
+		if (!isToken(tokens[k], "ident", name)) {
+			continue;
+		}
+
+		// Skip a type annotation, then take everything up to the `;`
+		const equals = findAtDepthZero(tokens, k + 1, ["=", ";"]);
+		if (equals === -1 || tokens[equals].text !== "=") {
+			return undefined;
+		}
+		const semicolon = findAtDepthZero(tokens, equals + 1, [";"]);
+		if (semicolon === -1 || semicolon === equals + 1) {
+			return undefined;
+		}
+		return { start: tokens[equals + 1].start, end: tokens[semicolon - 1].end };
+	}
+	return undefined;
+}
+
+function findAtDepthZero(tokens: Token[], from: number, texts: string[]): number {
+	let depth = 0;
+	for (let k = from; k < tokens.length; k++) {
+		const token = tokens[k];
+		if (token.kind !== "punct") {
+			continue;
+		}
+		if (depth === 0 && texts.includes(token.text)) {
+			return k;
+		}
+		if ("([{".includes(token.text)) {
+			depth++;
+		} else if (")]}".includes(token.text)) {
+			depth--;
+		}
+	}
+	return -1;
+}
+
+function isToken(token: Token | undefined, kind: Token["kind"], text: string): boolean {
+	return token !== undefined && token.kind === kind && token.text === text;
+}
+
+/**
+ * Insert a line after the last top-level `use` item, or after the file's
+ * inner attributes and `//!` docs when there is none
+ */
+export function importEdit(tree: SyntaxTree, line: string): FixEdit {
+	const tokens = tree.tokens;
+	let depth = 0;
+	let lastUseEnd = -1;
+	for (let k = 0; k < tokens.length; k++) {
+		const token = tokens[k];
+		if (token.kind === "punct" && "([{".includes(token.text)) {
+			depth++;
+		} else if (token.kind === "punct" && ")]}".includes(token.text)) {
+			depth--;
+		} else if (depth === 0 && isToken(token, "ident", "use")) {
+			const semicolon = findAtDepthZero(tokens, k + 1, [";"]);
+			if (semicolon !== -1) {
+				lastUseEnd = tokens[semicolon].end;
+				k = semicolon;
+			}
+		}
+	}
+
+	if (lastUseEnd !== -1) {
+		return { start: lastUseEnd, end: lastUseEnd, text: `\n${line}` };
+	}
+
+	let offset = 0;
+	for (let k = 0; k < tokens.length; k++) {
+		const token = tokens[k];
+		if (token.kind === "comment" && /^\/[/*]!/.test(token.text)) {
+			offset = token.end;
+		} else if (
+			isToken(token, "punct", "#") &&
+			isToken(tokens[k + 1], "punct", "!") &&
+			isToken(tokens[k + 2], "punct", "[")
+		) {
+			const close = findAtDepthZero(tokens, k + 3, ["]"]);
+			if (close === -1) {
+				break;
+			}
+			offset = tokens[close].end;
+			k = close;
+		} else {
+			break;
+		}
+	}
+
+	if (offset === 0) {
+		return { start: 0, end: 0, text: `${line}\n` };
+	}
+	const newline = tree.text.indexOf("\n", offset);
+	const at = newline === -1 ? tree.text.length : newline + 1;
+	return { start: at, end: at, text: newline === -1 ? `\n${line}\n` : `${line}\n` };
+}
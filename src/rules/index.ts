@@ -1,2 +1,13 @@
-export { RuleEngine } from "./ruleEngine";
+export { type FixEdit, mergeFixes, type ResolvedFix, withoutPlaceholders } from "./fixes";
+export * from "./refactors";
+export {
+	DEFAULT_MIN_CONFIDENCE,
+	hasDependency,
+	isCargoManifest,
+	normalizeCrateName,
+	RuleEngine,
+	unmetDependency,
+} from "./ruleEngine";
+export { isVersionRequirement, minimumVersion, satisfiesVersion } from "./semver";
+export * from "./syntax";
 export * from "./types";
@@ -0,0 +1,804 @@
+import type { SyntaxNode, SyntaxTree } from "../syntax";
+import {
+	CONTROL_FLOW,
+	hasComments,
+	KEYWORDS,
+	type Refactoring,
+	TokenStream,
+} from "./refactoring";
+
+type Wrapper = "option" | "result";
+
+/** Combinators that can be expanded into a `match`, with the rule explaining each */
+const COMBINATOR_RULES = new Map([
+	["map", "map-and-then"],
+	["and_then", "map-and-then"],
+	["map_or", "option-methods"],
+	["map_or_else", "option-methods"],
+	["unwrap_or", "option-methods"],
+	["unwrap_or_else", "option-methods"],
+	["unwrap_or_default", "unwrap-or-default"],
+	["ok_or", "ok-or"],
+	["ok_or_else", "ok-or"],
+]);
+
+/** Methods returning an `Option`, whatever they are called on */
+const OPTION_METHODS = new Set([
+	"checked_add",
+	"checked_div",
+	"checked_mul",
+	"checked_sub",
+	"extension",
+	"file_name",
+	"find",
+	"find_map",
+	"first",
+	"first_mut",
+	"get",
+	"get_mut",
+	"last",
+	"last_mut",
+	"max",
+	"max_by",
+	"max_by_key",
+	"min",
+	"min_by",
+	"min_by_key",
+	"next",
+	"next_back",
+	"nth",
+	"ok",
+	"parent",
+	"peek",
+	"pop",
+	"pop_back",
+	"pop_front",
+	"position",
+	"rsplit_once",
+	"split_once",
+	"strip_prefix",
+	"strip_suffix",
+	"to_str",
+]);
+
+/** Methods and functions returning a `Result` */
+const RESULT_METHODS = new Set([
+	"from_str",
+	"from_utf8",
+	"map_err",
+	"ok_or",
+	"ok_or_else",
+	"open",
+	"parse",
+	"read_line",
+	"read_to_string",
+	"try_from",
+	"try_into",
+]);
+
+/** Methods on an `Option` or `Result` that return the same kind of wrapper */
+const PRESERVING_METHODS = new Set([
+	"and_then",
+	"as_deref",
+	"as_mut",
+	"as_ref",
+	"cloned",
+	"copied",
+	"inspect",
+	"map",
+	"or",
+	"or_else",
+]);
+
+/** Collections whose `new()` is also their `Default` */
+const DEFAULT_CONSTRUCTORS = /^(?:String|Vec|VecDeque|HashMap|HashSet|BTreeMap|BTreeSet)::new\(\)$/;
+
+/**
+ * A `match` arm or `if let` branch: `{ EXPR }` blocks are reduced to `EXPR`
+ */
+interface Branch {
+	from: number;
+	to: number;
+	text: string;
+}
+
+/**
+ * A `match` or `if let` taking an `Option` or `Result` apart
+ */
+interface Branching {
+	keyword: "match" | "if let";
+	start: number;
+	end: number;
+	/** Expression being matched on, tokens `from..=to` */
+	scrutinee: { from: number; to: number };
+	wrapper: Wrapper;
+	/** `x` in `Some(x)` or `Ok(x)` */
+	binding: string;
+	success: Branch;
+	/** `e` in `Err(e)` (`_` when ignored); unset for `None` */
+	errorBinding?: string;
+	failure: Branch;
+}
+
+/**
+ * Rewrites between explicit `match` / `if let` and `Option` or `Result` combinators
+ * for the code around an offset:
+ *
+ * - `match opt { Some(x) => f(x), None => d }` → `opt.map_or(d, |x| f(x))`
+ * - `if let Some(x) = opt { Some(f(x)) } else { None }` → `opt.map(|x| f(x))`
+ * - `opt.unwrap_or_else(|| d)` → `match opt { Some(value) => value, None => d }`
+ *
+ * Branches have to be single expressions without early returns or `?`, and combinators
+ * are only expanded when it is clear whether they are called on an `Option` or a `Result`.
+ */
+export function findCombinatorRefactorings(tree: SyntaxTree, offset: number): Refactoring[] {
+	const ts = new TokenStream(tree.text, tree.tokens);
+	const refactorings: Refactoring[] = [];
+
+	const branching = findBranching(tree, ts, offset);
+	const combinator = branching && toCombinator(tree, ts, branching);
+	if (combinator) {
+		refactorings.push(combinator);
+	}
+
+	const call = findCombinatorCall(tree, offset);
+	const expanded = call && toMatch(tree, ts, call);
+	if (expanded) {
+		refactorings.push(expanded);
+	}
+	return refactorings;
+}
+
+/**
+ * The innermost two-armed `match` or `if let .. else` around an offset
+ */
+function findBranching(tree: SyntaxTree, ts: TokenStream, offset: number): Branching | undefined {
+	let candidates: Branching[] = [];
+
+	let node: SyntaxNode | undefined = tree.nodeAt(offset);
+	while (node && node.kind !== "match_expression") {
+		node = node.parent;
+	}
+	const match = node && parseMatch(ts, node);
+	if (match) {
+		candidates.push(match);
+	}
+
+	for (let k = 0; k < ts.tokens.length && ts.tokens[k].start <= offset; k++) {
+		if (ts.is(k, "if") && ts.is(k + 1, "let")) {
+			const ifLet = parseIfLet(ts, k);
+			if (ifLet && ts.tokens[ifLet.end].end > offset) {
+				candidates.push(ifLet);
+			}
+		}
+	}
+
+	// Comments in the branches would be lost
+	candidates = candidates.filter(
+		(c) => !hasComments(tree.tokens, ts.tokens[c.start].start, ts.tokens[c.end].end),
+	);
+	return candidates.sort((a, b) => b.start - a.start)[0];
+}
+
+function parseMatch(ts: TokenStream, node: SyntaxNode): Branching | undefined {
+	if (!node.body) {
+		return undefined;
+	}
+	const start = ts.indexAt(node.start);
+	const bodyOpen = ts.indexAt(node.body.start);
+	const bodyClose = ts.closeOf(bodyOpen);
+	if (bodyClose === -1 || bodyOpen === start + 1) {
+		return undefined;
+	}
+
+	const arms: { pattern: { from: number; to: number }; body: Branch }[] = [];
+	for (let k = bodyOpen + 1; k < bodyClose; ) {
+		const arrow = ts.findAtDepthZero(k, bodyClose, "=>");
+		if (arrow === -1 || arrow === k) {
+			return undefined;
+		}
+		let to: number;
+		let next: number;
+		if (ts.is(arrow + 1, "{") && ts.closeOf(arrow + 1) !== -1) {
+			to = ts.closeOf(arrow + 1);
+			next = ts.is(to + 1, ",") ? to + 2 : to + 1;
+		} else {
+			const comma = ts.findAtDepthZero(arrow + 1, bodyClose, ",");
+			to = comma === -1 ? bodyClose - 1 : comma - 1;
+			next = to + 2;
+		}
+		const body = parseBranch(ts, arrow + 1, to);
+		if (!body) {
+			return undefined;
+		}
+		arms.push({ pattern: { from: k, to: arrow - 1 }, body });
+		k = next;
+	}
+	if (arms.length !== 2) {
+		return undefined;
+	}
+
+	const [first, second] = arms.map((arm) => parsePattern(ts, arm.pattern.from, arm.pattern.to));
+	if (!first || !second) {
+		return undefined;
+	}
+	const successFirst = first.variant === "Some" || first.variant === "Ok";
+	const [success, failure] = successFirst ? [first, second] : [second, first];
+	const [successArm, failureArm] = successFirst ? arms : [arms[1], arms[0]];
+	const expected = success.variant === "Some" ? "None" : success.variant === "Ok" ? "Err" : "";
+	if (failure.variant !== expected || success.binding === undefined) {
+		return undefined;
+	}
+
+	return {
+		keyword: "match",
+		start,
+		end: bodyClose,
+		scrutinee: { from: start + 1, to: bodyOpen - 1 },
+		wrapper: success.variant === "Some" ? "option" : "result",
+		binding: success.binding,
+		success: successArm.body,
+		errorBinding: failure.binding,
+		failure: failureArm.body,
+	};
+}
+
+/**
+ * `if let Some(x) = EXPR { .. } else { .. }` starting at the `if` token
+ */
+function parseIfLet(ts: TokenStream, start: number): Branching | undefined {
+	// `else if let` chains have more than two branches
+	if (ts.is(start - 1, "else")) {
+		return undefined;
+	}
+	const equals = ts.findAtDepthZero(start + 2, ts.tokens.length, "=");
+	const pattern = equals === -1 ? undefined : parsePattern(ts, start + 2, equals - 1);
+	if (!pattern?.binding || (pattern.variant !== "Some" && pattern.variant !== "Ok")) {
+		return undefined;
+	}
+	const thenOpen = ts.findAtDepthZero(equals + 1, ts.tokens.length, "{");
+	const thenClose = thenOpen === -1 ? -1 : ts.closeOf(thenOpen);
+	if (thenClose === -1 || thenOpen === equals + 1 || !ts.is(thenClose + 1, "else")) {
+		return undefined;
+	}
+	const elseClose = ts.is(thenClose + 2, "{") ? ts.closeOf(thenClose + 2) : -1;
+	const success = parseBranch(ts, thenOpen, thenClose);
+	const failure = elseClose === -1 ? undefined : parseBranch(ts, thenClose + 2, elseClose);
+	if (!success || !failure) {
+		return undefined;
+	}
+
+	return {
+		keyword: "if let",
+		start,
+		end: elseClose,
+		scrutinee: { from: equals + 1, to: thenOpen - 1 },
+		wrapper: pattern.variant === "Some" ? "option" : "result",
+		binding: pattern.binding,
+		success,
+		errorBinding: pattern.variant === "Ok" ? "_" : undefined,
+		failure,
+	};
+}
+
+/**
+ * `Some(x)`, `Ok(_)`, `Err(e)` or `None`, binding at most a plain name
+ */
+function parsePattern(
+	ts: TokenStream,
+	from: number,
+	to: number,
+): { variant: string; binding?: string } | undefined {
+	const variant = ts.tokens[from]?.text;
+	if (from === to) {
+		return variant === "None" ? { variant } : undefined;
+	}
+	if (!["Some", "Ok", "Err"].includes(variant) || !ts.is(from + 1, "(")) {
+		return undefined;
+	}
+	if (ts.closeOf(from + 1) !== to) {
+		return undefined;
+	}
+	const name = ts.is(from + 2, "mut") ? from + 3 : from + 2;
+	const token = ts.tokens[name];
+	if (name !== to - 1 || token.kind !== "ident" || KEYWORDS.has(token.text)) {
+		return undefined;
+	}
+	return { variant, binding: ts.slice(from + 2, to - 1) };
+}
+
+/**
+ * An arm body or `if let` block as one expression, if it is that simple
+ */
+function parseBranch(ts: TokenStream, from: number, to: number): Branch | undefined {
+	if (to < from) {
+		return undefined;
+	}
+	for (let k = from; k <= to; k++) {
+		if (CONTROL_FLOW.has(ts.tokens[k].text)) {
+			return undefined;
+		}
+	}
+	if (ts.is(from, "{") && ts.closeOf(from) === to) {
+		if (
+			to === from + 1 ||
+			ts.is(from + 1, "let") ||
+			ts.findAtDepthZero(from + 1, to, ";") !== -1
+		) {
+			return undefined;
+		}
+		return parseBranch(ts, from + 1, to - 1);
+	}
+	return { from, to, text: ts.slice(from, to) };
+}
+
+/**
+ * The `match` / `if let` as a combinator call on what it matches on
+ */
+function toCombinator(
+	tree: SyntaxTree,
+	ts: TokenStream,
+	branching: Branching,
+): Refactoring | undefined {
+	const { binding, success, failure, wrapper } = branching;
+	const receiver = combinatorReceiver(tree, ts, branching);
+	const someOf = (branch: Branch) => variantArgument(ts, branch, "Some");
+	const okOf = (branch: Branch) => variantArgument(ts, branch, "Ok");
+	const errOf = (branch: Branch) => variantArgument(ts, branch, "Err");
+
+	// Whether the `Err(e)` arm uses the error, and the closure parameter for it
+	const error = branching.errorBinding ?? "_";
+	const errorName = error.replace(/^mut /, "");
+	// Format strings can use it too, as in `panic!("{e}")`
+	const inlineArgument = new RegExp(`\\{${errorName}[:}]`);
+	const failureTokens = ts.tokens.slice(failure.from, failure.to + 1);
+	const errorUsed =
+		error !== "_" &&
+		failureTokens.some(
+			(t) => t.text === errorName || (t.kind === "string" && inlineArgument.test(t.text)),
+		);
+	const errorParameter = errorUsed ? error : "_";
+
+	let call: string | undefined;
+	if (wrapper === "option") {
+		const mapped = someOf(success);
+		const okValue = okOf(success);
+		const errValue = errOf(failure);
+		if (failure.text === "None") {
+			call =
+				mapped === binding
+					? undefined
+					: mapped !== undefined
+						? `map(|${binding}| ${mapped})`
+						: `and_then(|${binding}| ${success.text})`;
+		} else if (okValue === binding && errValue !== undefined) {
+			call = isCheap(errValue) ? `ok_or(${errValue})` : `ok_or_else(|| ${errValue})`;
+		} else if (success.text === binding) {
+			call = isDefault(failure.text)
+				? "unwrap_or_default()"
+				: isCheap(failure.text)
+					? `unwrap_or(${failure.text})`
+					: `unwrap_or_else(|| ${failure.text})`;
+		} else {
+			call = isCheap(failure.text)
+				? `map_or(${failure.text}, |${binding}| ${success.text})`
+				: `map_or_else(|| ${failure.text}, |${binding}| ${success.text})`;
+		}
+	} else {
+		const mapped = okOf(success);
+		if (error !== "_" && errOf(failure) === error) {
+			call =
+				mapped === binding
+					? undefined
+					: mapped !== undefined
+						? `map(|${binding}| ${mapped})`
+						: `and_then(|${binding}| ${success.text})`;
+		} else if (someOf(success) === binding && failure.text === "None" && !errorUsed) {
+			call = "ok()";
+		} else if (success.text === binding) {
+			call =
+				!errorUsed && isDefault(failure.text)
+					? "unwrap_or_default()"
+					: !errorUsed && isCheap(failure.text)
+						? `unwrap_or(${failure.text})`
+						: `unwrap_or_else(|${errorParameter}| ${failure.text})`;
+		} else {
+			const f = `|${binding}| ${success.text}`;
+			call =
+				!errorUsed && isCheap(failure.text)
+					? `map_or(${failure.text}, ${f})`
+					: `map_or_else(|${errorParameter}| ${failure.text}, ${f})`;
+		}
+	}
+	if (!call) {
+		return undefined;
+	}
+
+	const method = call.slice(0, call.indexOf("("));
+	// A `match` statement needs no `;`, a method call does
+	const statement =
+		(branching.start === 0 || ["{", "}", ";"].some((p) => ts.is(branching.start - 1, p))) &&
+		!ts.is(branching.end + 1, "}") &&
+		!ts.is(branching.end + 1, ";");
+	return {
+		title: `Convert ${branching.keyword} to .${method}()`,
+		// `ok()` has no `match` form to offer back, but is explained with the others
+		ruleIds: [COMBINATOR_RULES.get(method) ?? "option-methods"],
+		edits: [
+			{
+				start: ts.tokens[branching.start].start,
+				end: ts.tokens[branching.end].end,
+				text: `${receiver}.${call}${statement ? ";" : ""}`,
+			},
+		],
+	};
+}
+
+/**
+ * What the combinator is called on: `&opt` becomes `opt.as_ref()`, so the closures still
+ * get references
+ */
+function combinatorReceiver(tree: SyntaxTree, ts: TokenStream, branching: Branching): string {
+	const { from, to } = branching.scrutinee;
+	if (ts.is(from, "&")) {
+		const mutable = ts.is(from + 1, "mut");
+		const inner = mutable ? from + 2 : from + 1;
+		return `${postfixOperand(ts, inner, to)}.${mutable ? "as_mut" : "as_ref"}()`;
+	}
+	if (from === to && ts.isIdent(from)) {
+		const name = ts.tokens[from].text;
+		const reference = new RegExp(
+			`\\b${name}\\s*:\\s*&\\s*(mut\\s+)?(?:\\w+::)*(?:Option|Result)\\b`,
+		).exec(enclosingFunctionText(tree, ts.tokens[from].start));
+		if (reference) {
+			return `${name}.${reference[1] ? "as_mut" : "as_ref"}()`;
+		}
+	}
+	return postfixOperand(ts, from, to);
+}
+
+/**
+ * Tokens `from..=to`, in parentheses unless a method can be called on them as they are
+ */
+function postfixOperand(ts: TokenStream, from: number, to: number): string {
+	for (let k = from; k <= to; k++) {
+		const token = ts.tokens[k];
+		const close = ts.closeOf(k);
+		if (close !== -1) {
+			k = close;
+		} else if (ts.is(k, "::") && ts.is(k + 1, "<")) {
+			// Skip a turbofish such as `parse::<u8>`
+			let depth = 0;
+			do {
+				k++;
+				depth += ts.is(k, "<") ? 1 : ts.is(k, ">") ? -1 : 0;
+			} while (depth > 0 && k < to);
+		} else if (
+			(token.kind === "punct" && ![".", "::", "?"].includes(token.text)) ||
+			(token.kind === "ident" && KEYWORDS.has(token.text) && token.text !== "self")
+		) {
+			return `(${ts.slice(from, to)})`;
+		}
+	}
+	return ts.slice(from, to);
+}
+
+/**
+ * `EXPR` when a branch is exactly `Some(EXPR)` (or `Ok`, `Err`)
+ */
+function variantArgument(ts: TokenStream, branch: Branch, variant: string): string | undefined {
+	const { from, to } = branch;
+	if (!ts.is(from, variant) || !ts.is(from + 1, "(") || ts.closeOf(from + 1) !== to) {
+		return undefined;
+	}
+	return to > from + 2 ? ts.slice(from + 2, to - 1) : undefined;
+}
+
+/**
+ * Literals and plain paths, which are fine to evaluate eagerly as a combinator argument
+ */
+function isCheap(text: string): boolean {
+	return (
+		text === "()" ||
+		/^-?\d[\w.]*$/.test(text) ||
+		/^"(?:[^"\\]|\\.)*"$/.test(text) ||
+		/^'(?:[^'\\]|\\.)+'$/.test(text) ||
+		/^[A-Za-z_]\w*(?:::[A-Za-z_]\w*)*$/.test(text)
+	);
+}
+
+/**
+ * Values `unwrap_or_default()` produces
+ */
+function isDefault(text: string): boolean {
+	const compact = text.replace(/\s+/g, "");
+	return (
+		/^(?:Default::default|[A-Z]\w*::default)\(\)$/.test(compact) ||
+		DEFAULT_CONSTRUCTORS.test(compact) ||
+		["vec![]", "0", "0.0", "false", '""'].includes(compact)
+	);
+}
+
+/**
+ * The combinator call (`.map(..)`, `.unwrap_or(..)`, ...) around an offset
+ */
+function findCombinatorCall(tree: SyntaxTree, offset: number): SyntaxNode | undefined {
+	let node: SyntaxNode | undefined = tree.nodeAt(offset);
+	while (node && !(node.kind === "method_call" && COMBINATOR_RULES.has(node.name ?? ""))) {
+		node = node.parent;
+	}
+	return node?.receiver ? node : undefined;
+}
+
+/**
+ * The combinator call expanded into an explicit `match`
+ */
+function toMatch(tree: SyntaxTree, ts: TokenStream, call: SyntaxNode): Refactoring | undefined {
+	const method = call.name ?? "";
+	const receiver = call.receiver;
+	const dot = ts.indexAt(call.start);
+	const open = dot + 2;
+	const close = ts.closeOf(open);
+	if (!receiver || !ts.is(open, "(") || close === -1) {
+		return undefined;
+	}
+	if (hasComments(tree.tokens, receiver.start, call.end)) {
+		return undefined;
+	}
+	const receiverFrom = ts.indexAt(receiver.start);
+	const wrapper = method.startsWith("ok_or")
+		? "option"
+		: inferWrapper(tree, ts, receiverFrom, dot - 1);
+	if (!wrapper) {
+		return undefined;
+	}
+
+	const args: { from: number; to: number }[] = [];
+	for (let k = open + 1; k < close; ) {
+		const comma = ts.findAtDepthZero(k, close, ",");
+		const end = comma === -1 ? close : comma;
+		args.push({ from: k, to: end - 1 });
+		k = end + 1;
+	}
+
+	const taken = new Set(ts.tokens.slice(receiverFrom, close).map((t) => t.text));
+	const value = ["value", "inner", "v"].find((name) => !taken.has(name)) ?? "value";
+	const error = ["e", "err", "error"].find((name) => !taken.has(name)) ?? "e";
+	const [some, none] = wrapper === "option" ? ["Some", "None"] : ["Ok", "Err"];
+	const fn = (index: number, parameters: number) => {
+		const arg = args[index];
+		return arg && parseFunction(ts, arg.from, arg.to, parameters, value);
+	};
+	const eager = (index: number) => {
+		const arg = args[index];
+		const text = arg && ts.slice(arg.from, arg.to);
+		return text && (isCheap(text) || isDefault(text)) ? text : undefined;
+	};
+	const errorArm = (text: string | undefined) =>
+		wrapper === "option" ? [none, text] : [`${none}(_)`, text];
+
+	let arms: (string | undefined)[][] | undefined;
+	switch (method) {
+		case "map": {
+			const f = fn(0, 1);
+			arms = f && [
+				[`${some}(${f.parameter})`, `${some}(${f.body})`],
+				wrapper === "option" ? [none, none] : [`${none}(${error})`, `${none}(${error})`],
+			];
+			break;
+		}
+		case "and_then": {
+			const f = fn(0, 1);
+			arms = f && [
+				[`${some}(${f.parameter})`, f.body],
+				wrapper === "option" ? [none, none] : [`${none}(${error})`, `${none}(${error})`],
+			];
+			break;
+		}
+		case "map_or": {
+			const f = fn(1, 1);
+			arms = f && [[`${some}(${f.parameter})`, f.body], errorArm(eager(0))];
+			break;
+		}
+		case "map_or_else": {
+			const f = fn(1, 1);
+			const g = fn(0, wrapper === "option" ? 0 : 1);
+			arms = f &&
+				g && [
+					[`${some}(${f.parameter})`, f.body],
+					wrapper === "option" ? [none, g.body] : [`${none}(${g.parameter})`, g.body],
+				];
+			break;
+		}
+		case "unwrap_or":
+			arms = [[`${some}(${value})`, value], errorArm(eager(0))];
+			break;
+		case "unwrap_or_else": {
+			const g = fn(0, wrapper === "option" ? 0 : 1);
+			arms = g && [
+				[`${some}(${value})`, value],
+				wrapper === "option" ? [none, g.body] : [`${none}(${g.parameter})`, g.body],
+			];
+			break;
+		}
+		case "unwrap_or_default":
+			arms = [[`${some}(${value})`, value], errorArm("Default::default()")];
+			break;
+		case "ok_or": {
+			const err = eager(0);
+			arms = [[`Some(${value})`, `Ok(${value})`], ["None", err && `Err(${err})`]];
+			break;
+		}
+		case "ok_or_else": {
+			const g = fn(0, 0);
+			arms = [[`Some(${value})`, `Ok(${value})`], ["None", g && `Err(${g.body})`]];
+			break;
+		}
+	}
+	if (!arms || arms.some(([pattern, body]) => !pattern || !body)) {
+		return undefined;
+	}
+
+	const lineStart = tree.text.lastIndexOf("\n", receiver.start - 1) + 1;
+	const indent = /^[ \t]*/.exec(tree.text.slice(lineStart))?.[0] ?? "";
+	const unit = indent.includes("\t") || (!indent && /\n\t/.test(tree.text)) ? "\t" : "    ";
+	const lines = arms.map(([pattern, body]) => `${indent}${unit}${pattern} => ${body},`);
+	let text = `match ${ts.slice(receiverFrom, dot - 1)} {\n${lines.join("\n")}\n${indent}}`;
+	// `match` can't be followed by `.method()` or `?` without parentheses
+	if ([".", "?", "[", "as"].some((p) => ts.is(close + 1, p))) {
+		text = `(${text})`;
+	}
+
+	return {
+		title: `Expand .${method}() into a match`,
+		ruleIds: [COMBINATOR_RULES.get(method) ?? "option-methods"],
+		edits: [{ start: receiver.start, end: call.end, text }],
+	};
+}
+
+/**
+ * A combinator argument as a match arm: `|x| BODY` gives pattern `x` and `BODY`,
+ * a function path `f` gives `f(value)`
+ */
+function parseFunction(
+	ts: TokenStream,
+	from: number,
+	to: number,
+	parameters: number,
+	value: string,
+): { parameter: string; body: string } | undefined {
+	let start = ts.is(from, "move") ? from + 1 : from;
+	let parameter = "";
+	if (ts.is(start, "||")) {
+		if (parameters !== 0) {
+			return undefined;
+		}
+		start++;
+	} else if (ts.is(start, "|")) {
+		let pipe = start + 1;
+		while (pipe < to && !ts.is(pipe, "|")) {
+			pipe++;
+		}
+		parameter = ts.slice(start + 1, pipe - 1);
+		// Typed or extra parameters don't fit in a pattern
+		if (
+			parameters !== 1 ||
+			pipe === start + 1 ||
+			ts.findAtDepthZero(start + 1, pipe, ":") !== -1 ||
+			ts.findAtDepthZero(start + 1, pipe, ",") !== -1
+		) {
+			return undefined;
+		}
+		start = pipe + 1;
+	} else {
+		// A function path such as `String::from`
+		for (let k = from; k <= to; k++) {
+			if ((k - from) % 2 === 0 ? !ts.isIdent(k) : !ts.is(k, "::")) {
+				return undefined;
+			}
+		}
+		if ((to - from) % 2 !== 0) {
+			return undefined;
+		}
+		const callee = ts.slice(from, to);
+		return parameters === 0
+			? { parameter, body: `${callee}()` }
+			: { parameter: value, body: `${callee}(${value})` };
+	}
+
+	if (start > to) {
+		return undefined;
+	}
+	for (let k = start; k <= to; k++) {
+		if (CONTROL_FLOW.has(ts.tokens[k].text)) {
+			return undefined;
+		}
+	}
+	return { parameter, body: ts.slice(start, to) };
+}
+
+/**
+ * Whether tokens `from..=to` produce an `Option` or a `Result`, as far as the file tells
+ */
+export function inferWrapper(
+	tree: SyntaxTree,
+	ts: TokenStream,
+	from: number,
+	to: number,
+): Wrapper | undefined {
+	const classify = (type: string | undefined): Wrapper | undefined =>
+		type === undefined
+			? undefined
+			: /(?:^|::)Option$/.test(type)
+				? "option"
+				: /Result$/.test(type)
+					? "result"
+					: undefined;
+
+	if (ts.is(to, ")")) {
+		const open = ts.openOf(to);
+		let callee = open - 1;
+		// `parse::<u32>()`
+		if (ts.is(callee, ">")) {
+			let depth = 0;
+			for (; callee > from; callee--) {
+				depth += ts.is(callee, ">") ? 1 : ts.is(callee, "<") ? -1 : 0;
+				if (depth === 0) {
+					break;
+				}
+			}
+			callee = ts.is(callee - 1, "::") ? callee - 2 : -1;
+		}
+		const name = ts.tokens[callee];
+		if (open <= from || callee < from || name?.kind !== "ident") {
+			return undefined;
+		}
+		if (name.text === "Some") {
+			return "option";
+		}
+		if (name.text === "Ok" || name.text === "Err") {
+			return "result";
+		}
+		if (OPTION_METHODS.has(name.text)) {
+			return "option";
+		}
+		if (RESULT_METHODS.has(name.text)) {
+			return "result";
+		}
+		if (PRESERVING_METHODS.has(name.text) && ts.is(callee - 1, ".")) {
+			return inferWrapper(tree, ts, from, callee - 2);
+		}
+		// A function or method declared in this file
+		const signature = new RegExp(`\\bfn\\s+${name.text}\\b[^{;]*?->\\s*([\\w:]+)`).exec(
+			tree.text,
+		);
+		return classify(signature?.[1]);
+	}
+
+	if (!ts.isIdent(to)) {
+		return undefined;
+	}
+	// Locals and parameters are declared in the function, fields anywhere in the file
+	const name = ts.tokens[to].text;
+	const field = ts.is(to - 1, ".");
+	const scope = field ? tree.text : enclosingFunctionText(tree, ts.tokens[to].start);
+	const annotated = new RegExp(`\\b${name}\\s*:\\s*&?\\s*(?:mut\\s+)?([\\w:]+)\\s*<`).exec(scope);
+	if (annotated) {
+		return classify(annotated[1]);
+	}
+	const assigned = new RegExp(
+		`\\blet\\s+(?:mut\\s+)?${name}\\s*=\\s*(Some|None|Ok|Err)\\b`,
+	).exec(scope);
+	if (!assigned) {
+		return undefined;
+	}
+	return assigned[1] === "Some" || assigned[1] === "None" ? "option" : "result";
+}
+
+function enclosingFunctionText(tree: SyntaxTree, offset: number): string {
+	let node: SyntaxNode | undefined = tree.nodeAt(offset);
+	while (node && node.kind !== "function_item") {
+		node = node.parent;
+	}
+	return node ? tree.text.slice(node.start, node.end) : tree.text;
+}
@@ -0,0 +1,9 @@
+export { findCombinatorRefactorings } from "./combinators";
+export { findLoopRefactorings } from "./loops";
+export {
+	type ErrorStyle,
+	findCallerEdit,
+	findPropagation,
+	type Propagation,
+} from "./propagate";
+export { hasComments, type Refactoring, TokenStream } from "./refactoring";
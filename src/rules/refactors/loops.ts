@@ -0,0 +1,552 @@
+import type { SyntaxNode, SyntaxTree } from "../syntax";
+import {
+	CONTROL_FLOW,
+	hasComments,
+	KEYWORDS,
+	type Refactoring,
+	TokenStream,
+} from "./refactoring";
+
+/** Methods that change a collection or value in place */
+const MUTATING_METHODS = new Set([
+	"append",
+	"clear",
+	"dedup",
+	"drain",
+	"extend",
+	"insert",
+	"iter_mut",
+	"pop",
+	"push",
+	"push_str",
+	"remove",
+	"resize",
+	"retain",
+	"reverse",
+	"sort",
+	"sort_by",
+	"sort_by_key",
+	"sort_unstable",
+	"swap",
+	"truncate",
+]);
+
+/** Methods whose result already is an iterator, so a chain can continue from it */
+const ITERATOR_METHODS = new Set([
+	"bytes",
+	"char_indices",
+	"chars",
+	"chunks",
+	"drain",
+	"enumerate",
+	"filter",
+	"into_iter",
+	"iter",
+	"iter_mut",
+	"keys",
+	"lines",
+	"map",
+	"rev",
+	"skip",
+	"split",
+	"split_whitespace",
+	"step_by",
+	"take",
+	"values",
+	"values_mut",
+	"windows",
+	"zip",
+]);
+
+const ASSIGNMENTS = new Set(["=", "+=", "-=", "*=", "/=", "%=", "^=", "&=", "|="]);
+
+/** Operators that bind at least as tightly as `+` on its right-hand side */
+const ADDITIVE_OPERATORS = new Set(["+", "-", "*", "/", "%", ".", "::", "!"]);
+
+/**
+ * Token positions of a `for PATTERN in ITERATOR { BODY }` loop
+ */
+interface ForLoop {
+	node: SyntaxNode;
+	forIndex: number;
+	inIndex: number;
+	bodyOpen: number;
+	bodyClose: number;
+}
+
+/**
+ * What a loop iterates over, as the start of an iterator chain
+ */
+interface LoopHeader {
+	/** Iterator expression the chain starts from, e.g. `items.iter()` */
+	source: string;
+	/** Closure parameter binding each item, e.g. `(i, item)` */
+	parameter: string;
+	/** Body tokens `from..=to` rewritten for the new bindings */
+	substitute: (from: number, to: number) => string;
+	ruleIds: string[];
+}
+
+/**
+ * `let mut NAME = Vec::new();` or `let mut NAME = 0;` right before a loop
+ */
+interface Accumulator {
+	name: string;
+	kind: "vec" | "number";
+	/** Declared or literal-suffix type, e.g. `Vec<String>` or `u64` */
+	type?: string;
+	init: string;
+	start: number;
+}
+
+/**
+ * Iterator-chain rewrites for the innermost `for` loop around an offset:
+ *
+ * - `for i in 0..v.len()` → `for x in v.iter()` / `for (i, x) in v.iter().enumerate()`
+ * - a loop that only pushes into a fresh `Vec` → `.map(..).collect()`
+ * - a loop that only adds to a zeroed number → `.sum()` / `.fold(..)`
+ *
+ * Only loops whose body translates exactly are offered.
+ */
+export function findLoopRefactorings(tree: SyntaxTree, offset: number): Refactoring[] {
+	let node: SyntaxNode | undefined = tree.nodeAt(offset);
+	while (node && node.kind !== "for_expression") {
+		node = node.parent;
+	}
+	if (!node?.body) {
+		return [];
+	}
+
+	const ts = new TokenStream(tree.text, tree.tokens);
+	const forIndex = ts.indexAt(node.start);
+	const bodyOpen = ts.indexAt(node.body.start);
+	const bodyClose = ts.closeOf(bodyOpen);
+	const inIndex = ts.findAtDepthZero(forIndex + 1, bodyOpen, "in");
+	// Labelled loops (`'outer: for`) may be broken out of from inside
+	if (bodyClose === -1 || inIndex === -1 || ts.is(forIndex - 1, ":")) {
+		return [];
+	}
+	const loop: ForLoop = { node, forIndex, inIndex, bodyOpen, bodyClose };
+
+	const refactorings: Refactoring[] = [];
+	const indexLoop = parseIndexLoop(ts, loop);
+	const header = indexLoop?.header ?? parsePlainHeader(ts, loop);
+	const chain = header && createChainRefactoring(tree, ts, loop, header);
+	if (chain) {
+		refactorings.push(chain);
+	}
+	if (indexLoop) {
+		refactorings.push(indexLoop.elementLoop);
+	}
+	return refactorings;
+}
+
+/**
+ * `for i in 0..items.len()` whose body only reads `items[i]`
+ */
+function parseIndexLoop(
+	ts: TokenStream,
+	loop: ForLoop,
+): { header: LoopHeader; elementLoop: Refactoring } | undefined {
+	const { forIndex, inIndex, bodyOpen, bodyClose } = loop;
+	if (inIndex !== forIndex + 2 || !ts.isIdent(forIndex + 1)) {
+		return undefined;
+	}
+	const index = ts.tokens[forIndex + 1].text;
+	const lenCall = bodyOpen - 4;
+	if (
+		ts.tokens[inIndex + 1]?.text !== "0" ||
+		!ts.is(inIndex + 2, "..") ||
+		!ts.is(lenCall, ".") ||
+		!ts.is(lenCall + 1, "len") ||
+		!ts.is(lenCall + 2, "(") ||
+		!ts.is(lenCall + 3, ")")
+	) {
+		return undefined;
+	}
+
+	// The collection: `items` or `self.items`
+	const pathStart = inIndex + 3;
+	if (!isPath(ts, pathStart, lenCall - 1)) {
+		return undefined;
+	}
+	const path = ts.tokens.slice(pathStart, lenCall).map((t) => t.text);
+
+	const uses: { start: number; end: number; kind: "ref" | "place" | "value" }[] = [];
+	let indexUsed = false;
+	// Format strings can use the index too, as in `format!("{i}")`
+	const inlineArgument = new RegExp(`\\{${index}[:}]`);
+	for (let k = bodyOpen + 1; k < bodyClose; k++) {
+		const token = ts.tokens[k];
+		if (token.kind === "string" && inlineArgument.test(token.text)) {
+			indexUsed = true;
+			continue;
+		}
+		if (token.kind !== "ident" || ts.is(k - 1, ".") || ts.is(k - 1, "::")) {
+			continue;
+		}
+		if (token.text === index) {
+			// Shadowing the index would change what `items[i]` refers to
+			if (["let", "mut", "for", "|"].some((p) => ts.is(k - 1, p))) {
+				return undefined;
+			}
+			indexUsed = true;
+			continue;
+		}
+		if (token.text !== path[0]) {
+			continue;
+		}
+
+		// Any other use of the collection (or of `self`) could conflict with borrowing it
+		const bracket = k + path.length;
+		const matchesPath = path.every((text, j) => ts.tokens[k + j]?.text === text);
+		if (
+			!matchesPath ||
+			!ts.is(bracket, "[") ||
+			!ts.is(bracket + 1, index) ||
+			!ts.is(bracket + 2, "]")
+		) {
+			return undefined;
+		}
+		const next = bracket + 3;
+		let afterFields = next;
+		while (
+			ts.is(afterFields, ".") &&
+			ts.isIdent(afterFields + 1) &&
+			!ts.is(afterFields + 2, "(")
+		) {
+			afterFields += 2;
+		}
+		const mutatingCall =
+			ts.is(next, ".") &&
+			MUTATING_METHODS.has(ts.tokens[next + 1]?.text) &&
+			ts.is(next + 2, "(");
+		if (ts.is(k - 1, "mut") || ASSIGNMENTS.has(ts.tokens[afterFields]?.text) || mutatingCall) {
+			return undefined;
+		}
+
+		// `&items[i].name` borrows the field, not the element
+		const place = ts.is(next, ".") || ts.is(next, "[");
+		const kind = place ? "place" : ts.is(k - 1, "&") ? "ref" : "value";
+		uses.push({ start: kind === "ref" ? k - 1 : k, end: bracket + 2, kind });
+		k = bracket + 2;
+	}
+	if (uses.length === 0) {
+		return undefined;
+	}
+
+	const taken = new Set(
+		ts.tokens.slice(forIndex, bodyClose).flatMap((t) => (t.kind === "ident" ? [t.text] : [])),
+	);
+	const name = elementName(path[path.length - 1], taken);
+	if (!name) {
+		return undefined;
+	}
+
+	// Field access, method calls and indexing auto-deref; other reads go through `*`
+	const replacement = (kind: string) => (kind === "value" ? `*${name}` : name);
+	const substitute = (from: number, to: number): string => {
+		let text = "";
+		let at = ts.tokens[from].start;
+		for (const use of uses.filter((u) => u.start >= from && u.end <= to)) {
+			text += ts.text.slice(at, ts.tokens[use.start].start) + replacement(use.kind);
+			at = ts.tokens[use.end].end;
+		}
+		return text + ts.text.slice(at, ts.tokens[to].end);
+	};
+
+	const collection = path.join("");
+	const parameter = indexUsed ? `(${index}, ${name})` : name;
+	const header: LoopHeader = {
+		source: indexUsed ? `${collection}.iter().enumerate()` : `${collection}.iter()`,
+		parameter,
+		substitute,
+		ruleIds: indexUsed ? ["enumerate"] : [],
+	};
+
+	const elementLoop: Refactoring = {
+		title: indexUsed
+			? "Loop with .iter().enumerate() instead of indexing"
+			: "Loop over the elements instead of indexing",
+		ruleIds: indexUsed ? ["enumerate", "for-in-loop"] : ["for-in-loop"],
+		edits: [
+			{
+				start: ts.tokens[forIndex + 1].start,
+				end: ts.tokens[bodyOpen - 1].end,
+				text: `${parameter} in ${header.source}`,
+			},
+			...uses.map((use) => ({
+				start: ts.tokens[use.start].start,
+				end: ts.tokens[use.end].end,
+				text: replacement(use.kind),
+			})),
+		],
+	};
+
+	return { header, elementLoop };
+}
+
+/**
+ * Any other `for PATTERN in ITERATOR`: the body is used as written
+ */
+function parsePlainHeader(ts: TokenStream, loop: ForLoop): LoopHeader | undefined {
+	const { forIndex, inIndex, bodyOpen } = loop;
+	if (inIndex === forIndex + 1 || bodyOpen === inIndex + 1) {
+		return undefined;
+	}
+	return {
+		source: iteratorSource(ts, inIndex + 1, bodyOpen - 1),
+		parameter: ts.slice(forIndex + 1, inIndex - 1),
+		substitute: (from, to) => ts.slice(from, to),
+		ruleIds: [],
+	};
+}
+
+/**
+ * The iterator a `for` loop gets from `ITERATOR` (tokens `from..=to`), written so a chain
+ * can continue from it: `&v` → `v.iter()`, `v` → `v.into_iter()`, `0..n` → `(0..n)`
+ */
+function iteratorSource(ts: TokenStream, from: number, to: number): string {
+	const text = ts.slice(from, to);
+	if (ts.is(from, "&")) {
+		const mutable = ts.is(from + 1, "mut");
+		const start = mutable ? from + 2 : from + 1;
+		if (isPath(ts, start, to)) {
+			return `${ts.slice(start, to)}.${mutable ? "iter_mut" : "iter"}()`;
+		}
+		return `(${text}).into_iter()`;
+	}
+	if (isPath(ts, from, to)) {
+		return `${text}.into_iter()`;
+	}
+	if (ts.is(to, ")")) {
+		const open = ts.openOf(to);
+		const method = ts.tokens[open - 1];
+		if (open > from + 1 && ts.is(open - 2, ".") && ITERATOR_METHODS.has(method.text)) {
+			return text;
+		}
+	}
+	if (
+		ts.findAtDepthZero(from, to + 1, "..") !== -1 ||
+		ts.findAtDepthZero(from, to + 1, "..=") !== -1
+	) {
+		return `(${text})`;
+	}
+	return `(${text}).into_iter()`;
+}
+
+/**
+ * Whether tokens `from..=to` are a plain path like `items` or `self.items`
+ */
+function isPath(ts: TokenStream, from: number, to: number): boolean {
+	if (to < from || (to - from) % 2 !== 0) {
+		return false;
+	}
+	for (let k = from; k <= to; k++) {
+		if ((k - from) % 2 === 0 ? !ts.isIdent(k) : !ts.is(k, ".")) {
+			return false;
+		}
+	}
+	return true;
+}
+
+/**
+ * `let mut out = Vec::new(); for .. { out.push(EXPR); }` → `.map(..).collect()`,
+ * `let mut total = 0; for .. { total += EXPR; }` → `.sum()` or `.fold(..)`
+ */
+function createChainRefactoring(
+	tree: SyntaxTree,
+	ts: TokenStream,
+	loop: ForLoop,
+	header: LoopHeader,
+): Refactoring | undefined {
+	const accumulator = findAccumulator(ts, loop.forIndex);
+	if (!accumulator || hasComments(tree.tokens, accumulator.start, loop.node.end)) {
+		return undefined;
+	}
+
+	// The body must be a single statement
+	let last = loop.bodyClose - 1;
+	if (ts.is(last, ";")) {
+		last--;
+	}
+	const first = loop.bodyOpen + 1;
+	if (last < first || ts.findAtDepthZero(first, last + 1, ";") !== -1) {
+		return undefined;
+	}
+	if (!ts.is(first, accumulator.name)) {
+		return undefined;
+	}
+
+	let expressionFrom: number;
+	let expressionTo: number;
+	if (accumulator.kind === "vec") {
+		const open = first + 3;
+		if (!ts.is(first + 1, ".") || !ts.is(first + 2, "push") || ts.closeOf(open) !== last) {
+			return undefined;
+		}
+		expressionFrom = open + 1;
+		expressionTo = last - 1;
+	} else {
+		if (!ts.is(first + 1, "+=")) {
+			return undefined;
+		}
+		expressionFrom = first + 2;
+		expressionTo = last;
+	}
+	if (expressionTo < expressionFrom) {
+		return undefined;
+	}
+
+	for (let k = expressionFrom; k <= expressionTo; k++) {
+		const token = ts.tokens[k];
+		if (CONTROL_FLOW.has(token.text)) {
+			return undefined;
+		}
+		if (token.text === accumulator.name && token.kind === "ident" && !ts.is(k - 1, ".")) {
+			return undefined;
+		}
+	}
+
+	const { source, parameter } = header;
+	const { name, init, type } = accumulator;
+	const expression = header.substitute(expressionFrom, expressionTo);
+	// `out.push(x)` for `x`; `out.push(*x)` for `x` or `out.push(x)` for `&x` copy items out
+	const identity = expression === parameter;
+	const copied = expression === `*${parameter}` || parameter === `&${expression}`;
+	const mapped = `${source}.map(|${parameter}| ${expression})`;
+	const binding = isMutatedLater(ts, loop.bodyClose + 1, name) ? `mut ${name}` : name;
+	const replace = (title: string, text: string): Refactoring => ({
+		title,
+		ruleIds: ["map-filter-fold", ...header.ruleIds],
+		edits: [{ start: accumulator.start, end: loop.node.end, text }],
+	});
+
+	if (accumulator.kind === "vec") {
+		const chain = identity ? source : copied ? `${source}.copied()` : mapped;
+		return replace(
+			identity || copied ? "Convert loop to .collect()" : "Convert loop to .map().collect()",
+			`let ${binding}: ${type ?? "Vec<_>"} = ${chain}.collect();`,
+		);
+	}
+
+	// `sum` needs to know the type; without one, `fold` infers it like the loop did
+	if (type) {
+		const chain = identity || copied ? source : mapped;
+		return replace("Convert loop to .sum()", `let ${binding}: ${type} = ${chain}.sum();`);
+	}
+	const operators: string[] = [];
+	for (let k = expressionFrom; k <= expressionTo; k++) {
+		if (ts.tokens[k].kind === "punct") {
+			operators.push(ts.tokens[k].text);
+		}
+		const close = ts.closeOf(k);
+		if (close !== -1) {
+			k = close;
+		}
+	}
+	const operand = operators.every((p) => ADDITIVE_OPERATORS.has(p))
+		? expression
+		: `(${expression})`;
+	return replace(
+		"Convert loop to .fold()",
+		`let ${binding} = ${source}.fold(${init}, |${name}, ${parameter}| ${name} + ${operand});`,
+	);
+}
+
+/**
+ * The `let mut` statement right before the loop, if it starts an empty `Vec` or a zero
+ */
+function findAccumulator(ts: TokenStream, forIndex: number): Accumulator | undefined {
+	if (!ts.is(forIndex - 1, ";")) {
+		return undefined;
+	}
+	let k = forIndex - 2;
+	while (k >= 0 && !ts.is(k, ";") && !ts.is(k, "{") && !ts.is(k, "}")) {
+		k--;
+	}
+	const letIndex = k + 1;
+	if (!ts.is(letIndex, "let") || !ts.is(letIndex + 1, "mut") || !ts.isIdent(letIndex + 2)) {
+		return undefined;
+	}
+	const equals = ts.findAtDepthZero(letIndex + 3, forIndex - 1, "=");
+	if (equals === -1 || equals === forIndex - 2) {
+		return undefined;
+	}
+	if (equals !== letIndex + 3 && !ts.is(letIndex + 3, ":")) {
+		return undefined;
+	}
+
+	const name = ts.tokens[letIndex + 2].text;
+	const declared = equals > letIndex + 3 ? ts.slice(letIndex + 4, equals - 1) : undefined;
+	const init = ts.slice(equals + 1, forIndex - 2);
+	const start = ts.tokens[letIndex].start;
+
+	const vec = /^(?:Vec(?:::<(.+)>)?::(?:new\(\)|with_capacity\(.*\))|vec!\[\])$/s.exec(
+		init.replace(/\s+/g, ""),
+	);
+	if (vec) {
+		const type = declared ?? (vec[1] ? `Vec<${vec[1]}>` : undefined);
+		return { name, kind: "vec", type, init, start };
+	}
+
+	const zero = /^0+(?:\.0*)?_*((?:[iu](?:8|16|32|64|128|size))|f32|f64)?$/.exec(init);
+	if (zero && equals === forIndex - 3) {
+		return { name, kind: "number", type: declared ?? zero[1], init, start };
+	}
+	return undefined;
+}
+
+/**
+ * Whether the accumulator is changed after the loop, so it must stay `mut`
+ */
+function isMutatedLater(ts: TokenStream, from: number, name: string): boolean {
+	let depth = 0;
+	for (let k = from; k < ts.tokens.length; k++) {
+		const token = ts.tokens[k];
+		if (token.kind === "punct" && "([{".includes(token.text)) {
+			depth++;
+		} else if (token.kind === "punct" && ")]}".includes(token.text)) {
+			// End of the block the accumulator lives in
+			if (depth === 0) {
+				return false;
+			}
+			depth--;
+		}
+		if (token.kind !== "ident" || token.text !== name || ts.is(k - 1, ".")) {
+			continue;
+		}
+		if (ts.is(k - 1, "let")) {
+			return false;
+		}
+		const indexed = ts.is(k + 1, "[") ? ts.closeOf(k + 1) + 1 : k + 1;
+		const method = ts.is(k + 1, ".") ? ts.tokens[k + 2]?.text : undefined;
+		if (
+			ts.is(k - 1, "mut") ||
+			ASSIGNMENTS.has(ts.tokens[indexed]?.text) ||
+			(method !== undefined && MUTATING_METHODS.has(method))
+		) {
+			return true;
+		}
+	}
+	return false;
+}
+
+/**
+ * A name for one element of a collection: `tokens` → `token`, else `item`
+ */
+function elementName(collection: string, taken: Set<string>): string | undefined {
+	let singular: string | undefined;
+	if (/[^aeiou]ies$/.test(collection)) {
+		singular = `${collection.slice(0, -3)}y`;
+	} else if (/(?:ch|sh|x|ss)es$/.test(collection)) {
+		singular = collection.slice(0, -2);
+	} else if (/[^s]s$/.test(collection)) {
+		singular = collection.slice(0, -1);
+	} else if (collection === "children") {
+		singular = "child";
+	}
+	return [singular, "item", "element", "x"].find(
+		(name): name is string => name !== undefined && !taken.has(name) && !KEYWORDS.has(name),
+	);
+}
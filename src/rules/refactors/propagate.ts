@@ -0,0 +1,388 @@
+import { type FixEdit, importEdit } from "../fixes";
+import type { SyntaxNode, SyntaxTree } from "../syntax";
+import { inferWrapper } from "./combinators";
+import { type Refactoring, TokenStream } from "./refactoring";
+
+/**
+ * Error type a function gets when it starts returning `Result`:
+ * `anyhow::Result<T>` or `Result<T, Box<dyn std::error::Error>>`
+ */
+export type ErrorStyle = "anyhow" | "box";
+
+/**
+ * Replacing `.unwrap()` / `.expect(..)` with `?`, plus the signature change that needs
+ */
+export interface Propagation extends Refactoring {
+	/** Offset of the function's name when its return type changes, so callers need updating */
+	changedFunction?: number;
+	/** Span of the whole function, whose own recursive calls are already taken care of */
+	functionSpan: { start: number; end: number };
+}
+
+const BLOCK_KEYWORDS = new Set(["for", "while", "loop", "if", "match", "unsafe"]);
+
+/**
+ * Propagate the error of the `.unwrap()` or `.expect(..)` around an offset with `?`:
+ *
+ * - the call becomes `?`, `.ok()?`, `.context(..)?`, `.ok_or(..)?` or `.map_err(..)?`,
+ *   depending on what it is called on and what the function returns
+ * - a function not returning `Result` (or `Option`) gets a `Result` return type, with its
+ *   tail expression and `return` values wrapped in `Ok(..)`
+ *
+ * Not offered inside closures and async blocks, where `?` would apply to those instead,
+ * or in trait impls, whose signatures can't change.
+ */
+export function findPropagation(
+	tree: SyntaxTree,
+	offset: number,
+	style: ErrorStyle,
+): Propagation | undefined {
+	let call: SyntaxNode | undefined = tree.nodeAt(offset);
+	while (call && !(call.kind === "method_call" && isPanickingCall(call))) {
+		call = call.parent;
+	}
+	let fn = call?.parent;
+	while (fn && fn.kind !== "function_item") {
+		fn = fn.parent;
+	}
+	// `?` isn't allowed in `const fn`
+	if (!call?.receiver || !fn?.body || isTraitMethod(fn) || fn.modifiers?.includes("const")) {
+		return undefined;
+	}
+
+	const ts = new TokenStream(tree.text, tree.tokens);
+	const bodyOpen = ts.indexAt(fn.body.start);
+	const bodyClose = ts.closeOf(bodyOpen);
+	const dot = ts.indexAt(call.start);
+	if (bodyClose === -1 || inClosure(ts, dot, bodyOpen)) {
+		return undefined;
+	}
+
+	const returnType = fn.returnType?.trim();
+	const returns = outerType(returnType);
+	const wrapper = inferWrapper(tree, ts, ts.indexAt(call.receiver.start), dot - 1);
+	const changesSignature = returns !== "Result" && returns !== "Option";
+	// Error type of the function after the change
+	const target: ErrorStyle | undefined = changesSignature
+		? style
+		: /\banyhow\b/.test(returnType ?? "")
+			? "anyhow"
+			: /\bBox\s*<\s*dyn\b/.test(returnType ?? "")
+				? "box"
+				: undefined;
+
+	const slice = (span: { start: number; end: number }) => ts.text.slice(span.start, span.end);
+	const message = call.name === "expect" && call.arguments ? slice(call.arguments) : undefined;
+	const receiver = slice(call.receiver);
+	const subject = /^[^"\\\n]{1,40}$/.test(receiver) ? `\`${receiver}\`` : "value";
+	let replacement: string;
+	let needsContext = false;
+	if (returns === "Option") {
+		replacement = wrapper === "result" ? ".ok()?" : "?";
+	} else if (wrapper === "option") {
+		const reason = message ?? `"${subject} was None"`;
+		if (target === "anyhow") {
+			replacement = `.context(${reason})?`;
+			needsContext = true;
+		} else if (target === "box") {
+			replacement = `.ok_or(${reason})?`;
+		} else {
+			// Whether the function's own error type converts from anything is unknown
+			return undefined;
+		}
+	} else if (message && target === "anyhow") {
+		replacement = `.context(${message})?`;
+		needsContext = true;
+	} else if (message && target === "box") {
+		replacement = `.map_err(|e| ${withError(message)})?`;
+	} else {
+		replacement = "?";
+	}
+
+	const edits: FixEdit[] = [];
+	const importsContext = /\buse\s+anyhow::(?:Context\b|\{[^}]*\bContext\b)/.test(tree.text);
+	if (needsContext && !importsContext) {
+		edits.push(importEdit(tree, "use anyhow::Context;"));
+	}
+	if (changesSignature) {
+		const signature = signatureEdit(tree, ts, fn, bodyOpen, style);
+		if (!signature) {
+			return undefined;
+		}
+		edits.push(signature);
+	}
+	edits.push({ start: call.start, end: call.end, text: replacement });
+	if (changesSignature) {
+		edits.push(...okEdits(tree, ts, bodyOpen, bodyClose, returns === "()"));
+	}
+	edits.sort((a, b) => a.start - b.start);
+
+	return {
+		title: "Propagate with ?",
+		ruleIds: ["question-mark-operator", "unwrap-usage"],
+		edits,
+		changedFunction:
+			changesSignature && fn.name !== "main" ? functionNameOffset(ts, fn) : undefined,
+		functionSpan: { start: fn.start, end: fn.end },
+	};
+}
+
+/**
+ * The edit for a call to a function that now returns `Result`: `?` where the caller's own
+ * return type takes the error, `.unwrap()` (which panics just as before) elsewhere
+ */
+export function findCallerEdit(
+	tree: SyntaxTree,
+	offset: number,
+	style: ErrorStyle,
+): FixEdit | undefined {
+	const ts = new TokenStream(tree.text, tree.tokens);
+	const name = ts.indexAt(offset);
+	// The definition itself, imports and function pointers aren't calls
+	if (!ts.isIdent(name) || ts.is(name - 1, "fn") || !ts.is(name + 1, "(")) {
+		return undefined;
+	}
+	const close = ts.closeOf(name + 1);
+	if (close === -1) {
+		return undefined;
+	}
+
+	let fn: SyntaxNode | undefined = tree.nodeAt(offset);
+	while (fn && fn.kind !== "function_item") {
+		fn = fn.parent;
+	}
+	const returnType = fn?.returnType ?? "";
+	const takesError =
+		style === "anyhow" ? /\banyhow\b/.test(returnType) : /\bBox\s*<\s*dyn\b/.test(returnType);
+	const propagate =
+		fn?.body !== undefined && takesError && !inClosure(ts, name, ts.indexAt(fn.body.start));
+
+	const end = ts.tokens[close].end;
+	return { start: end, end, text: propagate ? "?" : ".unwrap()" };
+}
+
+function isPanickingCall(node: SyntaxNode): boolean {
+	return (
+		(node.name === "unwrap" && node.argumentCount === 0) ||
+		(node.name === "expect" && node.argumentCount === 1)
+	);
+}
+
+function isTraitMethod(fn: SyntaxNode): boolean {
+	return fn.parent?.kind === "impl_item" && fn.parent.traitPath !== undefined;
+}
+
+/**
+ * Whether a token sits in a closure or async block within the function body at `bodyOpen`
+ */
+function inClosure(ts: TokenStream, index: number, bodyOpen: number): boolean {
+	// Closure parameters only count until the statement the token is in started
+	let statementStarted = false;
+	for (let j = index - 1; j > bodyOpen; j--) {
+		const open = ts.openOf(j);
+		if (open !== -1) {
+			j = open;
+			continue;
+		}
+		if (ts.is(j, "(") || ts.is(j, "[") || ts.is(j, "{")) {
+			const before = ["|", "||", "move", "async"].some((p) => ts.is(j - 1, p));
+			if (ts.is(j, "{") && before) {
+				return true;
+			}
+			statementStarted = false;
+		} else if (ts.is(j, ";")) {
+			statementStarted = true;
+		} else if (!statementStarted && (ts.is(j, "|") || ts.is(j, "||"))) {
+			return true;
+		}
+	}
+	return false;
+}
+
+/**
+ * `io::Result<()>` → `Result`, none → `()`
+ */
+function outerType(returnType: string | undefined): string {
+	if (!returnType || returnType.replace(/\s+/g, "") === "()") {
+		return "()";
+	}
+	return returnType.replace(/<[\s\S]*$/, "").split("::").pop()?.trim() ?? returnType;
+}
+
+/**
+ * `"msg"` → `format!("msg: {e}")`, so the message isn't lost
+ */
+function withError(message: string): string {
+	if (/^"(?:[^"\\{}]|\\.)*"$/.test(message)) {
+		return `format!("${message.slice(1, -1)}: {e}")`;
+	}
+	return `format!("{}: {e}", ${message})`;
+}
+
+function functionNameOffset(ts: TokenStream, fn: SyntaxNode): number | undefined {
+	const start = ts.indexAt(fn.start);
+	for (let k = start; k < ts.tokens.length && ts.tokens[k].start < fn.end; k++) {
+		if (ts.is(k, "fn") && ts.is(k + 1, fn.name ?? "")) {
+			return ts.tokens[k + 1].start;
+		}
+	}
+	return undefined;
+}
+
+/**
+ * Add `-> Result<..>` or wrap the declared return type in one
+ */
+function signatureEdit(
+	tree: SyntaxTree,
+	ts: TokenStream,
+	fn: SyntaxNode,
+	bodyOpen: number,
+	style: ErrorStyle,
+): FixEdit | undefined {
+	const name = functionNameOffset(ts, fn);
+	if (name === undefined || !fn.parameters) {
+		return undefined;
+	}
+	const paramsClose = ts.indexAt(fn.parameters.end);
+	if (!ts.is(paramsClose, ")")) {
+		return undefined;
+	}
+	const returnType = fn.returnType?.trim() || "()";
+
+	// Keep clear of a `Result` alias or import that takes a different number of arguments
+	const resultShadowed =
+		/\btype\s+Result\b|\buse\s+[\w:]*::Result\s*;|\buse\s+[\w:]*::\{[^}]*\bResult\b/.test(
+			tree.text,
+		);
+	const result = resultShadowed ? "std::result::Result" : "Result";
+	const errorTrait = /\buse\s+std::error::Error\s*;/.test(tree.text)
+		? "Error"
+		: "std::error::Error";
+	const type =
+		style === "anyhow"
+			? `anyhow::Result<${returnType}>`
+			: `${result}<${returnType}, Box<dyn ${errorTrait}>>`;
+
+	if (!ts.is(paramsClose + 1, "->")) {
+		const end = ts.tokens[paramsClose].end;
+		return { start: end, end, text: ` -> ${type}` };
+	}
+	let typeEnd = bodyOpen - 1;
+	const where = ts.findAtDepthZero(paramsClose + 2, bodyOpen, "where");
+	if (where !== -1) {
+		typeEnd = where - 1;
+	}
+	return {
+		start: ts.tokens[paramsClose + 2].start,
+		end: ts.tokens[typeEnd].end,
+		text: type,
+	};
+}
+
+/**
+ * Wrap what the body returns in `Ok(..)`: the tail expression (or a new `Ok(())` for
+ * functions returning nothing) and every `return` outside closures and nested functions
+ */
+function okEdits(
+	tree: SyntaxTree,
+	ts: TokenStream,
+	bodyOpen: number,
+	bodyClose: number,
+	unit: boolean,
+): FixEdit[] {
+	const edits: FixEdit[] = [];
+	const nested = tree.nodes.filter(
+		(n) =>
+			n.kind === "function_item" &&
+			n.start > ts.tokens[bodyOpen].start &&
+			n.end <= ts.tokens[bodyClose].end,
+	);
+
+	for (let k = bodyOpen + 1; k < bodyClose; k++) {
+		if (!ts.is(k, "return")) {
+			continue;
+		}
+		const offset = ts.tokens[k].start;
+		if (nested.some((n) => offset >= n.start && offset < n.end) || inClosure(ts, k, bodyOpen)) {
+			continue;
+		}
+		const end = expressionEnd(ts, k + 1, bodyClose);
+		if (end === k) {
+			edits.push({ start: ts.tokens[k].end, end: ts.tokens[k].end, text: " Ok(())" });
+		} else {
+			edits.push(
+				{ start: ts.tokens[k + 1].start, end: ts.tokens[k + 1].start, text: "Ok(" },
+				{ start: ts.tokens[end].end, end: ts.tokens[end].end, text: ")" },
+			);
+		}
+	}
+
+	const tail = tailStart(ts, bodyOpen, bodyClose);
+	if (unit) {
+		const lineStart = tree.text.lastIndexOf("\n", ts.tokens[bodyClose].start - 1) + 1;
+		const closeIndent = /^[ \t]*/.exec(tree.text.slice(lineStart))?.[0] ?? "";
+		const indent = closeIndent + (closeIndent.includes("\t") ? "\t" : "    ");
+		const last = ts.tokens[bodyClose - 1];
+		const semicolon = tail !== undefined && !ts.is(bodyClose - 1, "}") ? ";" : "";
+		const sameLine = !tree.text.slice(last.end, ts.tokens[bodyClose].start).includes("\n");
+		edits.push({
+			start: last.end,
+			end: last.end,
+			text: sameLine ? `${semicolon} Ok(())` : `${semicolon}\n${indent}Ok(())`,
+		});
+	} else if (tail !== undefined && !ts.is(tail, "return")) {
+		const start = ts.tokens[tail].start;
+		const end = ts.tokens[bodyClose - 1].end;
+		edits.push({ start, end: start, text: "Ok(" }, { start: end, end, text: ")" });
+	}
+	return edits;
+}
+
+/**
+ * Last token of the expression starting at `from`: before the next `;` or `,` at its
+ * level, or the bracket closing it. `from - 1` when there is no expression.
+ */
+function expressionEnd(ts: TokenStream, from: number, limit: number): number {
+	let k = from;
+	while (k < limit && !ts.is(k, ";") && !ts.is(k, ",") && ts.openOf(k) === -1) {
+		const close = ts.closeOf(k);
+		k = close === -1 ? k + 1 : close + 1;
+	}
+	return k - 1;
+}
+
+/**
+ * First token of the body's tail expression, skipping statements and block-like
+ * expression statements such as loops; `undefined` when the body ends in a statement
+ */
+function tailStart(ts: TokenStream, bodyOpen: number, bodyClose: number): number | undefined {
+	let start = bodyOpen + 1;
+	for (let k = start; k < bodyClose; k++) {
+		if (ts.is(k, ";")) {
+			start = k + 1;
+			continue;
+		}
+		const close = ts.closeOf(k);
+		if (close !== -1) {
+			k = close;
+		}
+	}
+
+	const blockEnd = (from: number) => {
+		const open = ts.is(from, "{") ? from : ts.findAtDepthZero(from, bodyClose, "{");
+		return open === -1 ? -1 : ts.closeOf(open);
+	};
+	// A `for`, `while`, `if ..` or `match` block followed by more code is a statement
+	while (start < bodyClose && (BLOCK_KEYWORDS.has(ts.tokens[start].text) || ts.is(start, "{"))) {
+		let end = blockEnd(start);
+		// `else if .. { }` and `else { }` continue the same expression
+		while (end !== -1 && ts.is(end + 1, "else")) {
+			end = blockEnd(end + 2);
+		}
+		if (end === -1 || end + 1 >= bodyClose) {
+			break;
+		}
+		start = end + 1;
+	}
+	return start < bodyClose ? start : undefined;
+}
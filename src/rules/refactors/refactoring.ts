@@ -0,0 +1,162 @@
+import type { FixEdit } from "../fixes";
+import type { Token } from "../syntax";
+
+/**
+ * A rewrite offered as a refactor code action
+ */
+export interface Refactoring {
+	title: string;
+	/** Rules whose learn pages explain the rewritten code */
+	ruleIds: string[];
+	edits: FixEdit[];
+}
+
+/** Tokens that would mean something else once moved into a closure, or out of one */
+export const CONTROL_FLOW = new Set(["return", "break", "continue", "?", "await", "yield"]);
+
+export const KEYWORDS = new Set([
+	"as",
+	"async",
+	"await",
+	"break",
+	"const",
+	"continue",
+	"crate",
+	"dyn",
+	"else",
+	"enum",
+	"false",
+	"fn",
+	"for",
+	"if",
+	"impl",
+	"in",
+	"let",
+	"loop",
+	"match",
+	"mod",
+	"move",
+	"mut",
+	"pub",
+	"ref",
+	"return",
+	"self",
+	"static",
+	"struct",
+	"super",
+	"trait",
+	"true",
+	"type",
+	"unsafe",
+	"use",
+	"where",
+	"while",
+	"yield",
+]);
+
+const OPENERS: Record<string, string> = { "(": ")", "[": "]", "{": "}" };
+
+/**
+ * The code tokens of a file (comments left out) with their brackets paired up
+ */
+export class TokenStream {
+	public readonly tokens: Token[];
+	private readonly closeIndex: number[];
+	private readonly openIndex: number[];
+
+	constructor(
+		public readonly text: string,
+		tokens: readonly Token[],
+	) {
+		this.tokens = tokens.filter((t) => t.kind !== "comment");
+		this.closeIndex = new Array(this.tokens.length).fill(-1);
+		this.openIndex = new Array(this.tokens.length).fill(-1);
+
+		const stack: number[] = [];
+		this.tokens.forEach((token, i) => {
+			if (token.kind !== "punct") {
+				return;
+			}
+			if (token.text in OPENERS) {
+				stack.push(i);
+			} else if (")]}".includes(token.text)) {
+				const open = stack.pop();
+				if (open !== undefined && OPENERS[this.tokens[open].text] === token.text) {
+					this.closeIndex[open] = i;
+					this.openIndex[i] = open;
+				}
+			}
+		});
+	}
+
+	/**
+	 * Index of the first token starting at or after an offset
+	 */
+	public indexAt(offset: number): number {
+		const index = this.tokens.findIndex((t) => t.start >= offset);
+		return index === -1 ? this.tokens.length : index;
+	}
+
+	/**
+	 * Index of the bracket closing the one at `open`, or -1
+	 */
+	public closeOf(open: number): number {
+		return this.closeIndex[open] ?? -1;
+	}
+
+	/**
+	 * Index of the bracket opening the one at `close`, or -1
+	 */
+	public openOf(close: number): number {
+		return this.openIndex[close] ?? -1;
+	}
+
+	/**
+	 * Whether token `i` is punctuation or an identifier with exactly this text
+	 */
+	public is(i: number, text: string): boolean {
+		const token = this.tokens[i];
+		return (
+			token !== undefined &&
+			(token.kind === "punct" || token.kind === "ident") &&
+			token.text === text
+		);
+	}
+
+	public isIdent(i: number): boolean {
+		return this.tokens[i]?.kind === "ident";
+	}
+
+	/**
+	 * Source text from token `from` to token `to` inclusive
+	 */
+	public slice(from: number, to: number): string {
+		if (to < from) {
+			return "";
+		}
+		return this.text.slice(this.tokens[from].start, this.tokens[to].end);
+	}
+
+	/**
+	 * First index in `from..to` holding `text` outside any brackets, or -1
+	 */
+	public findAtDepthZero(from: number, to: number, text: string): number {
+		for (let i = from; i < to; i++) {
+			if (this.is(i, text)) {
+				return i;
+			}
+			const close = this.closeOf(i);
+			if (close !== -1) {
+				i = close;
+			}
+		}
+		return -1;
+	}
+}
+
+/**
+ * Whether a source range has comments a rewrite of it would drop
+ */
+export function hasComments(tokens: readonly Token[], start: number, end: number): boolean {
+	return tokens.some((t) => t.kind === "comment" && t.start >= start && t.end <= end);
+}
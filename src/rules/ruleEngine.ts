@@ -1,11 +1,45 @@
 import * as fs from "node:fs";
 import * as path from "node:path";
 import type * as vscode from "vscode";
-import type { ProjectContext, Rule, RuleFile, RuleMatch } from "./types";
+import { type ResolvedFix, resolveFix } from "./fixes";
+import { type RuleExclusions, validateRule } from "./ruleValidator";
+import { satisfiesVersion } from "./semver";
+import {
+	getScope,
+	maskSource,
+	maskTomlComments,
+	matchesScope,
+	type ScopeInfo,
+	type Span,
+	Suppressions,
+	type SyntaxNode,
+	SyntaxTree,
+} from "./syntax";
+import type {
+	DependencyRequirement,
+	ProjectContext,
+	ProjectDependencies,
+	Rule,
+	RuleFile,
+	RuleLoadIssue,
+	RuleLoadReport,
+	RuleMatch,
+	RuleSettings,
+	RuleUserSettings,
+} from "./types";
+
+const MAX_CACHED_DOCUMENTS = 10;
+
+/** Default for the `rustCompass.minConfidence` setting */
+export const DEFAULT_MIN_CONFIDENCE = 0.7;
+
+/** Edition assumed for files that don't belong to a Cargo package */
+const LATEST_EDITION = "2024";
 
 export class RuleEngine {
 	private rules: Rule[] = [];
 	private compiledPatterns: Map<string, RegExp> = new Map();
+	private compiledExclusions: Map<string, RuleExclusions> = new Map();
 
 	// Cache matches per document version to avoid re-scanning
 	private matchCache: Map<
@@ -13,42 +47,152 @@ export class RuleEngine {
 		{ version: number; context: ProjectContext; matches: RuleMatch[] }
 	> = new Map();
 
-	constructor(private extensionPath: string) {
+	// Parsed syntax trees per document version, shared by structural rules
+	private syntaxTreeCache: Map<string, { version: number; tree: SyntaxTree }> = new Map();
+
+	// Where each rule was loaded from, plus what the last load did
+	private ruleSources: Map<string, { file: string; workspace: boolean }> = new Map();
+	private loadReport: RuleLoadReport = { files: [], issues: [], overrides: [] };
+
+	// Unknown until Cargo.toml has been scanned; dependency-gated rules stay hidden until then
+	private getDependencies: ((uri: vscode.Uri) => ProjectDependencies) | null = null;
+
+	// User overrides from `rustCompass.rules` / `rustCompass.minConfidence`
+	private settings: RuleSettings = { minConfidence: DEFAULT_MIN_CONFIDENCE, rules: {} };
+
+	constructor(
+		private extensionPath: string,
+		private getWorkspaceRuleDirectories: () => string[] = () => [],
+	) {
 		this.loadRules();
 	}
 
 	private loadRules(): void {
 		const rulesDir = path.join(this.extensionPath, "rules");
 
-		if (!fs.existsSync(rulesDir)) {
+		if (fs.existsSync(rulesDir)) {
+			this.loadRuleDirectory(rulesDir, false);
+		} else {
 			console.warn("Rules directory not found:", rulesDir);
-			return;
 		}
 
-		const files = fs.readdirSync(rulesDir).filter((f) => f.endsWith(".json"));
+		// Workspace rule packs load last so they can override bundled rules
+		for (const dir of this.getWorkspaceRuleDirectories()) {
+			if (fs.existsSync(dir)) {
+				this.loadRuleDirectory(dir, true);
+			}
+		}
+
+		console.log(`Total rules loaded: ${this.rules.length}`);
+	}
+
+	private loadRuleDirectory(rulesDir: string, isWorkspace: boolean): void {
+		let files: string[];
+		try {
+			files = fs.readdirSync(rulesDir).filter((f) => f.endsWith(".json"));
+		} catch (e) {
+			// e.g. a `ruleDirectories` entry that is a file or can't be read
+			console.error(`Error reading rule directory ${rulesDir}:`, e);
+			this.loadReport.issues.push({
+				severity: "error",
+				file: rulesDir,
+				message: `Could not read rule directory: ${e instanceof Error ? e.message : e}`,
+				skipped: true,
+			});
+			return;
+		}
 
 		for (const file of files) {
+			const filePath = path.join(rulesDir, file);
+			const summary = { file: filePath, loaded: 0, skipped: 0, workspace: isWorkspace };
+			this.loadReport.files.push(summary);
+
+			let content: string;
+			let ruleFile: RuleFile;
 			try {
-				const content = fs.readFileSync(path.join(rulesDir, file), "utf-8");
-				const ruleFile: RuleFile = JSON.parse(content);
-				this.rules.push(...ruleFile.rules);
+				content = fs.readFileSync(filePath, "utf-8");
+				ruleFile = JSON.parse(content);
+				if (!Array.isArray(ruleFile.rules)) {
+					throw new Error(`expected a "rules" array`);
+				}
+			} catch (e) {
+				console.error(`Error loading rule file ${filePath}:`, e);
+				this.loadReport.issues.push({
+					severity: "error",
+					file: filePath,
+					message: `Could not load rule file: ${e instanceof Error ? e.message : e}`,
+					skipped: true,
+				});
+				continue;
+			}
 
-				// Pre-compile patterns
-				for (const rule of ruleFile.rules) {
-					try {
-						this.compiledPatterns.set(rule.id, new RegExp(rule.pattern, "g"));
-					} catch (e) {
-						console.error(`Invalid pattern in rule ${rule.id}:`, e);
+			// Rules appear in file order, so each id is searched for after the previous one
+			let searchFrom = 0;
+			for (const rule of ruleFile.rules) {
+				const position = findRulePosition(content, rule.id, searchFrom);
+				if (position.offset !== undefined) {
+					searchFrom = position.offset + 1;
+				}
+				const report = (severity: RuleLoadIssue["severity"], message: string) => {
+					this.loadReport.issues.push({
+						severity,
+						file: filePath,
+						ruleId: rule.id,
+						message,
+						skipped: severity === "error",
+						line: position.line,
+						character: position.character,
+					});
+				};
+
+				const { errors, warnings, compiledPattern, exclusions } = validateRule(rule);
+				for (const warning of warnings) {
+					report("warning", warning);
+				}
+
+				// Same id twice is only allowed when a workspace pack replaces a bundled rule
+				const previous = this.ruleSources.get(rule.id);
+				if (previous && (previous.workspace || !isWorkspace)) {
+					errors.push(`Duplicate rule id, already defined in ${previous.file}`);
+				}
+
+				if (errors.length > 0) {
+					for (const error of errors) {
+						report("error", error);
 					}
+					console.error(`Skipping rule ${rule.id} in ${filePath}: ${errors.join("; ")}`);
+					summary.skipped++;
+					continue;
 				}
 
-				console.log(`Loaded ${ruleFile.rules.length} rules from ${file}`);
-			} catch (e) {
-				console.error(`Error loading rule file ${file}:`, e);
+				if (previous) {
+					this.rules = this.rules.filter((r) => r.id !== rule.id);
+					this.loadReport.overrides.push({
+						ruleId: rule.id,
+						source: filePath,
+						overriddenSource: previous.file,
+					});
+					console.log(`Rule ${rule.id} from ${filePath} overrides ${previous.file}`);
+				}
+
+				this.rules.push(rule);
+				this.ruleSources.set(rule.id, { file: filePath, workspace: isWorkspace });
+				// Pre-compile patterns
+				if (compiledPattern) {
+					this.compiledPatterns.set(rule.id, compiledPattern);
+				} else {
+					this.compiledPatterns.delete(rule.id);
+				}
+				if (exclusions) {
+					this.compiledExclusions.set(rule.id, exclusions);
+				} else {
+					this.compiledExclusions.delete(rule.id);
+				}
+				summary.loaded++;
 			}
-		}
 
-		console.log(`Total rules loaded: ${this.rules.length}`);
+			console.log(`Loaded ${summary.loaded} rules from ${filePath}`);
+		}
 	}
 
 	public findMatches(document: vscode.TextDocument, context: ProjectContext): RuleMatch[] {
@@ -59,12 +203,174 @@ export class RuleEngine {
 			return cached.matches;
 		}
 
+		const manifest = isCargoManifest(document.uri);
+		const rules = this.rules.filter(
+			(rule) => !rule.editionChange && (rule.language === "cargo-toml") === manifest,
+		);
+		const matches = manifest
+			? this.matchManifestRules(document, rules, context)
+			: this.matchRules(document, rules, context);
+
+		// Cache the results
+		this.matchCache.set(cacheKey, {
+			version: document.version,
+			context,
+			matches,
+		});
+
+		// Limit cache size (keep last 10 documents)
+		trimCache(this.matchCache);
+
+		return matches;
+	}
+
+	/**
+	 * Matches of the rules for code whose meaning changes in `edition` (see `editionChange`),
+	 * whatever the project context. Empty when the document's crate is already on that edition.
+	 * Not cached: only the edition migration report asks for these.
+	 */
+	public findEditionChanges(document: vscode.TextDocument, edition: string): RuleMatch[] {
+		const dependencies = this.getDependencies?.(document.uri) ?? null;
+		if ((dependencies?.edition ?? LATEST_EDITION) >= edition) {
+			return [];
+		}
+		return this.matchRules(
+			document,
+			this.rules.filter((rule) => rule.editionChange === edition),
+		);
+	}
+
+	/**
+	 * Run `cargo-toml` rules over a Cargo.toml. Their patterns see the manifest with comments
+	 * blanked out; there is no syntax tree, so no scopes or suppression comments.
+	 */
+	private matchManifestRules(
+		document: vscode.TextDocument,
+		rules: Rule[],
+		context: ProjectContext,
+	): RuleMatch[] {
+		const matches: RuleMatch[] = [];
+		const text = document.getText();
+		const source = maskTomlComments(text);
+		const dependencies = this.getDependencies?.(document.uri) ?? null;
+
+		for (const loadedRule of rules) {
+			const configured = this.applySettings(loadedRule);
+			const rule = configured && this.adaptToRust(configured, dependencies);
+			if (
+				!rule ||
+				(!rule.contexts.includes(context) && !rule.contexts.includes("general")) ||
+				!this.meetsDependencyRequirements(rule, dependencies)
+			) {
+				continue;
+			}
+			const exclusions = this.compiledExclusions.get(rule.id);
+			const pattern = this.compiledPatterns.get(rule.id);
+			if (!pattern || exclusions?.file?.test(source)) {
+				continue;
+			}
+
+			pattern.lastIndex = 0;
+			let match: RegExpExecArray | null;
+			while ((match = pattern.exec(source)) !== null) {
+				if (match[0] === "") {
+					pattern.lastIndex++;
+					continue;
+				}
+				const end = match.index + match[0].length;
+				if (exclusions && isExcluded(exclusions, source, match.index, end)) {
+					continue;
+				}
+				matches.push(
+					this.createMatch(document, rule, match.index, end, text, captureSpans(match)),
+				);
+			}
+		}
+
+		return matches;
+	}
+
+	/**
+	 * Run rules over a document; rules for other project contexts are skipped when one is given
+	 */
+	private matchRules(
+		document: vscode.TextDocument,
+		rules: Rule[],
+		context?: ProjectContext,
+	): RuleMatch[] {
 		const matches: RuleMatch[] = [];
 		const text = document.getText();
+		const tree = this.getSyntaxTree(document);
+		const dependencies = this.getDependencies?.(document.uri) ?? null;
+
+		// Regex rules run on a copy with literal contents, comments and attributes blanked out
+		const maskedTexts = new Map<string, string>();
+		const getMaskedText = (comments: boolean, attributes: boolean): string => {
+			const key = `${comments}:${attributes}`;
+			let masked = maskedTexts.get(key);
+			if (masked === undefined) {
+				masked = maskSource(tree, { comments, attributes });
+				maskedTexts.set(key, masked);
+			}
+			return masked;
+		};
+		// Exclusions look at code only, but attributes such as `#![no_std]` are code too
+		const exclusionText = () => getMaskedText(true, false);
+		const scopes = new Map<number, ScopeInfo>();
+		const scopeAt = (offset: number): ScopeInfo => {
+			let scope = scopes.get(offset);
+			if (!scope) {
+				scope = getScope(tree, offset);
+				scopes.set(offset, scope);
+			}
+			return scope;
+		};
+		const suppressions = Suppressions.fromTokens(text, tree.tokens);
+
+		for (const loadedRule of rules) {
+			const configured = this.applySettings(loadedRule);
+			const rule = configured && this.adaptToRust(configured, dependencies);
+			if (!rule) {
+				continue;
+			}
 
-		for (const rule of this.rules) {
 			// Filter by context
-			if (!rule.contexts.includes(context) && !rule.contexts.includes("general")) {
+			if (context && !rule.contexts.includes(context) && !rule.contexts.includes("general")) {
+				continue;
+			}
+
+			if (!this.meetsDependencyRequirements(rule, dependencies)) {
+				continue;
+			}
+
+			const exclusions = this.compiledExclusions.get(rule.id);
+			if (exclusions?.file?.test(exclusionText()) || suppressions.isDisabled(rule.id)) {
+				continue;
+			}
+
+			// Suppressions, scope predicates and exclusions are checked per match
+			const accept = (start: number, end: number): boolean => {
+				if (suppressions.isSuppressed(rule.id, document.positionAt(start).line)) {
+					return false;
+				}
+				const enclosingFunction = () => scopeAt(start).function;
+				if (
+					exclusions &&
+					isExcluded(exclusions, exclusionText(), start, end, enclosingFunction)
+				) {
+					return false;
+				}
+				return !rule.scope || matchesScope(scopeAt(start), rule.scope);
+			};
+
+			if (rule.query) {
+				for (const node of tree.find(rule.query)) {
+					if (accept(node.start, node.headEnd)) {
+						matches.push(
+							this.createMatch(document, rule, node.start, node.headEnd, text),
+						);
+					}
+				}
 				continue;
 			}
 
@@ -76,49 +382,229 @@ export class RuleEngine {
 			// Reset regex state
 			pattern.lastIndex = 0;
 
+			const source = getMaskedText(!rule.matchInComments, !rule.matchInAttributes);
 			let match: RegExpExecArray | null;
-			while ((match = pattern.exec(text)) !== null) {
-				const pos = document.positionAt(match.index);
-				matches.push({
-					rule,
-					range: {
-						start: match.index,
-						end: match.index + match[0].length,
-						line: pos.line,
-						character: pos.character,
-					},
-					matchedText: match[0],
-				});
+			while ((match = pattern.exec(source)) !== null) {
+				// Validation rejects empty matches, but a zero-width one must not stall the loop
+				if (match[0] === "") {
+					pattern.lastIndex++;
+					continue;
+				}
+				if (!accept(match.index, match.index + match[0].length)) {
+					continue;
+				}
+				matches.push(
+					this.createMatch(
+						document,
+						rule,
+						match.index,
+						match.index + match[0].length,
+						text,
+						captureSpans(match),
+					),
+				);
 			}
 		}
 
-		// Cache the results
-		this.matchCache.set(cacheKey, {
-			version: document.version,
-			context,
-			matches,
-		});
+		return matches;
+	}
 
-		// Limit cache size (keep last 10 documents)
-		if (this.matchCache.size > 10) {
-			const firstKey = this.matchCache.keys().next().value;
-			if (firstKey) {
-				this.matchCache.delete(firstKey);
+	/**
+	 * Update where `requiresDependency` / `requiresCategory` look up the dependencies of the
+	 * crate a document belongs to
+	 */
+	public setDependencies(getDependencies: (uri: vscode.Uri) => ProjectDependencies): void {
+		this.getDependencies = getDependencies;
+		this.matchCache.clear();
+	}
+
+	/**
+	 * Update the per-rule overrides and decoration threshold from the user's settings
+	 */
+	public setRuleSettings(settings: RuleSettings): void {
+		this.settings = settings;
+		this.matchCache.clear();
+	}
+
+	/**
+	 * The user's overrides for a rule (empty when there are none)
+	 */
+	public getRuleSettings(ruleId: string): RuleUserSettings {
+		return this.settings.rules[ruleId] ?? {};
+	}
+
+	/**
+	 * Whether a matched rule gets an inline decoration: `showDecoration` when set,
+	 * otherwise its (possibly overridden) confidence against `minConfidence`
+	 */
+	public shouldDecorate(rule: Rule): boolean {
+		const showDecoration = this.getRuleSettings(rule.id).showDecoration;
+		if (showDecoration !== undefined) {
+			return showDecoration;
+		}
+		return rule.confidence >= this.settings.minConfidence;
+	}
+
+	/**
+	 * The rule as the user configured it, or `undefined` when they turned it off
+	 */
+	private applySettings(rule: Rule): Rule | undefined {
+		const settings = this.settings.rules[rule.id];
+		if (!settings) {
+			return rule;
+		}
+		if (settings.enabled === false) {
+			return undefined;
+		}
+		if (typeof settings.confidence === "number") {
+			return { ...rule, confidence: Math.min(1, Math.max(0, settings.confidence)) };
+		}
+		return rule;
+	}
+
+	/**
+	 * Drop rules for other editions, and switch rules whose advice needs a newer Rust than the
+	 * crate supports to their `olderRust` advice (or drop them). Files outside a Cargo package
+	 * count as the latest edition; an unknown Rust version hides nothing.
+	 */
+	private adaptToRust(rule: Rule, dependencies: ProjectDependencies | null): Rule | undefined {
+		const edition = dependencies?.edition ?? LATEST_EDITION;
+		if (rule.editions && !rule.editions.includes(edition)) {
+			return undefined;
+		}
+		const rustVersion = dependencies?.rustVersion;
+		if (
+			!rule.minRustVersion ||
+			!rustVersion ||
+			satisfiesVersion(rustVersion, `>=${rule.minRustVersion}`)
+		) {
+			return rule;
+		}
+		if (!rule.olderRust) {
+			return undefined;
+		}
+		const { explanation, example, suggestedFix } = rule.olderRust;
+		return { ...rule, explanation, example: example ?? rule.example, suggestedFix };
+	}
+
+	private meetsDependencyRequirements(
+		rule: Rule,
+		dependencies: ProjectDependencies | null,
+	): boolean {
+		if (!rule.requiresDependency && !rule.requiresCategory) {
+			return true;
+		}
+		if (!dependencies) {
+			return false;
+		}
+
+		if (rule.requiresDependency) {
+			const required = Array.isArray(rule.requiresDependency)
+				? rule.requiresDependency
+				: [rule.requiresDependency];
+			if (!required.some((dep) => hasDependency(dependencies, dep))) {
+				return false;
 			}
 		}
 
-		return matches;
+		if (rule.requiresCategory) {
+			const required = Array.isArray(rule.requiresCategory)
+				? rule.requiresCategory
+				: [rule.requiresCategory];
+			const categories = dependencies.categories;
+			if (!required.some((category) => categories.includes(category))) {
+				return false;
+			}
+		}
+
+		return true;
 	}
 
-	public findMatchAtPosition(
+	/**
+	 * Enclosing function, loops, test items and trait impl at a position
+	 */
+	public getScopeAt(document: vscode.TextDocument, position: vscode.Position): ScopeInfo {
+		return getScope(this.getSyntaxTree(document), document.offsetAt(position));
+	}
+
+	/**
+	 * Rules silenced by `compass-ignore` / `compass-disable` comments in a document
+	 */
+	public getSuppressions(document: vscode.TextDocument): Suppressions {
+		return Suppressions.fromTokens(document.getText(), this.getSyntaxTree(document).tokens);
+	}
+
+	/**
+	 * Get the parsed syntax tree for a document (cached per version)
+	 */
+	public getSyntaxTree(document: vscode.TextDocument): SyntaxTree {
+		const cacheKey = document.uri.toString();
+		const cached = this.syntaxTreeCache.get(cacheKey);
+		if (cached && cached.version === document.version) {
+			return cached.tree;
+		}
+
+		const tree = SyntaxTree.parse(document.getText());
+		this.syntaxTreeCache.set(cacheKey, { version: document.version, tree });
+		trimCache(this.syntaxTreeCache);
+		return tree;
+	}
+
+	private createMatch(
+		document: vscode.TextDocument,
+		rule: Rule,
+		start: number,
+		end: number,
+		text: string,
+		captures?: Record<string, Span>,
+	): RuleMatch {
+		const pos = document.positionAt(start);
+		return {
+			rule,
+			range: {
+				start,
+				end,
+				line: pos.line,
+				character: pos.character,
+			},
+			matchedText: text.slice(start, end),
+			captures,
+		};
+	}
+
+	/**
+	 * Work out the concrete edits of a match's `suggestedFix`, if it applies here
+	 */
+	public resolveFix(document: vscode.TextDocument, match: RuleMatch): ResolvedFix | undefined {
+		return resolveFix(this.getSyntaxTree(document), match);
+	}
+
+	/**
+	 * Every match covering a position, most specific first
+	 */
+	public findMatchesAtPosition(
 		document: vscode.TextDocument,
 		position: vscode.Position,
 		context: ProjectContext,
-	): RuleMatch | undefined {
-		const matches = this.findMatches(document, context);
+	): RuleMatch[] {
 		const offset = document.offsetAt(position);
 
-		return matches.find((m) => offset >= m.range.start && offset <= m.range.end);
+		return this.findMatches(document, context)
+			.filter((m) => offset >= m.range.start && offset <= m.range.end)
+			.sort(
+				(a, b) =>
+					specificity(b.rule) - specificity(a.rule) ||
+					b.rule.confidence - a.rule.confidence ||
+					b.matchedText.length - a.matchedText.length,
+			);
+	}
+
+	public findMatchAtPosition(
+		document: vscode.TextDocument,
+		position: vscode.Position,
+		context: ProjectContext,
+	): RuleMatch | undefined {
+		return this.findMatchesAtPosition(document, position, context)[0];
 	}
 
 	public getRuleById(id: string): Rule | undefined {
@@ -129,10 +615,198 @@ export class RuleEngine {
 		return [...this.rules];
 	}
 
+	/**
+	 * What the last (re)load loaded, skipped and overrode
+	 */
+	public getLoadReport(): RuleLoadReport {
+		return this.loadReport;
+	}
+
 	public reloadRules(): void {
 		this.rules = [];
 		this.compiledPatterns.clear();
+		this.compiledExclusions.clear();
+		this.ruleSources.clear();
+		this.loadReport = { files: [], issues: [], overrides: [] };
 		this.matchCache.clear();
+		this.syntaxTreeCache.clear();
 		this.loadRules();
 	}
 }
+
+/**
+ * Whether a match's line(s) or surrounding text hit one of the rule's exclusions.
+ * `text` is the masked source, so comments and literals never count.
+ */
+function isExcluded(
+	exclusions: RuleExclusions,
+	text: string,
+	start: number,
+	end: number,
+	enclosingFunction?: () => SyntaxNode | undefined,
+): boolean {
+	if (exclusions.line) {
+		const lineStart = text.lastIndexOf("\n", start - 1) + 1;
+		const lineEnd = text.indexOf("\n", end);
+		const lines = text.slice(lineStart, lineEnd === -1 ? text.length : lineEnd);
+		if (exclusions.line.test(lines)) {
+			return true;
+		}
+	}
+	if (exclusions.within) {
+		const { pattern, before, after } = exclusions.within;
+		const fn = exclusions.within.function ? enclosingFunction?.() : undefined;
+		const window = fn
+			? text.slice(fn.start, fn.end)
+			: text.slice(Math.max(0, start - before), end + after);
+		if (pattern.test(window)) {
+			return true;
+		}
+	}
+	return false;
+}
+
+/**
+ * Locate a rule's `"id": "..."` in its file so load problems can point at it
+ */
+function findRulePosition(
+	content: string,
+	ruleId: string | undefined,
+	from: number,
+): { offset?: number; line?: number; character?: number } {
+	if (typeof ruleId !== "string") {
+		return {};
+	}
+	const idPattern = new RegExp(`"id"\\s*:\\s*${escapeRegExp(JSON.stringify(ruleId))}`, "g");
+	idPattern.lastIndex = from;
+	const found = idPattern.exec(content);
+	if (!found) {
+		return {};
+	}
+	const before = content.slice(0, found.index);
+	return {
+		offset: found.index,
+		line: before.split("\n").length - 1,
+		character: found.index - (before.lastIndexOf("\n") + 1),
+	};
+}
+
+/**
+ * Whether a crate is among the dependencies, at a version meeting the requirement if it has one
+ * and with the features it lists. A versioned requirement fails when the version isn't known.
+ */
+export function hasDependency(
+	dependencies: ProjectDependencies,
+	requirement: string | DependencyRequirement,
+): boolean {
+	return unmetDependency(dependencies, requirement) === undefined;
+}
+
+/**
+ * Why the dependencies don't meet a requirement, e.g. "serde's `derive` feature isn't
+ * enabled"; undefined when they do
+ */
+export function unmetDependency(
+	dependencies: ProjectDependencies,
+	requirement: string | DependencyRequirement,
+): string | undefined {
+	const { name, version, features = [] }: DependencyRequirement =
+		typeof requirement === "string" ? { name: requirement } : requirement;
+	const crate = normalizeCrateName(name);
+	if (!dependencies.crates.some((c) => normalizeCrateName(c) === crate)) {
+		return `${name} isn't a dependency`;
+	}
+	if (version !== undefined) {
+		const resolved = dependencies.versions.get(crate);
+		if (resolved === undefined) {
+			return `the version of ${name} isn't known`;
+		}
+		if (!satisfiesVersion(resolved, version)) {
+			return `${name} ${resolved} doesn't meet \`${version}\``;
+		}
+	}
+	const enabled = dependencies.features.get(crate) ?? [];
+	const missing = features.filter((f) => !enabled.includes(f));
+	if (missing.length === 1) {
+		return `${name}'s \`${missing[0]}\` feature isn't enabled`;
+	}
+	if (missing.length > 1) {
+		return `${name}'s ${missing.map((f) => `\`${f}\``).join(", ")} features aren't enabled`;
+	}
+	return undefined;
+}
+
+/**
+ * Whether a document is a Cargo manifest, which `cargo-toml` rules run on instead of Rust rules
+ */
+export function isCargoManifest(uri: vscode.Uri): boolean {
+	return /(?:^|\/)Cargo\.toml$/.test(uri.path);
+}
+
+/**
+ * Cargo treats `-` and `_` in crate names as the same crate
+ */
+export function normalizeCrateName(name: string): string {
+	return name.toLowerCase().replace(/_/g, "-");
+}
+
+/**
+ * Offsets of the capture groups that took part in a regex match (needs the `d` flag)
+ */
+function captureSpans(match: RegExpExecArray): Record<string, Span> | undefined {
+	if (!match.indices || match.length <= 1) {
+		return undefined;
+	}
+	const captures: Record<string, Span> = {};
+	match.indices.forEach((indices, i) => {
+		if (i > 0 && indices) {
+			captures[String(i)] = { start: indices[0], end: indices[1] };
+		}
+	});
+	for (const [name, indices] of Object.entries(match.indices.groups ?? {})) {
+		if (indices) {
+			captures[name] = { start: indices[0], end: indices[1] };
+		}
+	}
+	return captures;
+}
+
+/**
+ * How narrowly a rule targets code: structural queries and each extra condition
+ * (scope, exclusions, dependency gating, receiver types) count
+ */
+function specificity(rule: Rule): number {
+	let score = 0;
+	if (rule.query) {
+		score += 2;
+		score += [rule.query.name, rule.query.argumentCount, rule.query.scrutinee].filter(
+			(c) => c !== undefined,
+		).length;
+	}
+	if (rule.scope) {
+		score += Object.keys(rule.scope).length;
+	}
+	if (rule.excludeIfLineMatches || rule.excludeIfWithin || rule.excludeIfFileMatches) {
+		score += 1;
+	}
+	if (rule.requiresDependency || rule.requiresCategory) {
+		score += 1;
+	}
+	if (rule.receiverTypes) {
+		score += 1;
+	}
+	return score;
+}
+
+function escapeRegExp(text: string): string {
+	return text.replace(/[.*+?^${}()|[\]\\]/g, "\\$&");
+}
+
+function trimCache<T>(cache: Map<string, T>): void {
+	if (cache.size > MAX_CACHED_DOCUMENTS) {
+		const firstKey = cache.keys().next().value;
+		if (firstKey) {
+			cache.delete(firstKey);
+		}
+	}
+}
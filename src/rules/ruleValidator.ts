@@ -0,0 +1,405 @@
+import { SCRUTINEE_KINDS, SYNTAX_NODE_KINDS } from "./syntax";
+import { FIX_TARGETS, TEMPLATE_REFERENCE, TEMPLATE_VALUES } from "./fixes";
+import { isVersionRequirement } from "./semver";
+import type {
+	DependencyRequirement,
+	ProjectContext,
+	ReceiverType,
+	Rule,
+	RuleExclusionWindow,
+	RuleLanguage,
+	RuleSuggestedFix,
+} from "./types";
+
+export const PROJECT_CONTEXTS: readonly ProjectContext[] = [
+	"general",
+	"parser",
+	"web",
+	"cli",
+	"systems",
+];
+
+/**
+ * Categories `CargoAnalyzerService.getRelevantHintCategories()` can report
+ */
+export const DEPENDENCY_CATEGORIES: readonly string[] = [
+	"general",
+	"async",
+	"web",
+	"serialization",
+	"cli",
+	"parser",
+	"error-handling-advanced",
+	"database",
+	"testing",
+];
+
+export const RECEIVER_TYPES: readonly ReceiverType[] = ["iterator", "option", "result", "string"];
+
+export const RUST_EDITIONS: readonly string[] = ["2015", "2018", "2021", "2024"];
+
+export const RULE_LANGUAGES: readonly RuleLanguage[] = ["rust", "cargo-toml"];
+
+/** Fields that rely on Rust syntax, so manifest rules can't use them */
+const RUST_ONLY_FIELDS = [
+	"query",
+	"scope",
+	"receiverTypes",
+	"suggestedFix",
+	"editionChange",
+] as const;
+
+const SCOPE_FLAGS = ["inLoop", "inTest", "inAsyncFn"] as const;
+const SCOPE_NAMES = ["fnReturns", "inImplOf"] as const;
+
+const REQUIRED_TEXT_FIELDS = [
+	"id",
+	"title",
+	"rustTerm",
+	"explanation",
+	"example",
+	"deepExplanation",
+] as const;
+
+/**
+ * Compiled `excludeIf*` conditions of a rule
+ */
+export interface RuleExclusions {
+	line?: RegExp;
+	within?: { pattern: RegExp; before: number; after: number; function: boolean };
+	file?: RegExp;
+}
+
+export interface RuleValidationResult {
+	/** Problems that stop the rule from loading */
+	errors: string[];
+	/** Problems worth reporting that still let the rule load */
+	warnings: string[];
+	/** Compiled `pattern`, when the rule has one and it is valid */
+	compiledPattern?: RegExp;
+	/** Compiled exclusion conditions, when the rule has any and they are valid */
+	exclusions?: RuleExclusions;
+}
+
+/**
+ * Check a rule parsed from JSON against what the engine and UI rely on
+ */
+export function validateRule(rule: Partial<Rule>): RuleValidationResult {
+	const errors: string[] = [];
+	const warnings: string[] = [];
+	let compiledPattern: RegExp | undefined;
+
+	for (const field of REQUIRED_TEXT_FIELDS) {
+		if (typeof rule[field] !== "string" || rule[field] === "") {
+			errors.push(`Missing or empty "${field}"`);
+		}
+	}
+
+	if (rule.pattern === undefined && rule.query === undefined) {
+		errors.push(`Needs either a "pattern" or a "query"`);
+	}
+
+	if (rule.pattern !== undefined) {
+		try {
+			// `d` records capture group offsets for fix templates
+			compiledPattern = new RegExp(rule.pattern, "gd");
+		} catch (e) {
+			errors.push(`Invalid regex in "pattern": ${e instanceof Error ? e.message : e}`);
+		}
+	}
+
+	if (compiledPattern && matchesEmpty(compiledPattern, rule.example)) {
+		errors.push(`"pattern" can match an empty string, so it would mark nothing`);
+	}
+
+	if (rule.query !== undefined) {
+		if (!(SYNTAX_NODE_KINDS as readonly string[]).includes(rule.query.kind)) {
+			errors.push(`Unknown query kind "${rule.query.kind}"`);
+		}
+		if (
+			rule.query.scrutinee !== undefined &&
+			!(SCRUTINEE_KINDS as readonly string[]).includes(rule.query.scrutinee)
+		) {
+			errors.push(`Unknown query scrutinee "${rule.query.scrutinee}"`);
+		}
+	}
+
+	if (rule.scope !== undefined) {
+		if (typeof rule.scope !== "object" || rule.scope === null || Array.isArray(rule.scope)) {
+			errors.push(`"scope" must be an object`);
+		} else {
+			const scope = rule.scope as Record<string, unknown>;
+			for (const flag of SCOPE_FLAGS) {
+				if (scope[flag] !== undefined && typeof scope[flag] !== "boolean") {
+					errors.push(`"scope.${flag}" must be true or false`);
+				}
+			}
+			for (const field of SCOPE_NAMES) {
+				const value = scope[field];
+				const names = Array.isArray(value) ? value : [value];
+				if (value !== undefined && !names.every((n) => typeof n === "string")) {
+					errors.push(`"scope.${field}" must be a name or an array of names`);
+				}
+			}
+			const known: readonly string[] = [...SCOPE_FLAGS, ...SCOPE_NAMES];
+			const unknown = Object.keys(scope).filter((key) => !known.includes(key));
+			if (unknown.length > 0) {
+				warnings.push(`Unknown scope predicate(s) ${unknown.join(", ")}`);
+			}
+		}
+	}
+
+	if (typeof rule.confidence !== "number" || rule.confidence < 0 || rule.confidence > 1) {
+		errors.push(`"confidence" must be a number between 0 and 1 (got ${rule.confidence})`);
+	}
+
+	if (!Array.isArray(rule.contexts)) {
+		errors.push(`"contexts" must be an array`);
+	} else {
+		const unknown = rule.contexts.filter(
+			(c) => !(PROJECT_CONTEXTS as readonly string[]).includes(c),
+		);
+		if (unknown.length > 0) {
+			warnings.push(
+				`Unknown context(s) ${unknown.map((c) => `"${c}"`).join(", ")}; expected one of ${PROJECT_CONTEXTS.join(", ")}`,
+			);
+		}
+	}
+
+	if (rule.requiresDependency !== undefined) {
+		const dependencies: unknown[] = Array.isArray(rule.requiresDependency)
+			? rule.requiresDependency
+			: [rule.requiresDependency];
+		if (!dependencies.every((dep) => typeof dep === "string" || isRequirement(dep))) {
+			errors.push(
+				`"requiresDependency" must be a crate name, a { "name", "version", "features" } object, or an array of them`,
+			);
+		} else {
+			for (const dep of dependencies as (string | DependencyRequirement)[]) {
+				if (typeof dep !== "string" && dep.version && !isVersionRequirement(dep.version)) {
+					errors.push(
+						`"requiresDependency" has an invalid version requirement "${dep.version}"`,
+					);
+				}
+			}
+		}
+	}
+
+	if (rule.expectsDependency !== undefined) {
+		if (!isRequirement(rule.expectsDependency)) {
+			errors.push(`"expectsDependency" must be a { "name", "version", "features" } object`);
+		} else if (
+			rule.expectsDependency.version &&
+			!isVersionRequirement(rule.expectsDependency.version)
+		) {
+			errors.push(
+				`"expectsDependency" has an invalid version requirement "${rule.expectsDependency.version}"`,
+			);
+		}
+	}
+
+	if (rule.requiresCategory !== undefined) {
+		const categories = Array.isArray(rule.requiresCategory)
+			? rule.requiresCategory
+			: [rule.requiresCategory];
+		if (!categories.every((c) => typeof c === "string")) {
+			errors.push(`"requiresCategory" must be a category name or an array of them`);
+		} else {
+			const unknown = categories.filter((c) => !DEPENDENCY_CATEGORIES.includes(c));
+			if (unknown.length > 0) {
+				warnings.push(
+					`Unknown dependency category(ies) ${unknown.map((c) => `"${c}"`).join(", ")}; expected one of ${DEPENDENCY_CATEGORIES.join(", ")}`,
+				);
+			}
+		}
+	}
+
+	if (
+		rule.receiverTypes !== undefined &&
+		(!Array.isArray(rule.receiverTypes) ||
+			!rule.receiverTypes.every((t) => RECEIVER_TYPES.includes(t)))
+	) {
+		errors.push(`"receiverTypes" must be an array of ${RECEIVER_TYPES.join(", ")}`);
+	}
+
+	if (rule.minRustVersion !== undefined) {
+		const version: unknown = rule.minRustVersion;
+		if (typeof version !== "string" || !/^1\.\d+(\.\d+)?$/.test(version)) {
+			errors.push(`"minRustVersion" must be a Rust version such as "1.65"`);
+		}
+	} else if (rule.olderRust !== undefined) {
+		warnings.push(`"olderRust" has no effect without "minRustVersion"`);
+	}
+	if (rule.olderRust !== undefined) {
+		if (typeof rule.olderRust?.explanation !== "string") {
+			errors.push(`"olderRust" needs a string "explanation"`);
+		} else if (rule.olderRust.suggestedFix !== undefined) {
+			errors.push(
+				...validateFix(rule.olderRust.suggestedFix, compiledPattern).map((e) =>
+					e.replace(`"suggestedFix`, `"olderRust.suggestedFix`),
+				),
+			);
+		}
+	}
+
+	if (
+		rule.editions !== undefined &&
+		(!Array.isArray(rule.editions) || !rule.editions.every((e) => RUST_EDITIONS.includes(e)))
+	) {
+		errors.push(`"editions" must be an array of ${RUST_EDITIONS.join(", ")}`);
+	}
+	if (rule.editionChange !== undefined) {
+		// 2015 is the first edition, so nothing changes in it
+		const edition: unknown = rule.editionChange;
+		const changing = RUST_EDITIONS.slice(1);
+		if (typeof edition !== "string" || !changing.includes(edition)) {
+			errors.push(`"editionChange" must be one of ${changing.join(", ")}`);
+		}
+	}
+
+	if (rule.language !== undefined && !RULE_LANGUAGES.includes(rule.language)) {
+		errors.push(`"language" must be one of ${RULE_LANGUAGES.join(", ")}`);
+	}
+	if (rule.language === "cargo-toml") {
+		for (const field of RUST_ONLY_FIELDS.filter((f) => rule[f] !== undefined)) {
+			errors.push(`"${field}" isn't supported in "cargo-toml" rules`);
+		}
+	}
+
+	const exclusions: RuleExclusions = {};
+	const compileExclusion = (field: string, source: unknown): RegExp | undefined => {
+		if (typeof source !== "string") {
+			errors.push(`"${field}" must be a regex string`);
+			return undefined;
+		}
+		try {
+			return new RegExp(source);
+		} catch (e) {
+			errors.push(`Invalid regex in "${field}": ${e instanceof Error ? e.message : e}`);
+			return undefined;
+		}
+	};
+
+	if (rule.excludeIfLineMatches !== undefined) {
+		exclusions.line = compileExclusion("excludeIfLineMatches", rule.excludeIfLineMatches);
+	}
+	if (rule.excludeIfFileMatches !== undefined) {
+		exclusions.file = compileExclusion("excludeIfFileMatches", rule.excludeIfFileMatches);
+	}
+	if (rule.excludeIfWithin !== undefined) {
+		const window: Partial<RuleExclusionWindow> = rule.excludeIfWithin ?? {};
+		const { pattern, before = 0, after = 0, function: inFunction = false } = window;
+		const compiled = compileExclusion("excludeIfWithin.pattern", pattern);
+		const isCount = (n: unknown) => typeof n === "number" && Number.isInteger(n) && n >= 0;
+		if (!isCount(before) || !isCount(after)) {
+			errors.push(`"excludeIfWithin" before/after must be non-negative whole numbers`);
+		} else if (typeof inFunction !== "boolean") {
+			errors.push(`"excludeIfWithin.function" must be true or false`);
+		} else if (compiled) {
+			exclusions.within = { pattern: compiled, before, after, function: inFunction };
+		}
+	}
+
+	if (rule.suggestedFix !== undefined) {
+		errors.push(...validateFix(rule.suggestedFix, compiledPattern));
+	}
+
+	const hasExclusions = exclusions.line || exclusions.within || exclusions.file;
+	return {
+		errors,
+		warnings,
+		compiledPattern,
+		exclusions: hasExclusions ? exclusions : undefined,
+	};
+}
+
+/**
+ * Whether a pattern matches zero characters, in an empty string or anywhere in the rule's
+ * example. The engine would mark nothing there, and `(?=...)`-style patterns match this way.
+ */
+function matchesEmpty(pattern: RegExp, example: unknown): boolean {
+	const probe = new RegExp(pattern.source, "g");
+	const samples = typeof example === "string" ? ["", example] : [""];
+	return samples.some((sample) => [...sample.matchAll(probe)].some((match) => match[0] === ""));
+}
+
+/**
+ * A fix is either literal `before`/`after`, or templates whose targets and
+ * `$references` must exist
+ */
+function validateFix(fix: RuleSuggestedFix, pattern: RegExp | undefined): string[] {
+	if (typeof fix !== "object" || fix === null || typeof fix.description !== "string") {
+		return [`"suggestedFix" needs a string "description"`];
+	}
+
+	const hasTemplates = fix.replacement !== undefined || fix.edits !== undefined;
+	if (!hasTemplates) {
+		return typeof fix.before === "string" && typeof fix.after === "string"
+			? []
+			: [`"suggestedFix" needs "before" and "after", a "replacement" or "edits"`];
+	}
+
+	const errors: string[] = [];
+	if (fix.snippet !== undefined && typeof fix.snippet !== "boolean") {
+		errors.push(`"suggestedFix.snippet" must be true or false`);
+	}
+	if (fix.edits !== undefined && !Array.isArray(fix.edits)) {
+		errors.push(`"suggestedFix.edits" must be an array`);
+		return errors;
+	}
+
+	// Capture groups the pattern declares
+	const empty = pattern ? new RegExp(`${pattern.source}|`, "d").exec("") : null;
+	const groupCount = empty ? empty.length - 1 : 0;
+	const groupNames = Object.keys(empty?.groups ?? {});
+	const isGroup = (name: string) =>
+		/^\d+$/.test(name)
+			? Number(name) >= 1 && Number(name) <= groupCount
+			: groupNames.includes(name);
+
+	const templates = (fix.edits ?? []).map((edit, i) => ({ field: `edits[${i}]`, edit }));
+	if (fix.replacement !== undefined) {
+		templates.unshift({ field: "replacement", edit: { text: fix.replacement } });
+	}
+	for (const { field, edit } of templates) {
+		if (typeof edit?.text !== "string") {
+			errors.push(`"suggestedFix.${field}" needs a string "text"`);
+			continue;
+		}
+		if (edit.target !== undefined) {
+			const group = /^\$\{?(\w+)\}?$/.exec(edit.target);
+			if (!FIX_TARGETS.includes(edit.target) && !(group && isGroup(group[1]))) {
+				errors.push(
+					`Unknown "suggestedFix.${field}.target" "${edit.target}"; expected a capture group or one of ${FIX_TARGETS.join(", ")}`,
+				);
+			}
+		}
+		for (const [, braced, bare] of edit.text.matchAll(TEMPLATE_REFERENCE)) {
+			const name: string | undefined = braced ?? bare;
+			if (name === undefined) {
+				continue;
+			}
+			const tabStop = fix.snippet === true && /^\d/.test(name);
+			if (!tabStop && !TEMPLATE_VALUES.includes(name) && !isGroup(name)) {
+				errors.push(`"suggestedFix.${field}" references unknown "$${name}"`);
+			}
+		}
+	}
+	return errors;
+}
+
+/**
+ * Whether a value is a `{ "name", "version", "features" }` dependency requirement
+ */
+function isRequirement(value: unknown): value is DependencyRequirement {
+	if (typeof value !== "object" || value === null) {
+		return false;
+	}
+	const { name, version, features } = value as Partial<Record<string, unknown>>;
+	return (
+		typeof name === "string" &&
+		(version === undefined || typeof version === "string") &&
+		(features === undefined ||
+			(Array.isArray(features) && features.every((f) => typeof f === "string")))
+	);
+}
@@ -0,0 +1,180 @@
+interface Version {
+	major: number;
+	minor: number;
+	patch: number;
+	pre: string[];
+}
+
+type Bound = [">=" | ">" | "<=" | "<", Version];
+
+const PART = String.raw`(\d+|[*xX])`;
+const COMPARATOR = new RegExp(
+	String.raw`^(\^|~|=|>=|>|<=|<)?\s*v?${PART}(?:\.${PART})?(?:\.${PART})?` +
+		String.raw`(?:-([0-9A-Za-z.-]+))?(?:\+[0-9A-Za-z.-]+)?$`,
+);
+
+/**
+ * Parse `1.2.3`, `1.2.3-beta.1` or `1.2.3+build`; build metadata is dropped
+ */
+function parseVersion(text: string): Version | undefined {
+	const match = /^v?(\d+)\.(\d+)\.(\d+)(?:-([0-9A-Za-z.-]+))?(?:\+[0-9A-Za-z.-]+)?$/.exec(
+		text.trim(),
+	);
+	if (!match) {
+		return undefined;
+	}
+	return {
+		major: Number(match[1]),
+		minor: Number(match[2]),
+		patch: Number(match[3]),
+		pre: match[4] ? match[4].split(".") : [],
+	};
+}
+
+function compareVersions(a: Version, b: Version): number {
+	const core = a.major - b.major || a.minor - b.minor || a.patch - b.patch;
+	if (core !== 0) {
+		return Math.sign(core);
+	}
+	// A pre-release sorts before its release
+	if (a.pre.length === 0 || b.pre.length === 0) {
+		return Math.sign(b.pre.length - a.pre.length);
+	}
+	for (let i = 0; i < Math.min(a.pre.length, b.pre.length); i++) {
+		const [x, y] = [a.pre[i], b.pre[i]];
+		const numeric = /^\d+$/.test(x) && /^\d+$/.test(y);
+		const order = numeric ? Number(x) - Number(y) : x < y ? -1 : x > y ? 1 : 0;
+		if (order !== 0) {
+			return Math.sign(order);
+		}
+	}
+	return Math.sign(a.pre.length - b.pre.length);
+}
+
+const version = (major: number, minor = 0, patch = 0, pre: string[] = []): Version => ({
+	major,
+	minor,
+	patch,
+	pre,
+});
+
+/**
+ * Bounds one comparator of a Cargo version requirement stands for, e.g. `^1.2` is
+ * `>=1.2.0, <2.0.0`. Undefined when the comparator can't be read.
+ */
+function parseComparator(text: string): Bound[] | undefined {
+	const match = COMPARATOR.exec(text.trim());
+	if (!match) {
+		return undefined;
+	}
+	const op = match[1] ?? "^";
+	const parts = [match[2], match[3], match[4]];
+	// A wildcard ends the version: `1.*` means `1`
+	const wildcard = parts.findIndex((p) => p !== undefined && /^[*xX]$/.test(p));
+	const given = parts.slice(0, wildcard === -1 ? 3 : wildcard).filter((p) => p !== undefined);
+	if (given.length === 0) {
+		return [];
+	}
+	if (wildcard !== -1 && op !== "^" && op !== "=") {
+		return undefined;
+	}
+
+	const [major, minor, patch] = given.map(Number);
+	const pre = match[5] ? match[5].split(".") : [];
+	const low = version(major, minor ?? 0, patch ?? 0, pre);
+	// The first version past everything the given parts allow, e.g. `1.2` → `1.3.0`
+	const next =
+		given.length === 1
+			? version(major + 1)
+			: given.length === 2
+				? version(major, minor + 1)
+				: version(major, minor, patch + 1);
+
+	switch (wildcard !== -1 && op === "^" ? "=" : op) {
+		case "=":
+			return given.length === 3 ? [[">=", low], ["<=", low]] : [[">=", low], ["<", next]];
+		case ">":
+			return given.length === 3 ? [[">", low]] : [[">=", next]];
+		case ">=":
+			return [[">=", low]];
+		case "<":
+			return [["<", low]];
+		case "<=":
+			return given.length === 3 ? [["<=", low]] : [["<", next]];
+		case "~":
+			return [[">=", low], ["<", given.length === 1 ? next : version(major, minor + 1)]];
+		default: {
+			// Caret: anything up to the next change in the leftmost non-zero part
+			let upper: Version;
+			if (major > 0 || given.length === 1) {
+				upper = version(major + 1);
+			} else if (minor > 0 || given.length === 2) {
+				upper = version(0, minor + 1);
+			} else {
+				upper = version(0, 0, patch + 1);
+			}
+			return [[">=", low], ["<", upper]];
+		}
+	}
+}
+
+function parseRequirement(requirement: string): Bound[] | undefined {
+	const bounds: Bound[] = [];
+	for (const comparator of requirement.split(",")) {
+		const parsed = parseComparator(comparator);
+		if (!parsed) {
+			return undefined;
+		}
+		bounds.push(...parsed);
+	}
+	return bounds;
+}
+
+/**
+ * Whether a version such as `4.5.1` meets a Cargo version requirement such as `>=4` or
+ * `^1.2, <1.8`. Pre-release versions are simply ordered before their release. False when
+ * either can't be read.
+ */
+export function satisfiesVersion(versionText: string, requirement: string): boolean {
+	const parsed = parseVersion(versionText);
+	const bounds = parseRequirement(requirement);
+	if (!parsed || !bounds) {
+		return false;
+	}
+	return bounds.every(([op, bound]) => {
+		const order = compareVersions(parsed, bound);
+		switch (op) {
+			case ">=":
+				return order >= 0;
+			case ">":
+				return order > 0;
+			case "<=":
+				return order <= 0;
+			default:
+				return order < 0;
+		}
+	});
+}
+
+/**
+ * Lowest version a requirement allows, e.g. `1.2.0` for `"1.2"`; undefined for `*` or `<2`
+ */
+export function minimumVersion(requirement: string): string | undefined {
+	const lower = parseRequirement(requirement)
+		?.filter(([op]) => op === ">=" || op === ">")
+		.map(([, v]) => v)
+		.sort(compareVersions)
+		.pop();
+	if (!lower) {
+		return undefined;
+	}
+	const pre = lower.pre.length > 0 ? `-${lower.pre.join(".")}` : "";
+	return `${lower.major}.${lower.minor}.${lower.patch}${pre}`;
+}
+
+/**
+ * Whether a requirement is valid Cargo syntax
+ */
+export function isVersionRequirement(requirement: string): boolean {
+	return parseRequirement(requirement) !== undefined;
+}
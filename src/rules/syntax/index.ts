@@ -0,0 +1,14 @@
+export { type Token, type TokenKind, tokenize } from "./lexer";
+export { type MaskOptions, maskSource, maskTomlComments } from "./masking";
+export { getScope, matchesScope, type ScopeInfo, type ScopePredicate } from "./scope";
+export { Suppressions } from "./suppressions";
+export {
+	SCRUTINEE_KINDS,
+	SYNTAX_NODE_KINDS,
+	type ScrutineeKind,
+	type Span,
+	type SyntaxNode,
+	type SyntaxNodeKind,
+	type SyntaxQuery,
+	SyntaxTree,
+} from "./syntaxTree";
@@ -0,0 +1,251 @@
+/**
+ * Kinds of tokens produced by the Rust lexer
+ */
+export type TokenKind =
+	| "ident"
+	| "lifetime"
+	| "string"
+	| "char"
+	| "number"
+	| "punct"
+	| "comment";
+
+export interface Token {
+	kind: TokenKind;
+	start: number;
+	end: number;
+	text: string;
+	/** Set on comments that are doc comments (`///`, `//!`, `/** */`, `/*! */`) */
+	doc?: boolean;
+}
+
+/**
+ * Multi-character punctuation recognised as a single token.
+ * `<` and `>` are deliberately kept single so generics like `Vec<Vec<u8>>` stay balanced.
+ */
+const MULTI_CHAR_PUNCT = [
+	"..=",
+	"...",
+	"::",
+	"->",
+	"=>",
+	"..",
+	"==",
+	"!=",
+	"&&",
+	"||",
+	"+=",
+	"-=",
+	"*=",
+	"/=",
+	"%=",
+	"^=",
+	"&=",
+	"|=",
+];
+
+function isIdentStart(ch: string): boolean {
+	return /[\p{L}_]/u.test(ch);
+}
+
+function isIdentContinue(ch: string): boolean {
+	return /[\p{L}\p{N}_]/u.test(ch);
+}
+
+function isDigit(ch: string): boolean {
+	return ch >= "0" && ch <= "9";
+}
+
+/**
+ * Small, forgiving Rust tokenizer.
+ * Whitespace is dropped; comments are kept so callers can mask or inspect them.
+ * Unterminated literals and comments run to the end of the input instead of failing.
+ */
+export function tokenize(source: string): Token[] {
+	const tokens: Token[] = [];
+	const length = source.length;
+	let i = 0;
+
+	const push = (kind: TokenKind, start: number, end: number, doc?: boolean) => {
+		const token: Token = { kind, start, end, text: source.slice(start, end) };
+		if (doc) {
+			token.doc = true;
+		}
+		tokens.push(token);
+	};
+
+	// Skip a shebang line, but not an inner attribute like `#![allow(...)]`
+	if (source.startsWith("#!") && !source.startsWith("#![")) {
+		const newline = source.indexOf("\n");
+		i = newline === -1 ? length : newline;
+	}
+
+	while (i < length) {
+		const ch = source[i];
+		const next = source[i + 1];
+
+		if (/\s/.test(ch)) {
+			i++;
+			continue;
+		}
+
+		// Line comments
+		if (ch === "/" && next === "/") {
+			const start = i;
+			const newline = source.indexOf("\n", i);
+			i = newline === -1 ? length : newline;
+			const text = source.slice(start, i);
+			const doc =
+				(text.startsWith("///") && !text.startsWith("////")) || text.startsWith("//!");
+			push("comment", start, i, doc);
+			continue;
+		}
+
+		// Block comments (nested)
+		if (ch === "/" && next === "*") {
+			const start = i;
+			let depth = 1;
+			i += 2;
+			while (i < length && depth > 0) {
+				if (source[i] === "/" && source[i + 1] === "*") {
+					depth++;
+					i += 2;
+				} else if (source[i] === "*" && source[i + 1] === "/") {
+					depth--;
+					i += 2;
+				} else {
+					i++;
+				}
+			}
+			const text = source.slice(start, i);
+			const doc =
+				(text.startsWith("/**") && !text.startsWith("/***") && text !== "/**/") ||
+				text.startsWith("/*!");
+			push("comment", start, i, doc);
+			continue;
+		}
+
+		// Raw strings: r"..", r#".."#, br"..", cr".."
+		const rawPrefix =
+			ch === "r" || ch === "b" || ch === "c"
+				? /^(?:br|cr|r)(#*)"/.exec(source.slice(i, i + 260))
+				: null;
+		if (rawPrefix) {
+			const start = i;
+			const hashes = rawPrefix[1];
+			const terminator = `"${hashes}`;
+			const close = source.indexOf(terminator, i + rawPrefix[0].length);
+			i = close === -1 ? length : close + terminator.length;
+			push("string", start, i);
+			continue;
+		}
+
+		// Raw identifiers: r#match
+		if (ch === "r" && next === "#" && isIdentStart(source[i + 2] ?? "")) {
+			const start = i;
+			i += 2;
+			while (i < length && isIdentContinue(source[i])) {
+				i++;
+			}
+			push("ident", start, i);
+			continue;
+		}
+
+		// Strings with an optional prefix: "..", b"..", c".."
+		if (ch === '"' || ((ch === "b" || ch === "c") && next === '"')) {
+			const start = i;
+			i += ch === '"' ? 1 : 2;
+			while (i < length && source[i] !== '"') {
+				i += source[i] === "\\" ? 2 : 1;
+			}
+			i = Math.min(length, i + 1);
+			push("string", start, i);
+			continue;
+		}
+
+		// Byte chars: b'x'
+		if (ch === "b" && next === "'") {
+			const start = i;
+			i = scanCharLiteral(source, i + 1);
+			push("char", start, i);
+			continue;
+		}
+
+		// Char literals and lifetimes
+		if (ch === "'") {
+			const start = i;
+			if (next === "\\") {
+				i = scanCharLiteral(source, i);
+				push("char", start, i);
+				continue;
+			}
+			const codePoint = source.codePointAt(i + 1);
+			const width = codePoint !== undefined && codePoint > 0xffff ? 2 : 1;
+			if (source[i + 1 + width] === "'") {
+				i += 2 + width;
+				push("char", start, i);
+				continue;
+			}
+			if (next !== undefined && isIdentStart(next)) {
+				i++;
+				while (i < length && isIdentContinue(source[i])) {
+					i++;
+				}
+				push("lifetime", start, i);
+				continue;
+			}
+			i = scanCharLiteral(source, i);
+			push("char", start, i);
+			continue;
+		}
+
+		if (isIdentStart(ch)) {
+			const start = i;
+			while (i < length && isIdentContinue(source[i])) {
+				i++;
+			}
+			push("ident", start, i);
+			continue;
+		}
+
+		if (isDigit(ch)) {
+			const start = i;
+			while (i < length && isIdentContinue(source[i])) {
+				i++;
+			}
+			// Fractional part, but not a range like `0..10` or a method call like `1.max(2)`
+			if (source[i] === "." && isDigit(source[i + 1] ?? "")) {
+				i++;
+				while (i < length && isIdentContinue(source[i])) {
+					i++;
+				}
+			}
+			push("number", start, i);
+			continue;
+		}
+
+		const multi = MULTI_CHAR_PUNCT.find((p) => source.startsWith(p, i));
+		if (multi) {
+			push("punct", i, i + multi.length);
+			i += multi.length;
+			continue;
+		}
+
+		push("punct", i, i + 1);
+		i++;
+	}
+
+	return tokens;
+}
+
+/**
+ * Scan a char literal starting at the opening quote, returning the offset after the closing quote.
+ * Stops at the end of the line if the literal is unterminated.
+ */
+function scanCharLiteral(source: string, quote: number): number {
+	let i = quote + 1;
+	while (i < source.length && source[i] !== "'" && source[i] !== "\n") {
+		i += source[i] === "\\" ? 2 : 1;
+	}
+	return source[i] === "'" ? i + 1 : i;
+}
@@ -0,0 +1,101 @@
+import type { Token } from "./lexer";
+import type { SyntaxTree } from "./syntaxTree";
+
+export interface MaskOptions {
+	/** Blank out comment text (including doc comments) */
+	comments: boolean;
+	/** Blank out attributes such as `#[derive(Debug)]` */
+	attributes: boolean;
+}
+
+/**
+ * Replace the contents of literals (and optionally comments and attributes) with spaces.
+ * Offsets and line breaks are preserved, so regex matches on the masked text map 1:1
+ * back onto the original document. Delimiters and prefixes are kept, so `b"..."` still
+ * looks like a byte string to a rule that wants to find one.
+ */
+export function maskSource(tree: SyntaxTree, options: MaskOptions): string {
+	const chars = tree.text.split("");
+
+	const blank = (start: number, end: number) => {
+		for (let i = start; i < end; i++) {
+			if (chars[i] !== "\n" && chars[i] !== "\r") {
+				chars[i] = " ";
+			}
+		}
+	};
+
+	for (const token of tree.tokens) {
+		switch (token.kind) {
+			case "string":
+			case "char":
+				blankLiteral(token, blank);
+				break;
+			case "comment":
+				if (options.comments) {
+					const terminated = token.text.startsWith("/*") && token.text.endsWith("*/");
+					blank(token.start + 2, terminated ? token.end - 2 : token.end);
+				}
+				break;
+		}
+	}
+
+	if (options.attributes) {
+		for (const node of tree.nodes) {
+			if (node.kind === "attribute") {
+				blank(node.start, node.end);
+			}
+		}
+	}
+
+	return chars.join("");
+}
+
+function blankLiteral(token: Token, blank: (start: number, end: number) => void): void {
+	const quote = token.kind === "char" ? "'" : '"';
+	const open = token.text.indexOf(quote);
+	if (open === -1) {
+		return;
+	}
+
+	// Closing quote plus any raw-string hashes, e.g. `"#`
+	let close = token.text.length;
+	const closing = /(["'])#*$/.exec(token.text);
+	if (closing && closing.index > open) {
+		close = closing.index;
+	}
+
+	blank(token.start + open + 1, token.start + close);
+}
+
+/**
+ * Replace the `#` comments of a TOML document with spaces, leaving strings (which may contain
+ * `#`) alone. Offsets and line breaks are preserved like `maskSource`.
+ */
+export function maskTomlComments(text: string): string {
+	const chars = text.split("");
+	let quote: string | undefined;
+	for (let i = 0; i < chars.length; i++) {
+		const ch = chars[i];
+		if (quote) {
+			if (ch === "\\" && quote.startsWith('"')) {
+				i++;
+			} else if (text.startsWith(quote, i)) {
+				i += quote.length - 1;
+				quote = undefined;
+			} else if (ch === "\n" && quote.length === 1) {
+				quote = undefined; // An unterminated string ends with its line
+			}
+		} else if (ch === '"' || ch === "'") {
+			quote = text.startsWith(ch.repeat(3), i) ? ch.repeat(3) : ch;
+			i += quote.length - 1;
+		} else if (ch === "#") {
+			for (; i < chars.length && chars[i] !== "\n"; i++) {
+				if (chars[i] !== "\r") {
+					chars[i] = " ";
+				}
+			}
+		}
+	}
+	return chars.join("");
+}
@@ -0,0 +1,208 @@
+import type { Token } from "./lexer";
+import type { Span, SyntaxNode, SyntaxTree } from "./syntaxTree";
+
+/**
+ * Where a rule may fire. Boolean predicates require the match to be inside (`true`)
+ * or outside (`false`) the construct; omitted predicates are not checked.
+ */
+export interface ScopePredicate {
+	/** Outer type the enclosing fn returns, e.g. `["Result", "Option"]`; `"()"` for none */
+	fnReturns?: string | string[];
+	/** Inside the body of a `for`, `while` or `loop` in the same function */
+	inLoop?: boolean;
+	/** Inside a `#[test]`-style function or an item marked `#[cfg(test)]` */
+	inTest?: boolean;
+	/** Inside an `async fn` */
+	inAsyncFn?: boolean;
+	/** Inside `impl Trait for Type` for one of these traits (last path segment or full path) */
+	inImplOf?: string | string[];
+}
+
+/**
+ * What encloses an offset in the source
+ */
+export interface ScopeInfo {
+	/** Innermost enclosing function, if any */
+	function?: SyntaxNode;
+	/**
+	 * Outer type name the enclosing function returns (`"()"` when it declares none).
+	 * Unknown inside a closure or async block, since `?` and `return` leave those instead.
+	 */
+	returnType?: string;
+	/** Inside a closure or async block in the enclosing function */
+	inClosure: boolean;
+	inLoop: boolean;
+	inTest: boolean;
+	inAsyncFn: boolean;
+	/** Trait path of the innermost enclosing trait impl */
+	implTrait?: string;
+}
+
+const LOOP_KINDS = new Set(["for_expression", "while_expression", "loop_expression"]);
+
+/**
+ * Work out the enclosing function, loops, test items and trait impl at an offset
+ */
+export function getScope(tree: SyntaxTree, offset: number): ScopeInfo {
+	const scope: ScopeInfo = {
+		inLoop: false,
+		inTest: false,
+		inAsyncFn: false,
+		inClosure: false,
+	};
+	let loopsEnded = false;
+
+	for (let node: SyntaxNode | undefined = tree.nodeAt(offset); node; node = node.parent) {
+		const inBody =
+			node.body !== undefined && offset >= node.body.start && offset < node.body.end;
+
+		if (LOOP_KINDS.has(node.kind) && inBody && !loopsEnded) {
+			scope.inLoop = true;
+		}
+
+		if (node.kind === "function_item" && inBody) {
+			if (!scope.function) {
+				scope.function = node;
+				scope.inClosure = inClosure(tree.tokens, offset, node.body as Span);
+				scope.returnType = scope.inClosure ? undefined : outerTypeName(node.returnType);
+				scope.inAsyncFn = node.modifiers?.includes("async") ?? false;
+			}
+			// A loop around a nested fn item doesn't make the inner body loop
+			loopsEnded = true;
+		}
+
+		if (node.kind === "impl_item" && inBody && scope.implTrait === undefined) {
+			scope.implTrait = node.traitPath;
+		}
+
+		if (node.attributes?.some(isTestAttribute)) {
+			scope.inTest = true;
+		}
+	}
+
+	return scope;
+}
+
+/**
+ * Check a scope against a rule's predicate
+ */
+export function matchesScope(scope: ScopeInfo, predicate: ScopePredicate): boolean {
+	if (predicate.fnReturns !== undefined) {
+		const wanted = Array.isArray(predicate.fnReturns)
+			? predicate.fnReturns
+			: [predicate.fnReturns];
+		if (!scope.returnType || !wanted.includes(scope.returnType)) {
+			return false;
+		}
+	}
+	if (predicate.inLoop !== undefined && predicate.inLoop !== scope.inLoop) {
+		return false;
+	}
+	if (predicate.inTest !== undefined && predicate.inTest !== scope.inTest) {
+		return false;
+	}
+	if (predicate.inAsyncFn !== undefined && predicate.inAsyncFn !== scope.inAsyncFn) {
+		return false;
+	}
+	if (predicate.inImplOf !== undefined) {
+		const traits = Array.isArray(predicate.inImplOf)
+			? predicate.inImplOf
+			: [predicate.inImplOf];
+		if (!scope.implTrait || !traits.some((t) => traitMatches(scope.implTrait as string, t))) {
+			return false;
+		}
+	}
+	return true;
+}
+
+/**
+ * Whether an offset in a function body sits in a closure or async block. Walks back to
+ * the body's `{` and errs towards `true`, e.g. for `a | b` before the offset.
+ */
+function inClosure(tokens: Token[], offset: number, body: Span): boolean {
+	const before = tokens.filter(
+		(t) => t.kind !== "comment" && t.start > body.start && t.end <= offset,
+	);
+	const is = (j: number, ...texts: string[]) => {
+		const token = before[j];
+		return (
+			(token?.kind === "punct" || token?.kind === "ident") && texts.includes(token.text)
+		);
+	};
+
+	// Closure parameters only count until the statement, argument or arm started
+	let statementStarted = false;
+	let depth = 0;
+	for (let j = before.length - 1; j >= 0; j--) {
+		if (is(j, ")", "]", "}")) {
+			depth++;
+		} else if (is(j, "(", "[", "{")) {
+			if (depth > 0) {
+				depth--;
+			} else if (is(j, "{") && is(j - 1, "|", "||", "move", "async")) {
+				return true;
+			} else {
+				statementStarted = false;
+			}
+		} else if (depth === 0 && is(j, ";", ",", "=>")) {
+			statementStarted = true;
+		} else if (depth === 0 && !statementStarted && is(j, "|", "||")) {
+			// `a || b` is an operator, not a closure without parameters
+			if (!is(j, "||") || !isOperand(before[j - 1])) {
+				return true;
+			}
+		}
+	}
+	return false;
+}
+
+/**
+ * Whether a token can end an expression, so a `||` after it is a logical or
+ */
+function isOperand(token: Token | undefined): boolean {
+	switch (token?.kind) {
+		case "ident":
+			return token.text !== "move" && token.text !== "return";
+		case "number":
+		case "string":
+		case "char":
+			return true;
+		case "punct":
+			return [")", "]", "?"].includes(token.text);
+		default:
+			return false;
+	}
+}
+
+/**
+ * `io::Result<()>` → `Result`, `&'a str` → `str`, none → `()`
+ */
+function outerTypeName(returnType: string | undefined): string {
+	if (!returnType) {
+		return "()";
+	}
+	const type = returnType
+		.trim()
+		.replace(/^&\s*('\w+\s+)?(mut\s+)?/, "")
+		.replace(/^(impl|dyn)\s+/, "");
+	if (type.startsWith("(")) {
+		return type.replace(/\s+/g, "") === "()" ? "()" : "tuple";
+	}
+	return type.replace(/<[\s\S]*$/, "").split("::").pop()?.trim() || type;
+}
+
+function traitMatches(traitPath: string, wanted: string): boolean {
+	const path = traitPath.replace(/<[\s\S]*$/, "").replace(/\s+/g, "");
+	return path === wanted || path.split("::").pop() === wanted;
+}
+
+/**
+ * `#[test]`, `#[tokio::test]`, `#[rstest]`, `#[cfg(test)]`, `#[cfg(all(test, ...))]`
+ */
+function isTestAttribute(attribute: string): boolean {
+	const inner = attribute.replace(/^#!?\[\s*|\s*\]$/g, "");
+	if (/^(\w+::)*test$/.test(inner) || inner === "rstest") {
+		return true;
+	}
+	return /^cfg\s*\(/.test(inner) && /\btest\b/.test(inner) && !/\bnot\s*\(\s*test\b/.test(inner);
+}
@@ -0,0 +1,106 @@
+import type { Token } from "./lexer";
+
+/** Stands for every rule when a directive lists no rule ids */
+const ALL_RULES = "*";
+
+const DIRECTIVE = /^\/\/[/!]?\s*compass-(ignore-next-line|ignore|disable)\b(.*)$/;
+
+/**
+ * Rules silenced in a file by suppression comments:
+ *
+ * - `// compass-ignore-next-line unwrap-usage` — the next line of code
+ * - `// compass-ignore unwrap-usage` — the line the comment is on
+ * - `//! compass-disable excessive-clone` — the whole file
+ *
+ * Several ids may be given, separated by spaces or commas; none means every rule.
+ * Anything after `--` is a free-form reason.
+ */
+export class Suppressions {
+	private readonly file = new Set<string>();
+	private readonly lines = new Map<number, Set<string>>();
+
+	/**
+	 * Collect the directives from a file's comment tokens
+	 */
+	public static fromTokens(text: string, tokens: readonly Token[]): Suppressions {
+		const suppressions = new Suppressions();
+		const lineStarts = [0];
+		for (let i = text.indexOf("\n"); i !== -1; i = text.indexOf("\n", i + 1)) {
+			lineStarts.push(i + 1);
+		}
+
+		tokens.forEach((token, i) => {
+			if (token.kind !== "comment") {
+				return;
+			}
+			const directive = DIRECTIVE.exec(token.text.trimEnd());
+			if (!directive) {
+				return;
+			}
+
+			const ids = directive[2].split("--")[0].split(/[\s,]+/).filter(Boolean);
+			const rules = ids.length > 0 ? ids : [ALL_RULES];
+
+			switch (directive[1]) {
+				case "disable":
+					for (const id of rules) {
+						suppressions.file.add(id);
+					}
+					break;
+				case "ignore":
+					suppressions.add(lineOf(lineStarts, token.start), rules);
+					break;
+				case "ignore-next-line": {
+					// Skip blank lines and further comments, so directives can be stacked
+					const next = tokens.slice(i + 1).find((t) => t.kind !== "comment");
+					if (next) {
+						suppressions.add(lineOf(lineStarts, next.start), rules);
+					}
+					break;
+				}
+			}
+		});
+
+		return suppressions;
+	}
+
+	/**
+	 * Whether a file-level `compass-disable` turns the rule off
+	 */
+	public isDisabled(ruleId: string): boolean {
+		return this.file.has(ruleId) || this.file.has(ALL_RULES);
+	}
+
+	/**
+	 * Whether the rule is silenced on a (zero-based) line
+	 */
+	public isSuppressed(ruleId: string, line: number): boolean {
+		if (this.isDisabled(ruleId)) {
+			return true;
+		}
+		const rules = this.lines.get(line);
+		return rules !== undefined && (rules.has(ruleId) || rules.has(ALL_RULES));
+	}
+
+	private add(line: number, rules: string[]): void {
+		const existing = this.lines.get(line) ?? new Set<string>();
+		for (const id of rules) {
+			existing.add(id);
+		}
+		this.lines.set(line, existing);
+	}
+}
+
+function lineOf(lineStarts: number[], offset: number): number {
+	let low = 0;
+	let high = lineStarts.length - 1;
+	while (low < high) {
+		const mid = (low + high + 1) >> 1;
+		if (lineStarts[mid] <= offset) {
+			low = mid;
+		} else {
+			high = mid - 1;
+		}
+	}
+	return low;
+}
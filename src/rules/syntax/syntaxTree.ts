@@ -0,0 +1,947 @@
+import { type Token, tokenize } from "./lexer";
+
+/**
+ * Node kinds recognised by the lightweight Rust parser
+ */
+export const SYNTAX_NODE_KINDS = [
+	"source_file",
+	"attribute",
+	"function_item",
+	"impl_item",
+	"mod_item",
+	"for_expression",
+	"while_expression",
+	"loop_expression",
+	"match_expression",
+	"method_call",
+	"call_expression",
+	"macro_invocation",
+] as const;
+
+export type SyntaxNodeKind = (typeof SYNTAX_NODE_KINDS)[number];
+
+/**
+ * Shape of the expression being matched on in a `match`
+ */
+export const SCRUTINEE_KINDS = [
+	"identifier",
+	"field",
+	"call",
+	"method_call",
+	"expression",
+] as const;
+
+export type ScrutineeKind = (typeof SCRUTINEE_KINDS)[number];
+
+export interface Span {
+	start: number;
+	end: number;
+}
+
+export interface SyntaxNode {
+	kind: SyntaxNodeKind;
+	start: number;
+	end: number;
+	/** End of the header, e.g. `match x {` or `.unwrap()`; rule matches cover start..headEnd */
+	headEnd: number;
+	/** Method, function, macro, module, attribute or implemented trait name */
+	name?: string;
+	parent?: SyntaxNode;
+	children: SyntaxNode[];
+	/** Braced body of items, loops and match expressions */
+	body?: Span;
+	/** Receiver of a method call, e.g. `self.chars` in `self.chars.next()` */
+	receiver?: Span;
+	/** Identifier the receiver ends with, e.g. `iter` in `v.iter().map(...)` */
+	receiverName?: Span;
+	/** Arguments of calls, method calls and macro invocations (without delimiters) */
+	arguments?: Span;
+	argumentCount?: number;
+	/** match_expression: what is being matched on */
+	scrutinee?: ScrutineeKind;
+	/** function_item: parameter list (without parentheses) */
+	parameters?: Span;
+	/** function_item: return type text, if declared */
+	returnType?: string;
+	/** function_item: qualifiers such as `pub`, `async`, `const`, `unsafe` */
+	modifiers?: string[];
+	/** Items: outer attributes attached to the item, e.g. `#[test]` */
+	attributes?: string[];
+	/** impl_item: full trait path, e.g. `fmt::Display` */
+	traitPath?: string;
+	/** impl_item: the implementing type */
+	selfType?: string;
+}
+
+/**
+ * Structural query a rule can use instead of a regex
+ */
+export interface SyntaxQuery {
+	kind: SyntaxNodeKind;
+	/** Node name; an array matches any of the names */
+	name?: string | string[];
+	/** Exact number of arguments for calls and macro invocations */
+	argumentCount?: number;
+	/** match_expression only: required scrutinee shape */
+	scrutinee?: ScrutineeKind;
+}
+
+const KEYWORDS = new Set([
+	"as",
+	"async",
+	"await",
+	"break",
+	"const",
+	"continue",
+	"crate",
+	"dyn",
+	"else",
+	"enum",
+	"extern",
+	"fn",
+	"for",
+	"if",
+	"impl",
+	"in",
+	"let",
+	"loop",
+	"match",
+	"mod",
+	"move",
+	"mut",
+	"pub",
+	"ref",
+	"return",
+	"static",
+	"struct",
+	"super",
+	"trait",
+	"type",
+	"union",
+	"unsafe",
+	"use",
+	"where",
+	"while",
+	"yield",
+]);
+
+const ITEM_MODIFIERS = new Set(["pub", "async", "const", "unsafe", "extern", "default"]);
+
+const OPENERS: Record<string, string> = { "(": ")", "[": "]", "{": "}" };
+
+/**
+ * A best-effort syntax tree for a Rust source file.
+ * It only models the constructs rules and scope checks care about, and never throws on
+ * incomplete code — unrecognised tokens are simply skipped.
+ */
+export class SyntaxTree {
+	public readonly root: SyntaxNode;
+	/** All nodes except the root, ordered by start offset */
+	public readonly nodes: SyntaxNode[];
+
+	private constructor(
+		public readonly text: string,
+		public readonly tokens: Token[],
+		nodes: SyntaxNode[],
+	) {
+		this.root = {
+			kind: "source_file",
+			start: 0,
+			end: text.length,
+			headEnd: 0,
+			children: [],
+		};
+		this.nodes = buildHierarchy(this.root, nodes);
+	}
+
+	public static parse(text: string): SyntaxTree {
+		const tokens = tokenize(text);
+		const parser = new Parser(
+			text,
+			tokens.filter((t) => t.kind !== "comment"),
+		);
+		return new SyntaxTree(text, tokens, parser.parse());
+	}
+
+	/**
+	 * Find all nodes matching a structural query
+	 */
+	public find(query: SyntaxQuery): SyntaxNode[] {
+		return this.nodes.filter((node) => matchesQuery(node, query));
+	}
+
+	/**
+	 * Innermost node containing an offset (the root if none)
+	 */
+	public nodeAt(offset: number): SyntaxNode {
+		let current = this.root;
+		for (;;) {
+			const child = current.children.find((c) => offset >= c.start && offset < c.end);
+			if (!child) {
+				return current;
+			}
+			current = child;
+		}
+	}
+}
+
+function matchesQuery(node: SyntaxNode, query: SyntaxQuery): boolean {
+	if (node.kind !== query.kind) {
+		return false;
+	}
+	if (query.name !== undefined) {
+		const names = Array.isArray(query.name) ? query.name : [query.name];
+		if (!node.name || !names.includes(node.name)) {
+			return false;
+		}
+	}
+	if (query.argumentCount !== undefined && node.argumentCount !== query.argumentCount) {
+		return false;
+	}
+	if (query.scrutinee !== undefined && node.scrutinee !== query.scrutinee) {
+		return false;
+	}
+	return true;
+}
+
+/**
+ * Link nodes into a tree by span containment and return them sorted by start
+ */
+function buildHierarchy(root: SyntaxNode, nodes: SyntaxNode[]): SyntaxNode[] {
+	const sorted = [...nodes].sort((a, b) => a.start - b.start || b.end - a.end);
+	const stack: SyntaxNode[] = [root];
+
+	for (const node of sorted) {
+		while (stack.length > 1) {
+			const top = stack[stack.length - 1];
+			if (node.start >= top.start && node.end <= top.end) {
+				break;
+			}
+			stack.pop();
+		}
+		const parent = stack[stack.length - 1];
+		node.parent = parent;
+		parent.children.push(node);
+		stack.push(node);
+	}
+
+	return sorted;
+}
+
+class Parser {
+	private closeIndex: number[];
+	private openIndex: number[];
+	private nodes: SyntaxNode[] = [];
+
+	constructor(
+		private text: string,
+		private tokens: Token[],
+	) {
+		this.closeIndex = new Array(tokens.length).fill(-1);
+		this.openIndex = new Array(tokens.length).fill(-1);
+		this.matchDelimiters();
+	}
+
+	public parse(): SyntaxNode[] {
+		for (let i = 0; i < this.tokens.length; i++) {
+			const token = this.tokens[i];
+			if (token.kind === "punct") {
+				if (token.text === "#") {
+					this.parseAttribute(i);
+				} else if (token.text === ".") {
+					this.parseMethodCall(i);
+				}
+				continue;
+			}
+			if (token.kind !== "ident") {
+				continue;
+			}
+			switch (token.text) {
+				case "fn":
+					this.parseFunction(i);
+					break;
+				case "impl":
+					this.parseImpl(i);
+					break;
+				case "mod":
+					this.parseMod(i);
+					break;
+				case "for":
+					this.parseFor(i);
+					break;
+				case "while":
+					this.parseBlockExpression(i, "while_expression");
+					break;
+				case "loop":
+					this.parseBlockExpression(i, "loop_expression");
+					break;
+				case "match":
+					this.parseMatch(i);
+					break;
+				default:
+					if (!KEYWORDS.has(token.text)) {
+						this.parseCallOrMacro(i);
+					}
+			}
+		}
+		return this.nodes;
+	}
+
+	// Token helpers
+
+	private isPunct(i: number, text: string): boolean {
+		const token = this.tokens[i];
+		return token !== undefined && token.kind === "punct" && token.text === text;
+	}
+
+	private isIdent(i: number, text?: string): boolean {
+		const token = this.tokens[i];
+		return (
+			token !== undefined &&
+			token.kind === "ident" &&
+			(text === undefined || token.text === text)
+		);
+	}
+
+	private isOpen(i: number): boolean {
+		const token = this.tokens[i];
+		return token?.kind === "punct" && token.text in OPENERS && this.closeIndex[i] !== -1;
+	}
+
+	private slice(from: number, to: number): string {
+		if (to < from) {
+			return "";
+		}
+		return this.text.slice(this.tokens[from].start, this.tokens[to].end);
+	}
+
+	private span(from: number, to: number): Span {
+		if (to < from) {
+			const at = this.tokens[from]?.start ?? this.text.length;
+			return { start: at, end: at };
+		}
+		return { start: this.tokens[from].start, end: this.tokens[to].end };
+	}
+
+	private matchDelimiters(): void {
+		const stack: number[] = [];
+		for (let i = 0; i < this.tokens.length; i++) {
+			const token = this.tokens[i];
+			if (token.kind !== "punct") {
+				continue;
+			}
+			if (token.text in OPENERS) {
+				stack.push(i);
+			} else if (token.text === ")" || token.text === "]" || token.text === "}") {
+				// Tolerate unbalanced input: close the nearest matching opener
+				for (let s = stack.length - 1; s >= 0; s--) {
+					if (OPENERS[this.tokens[stack[s]].text] === token.text) {
+						this.closeIndex[stack[s]] = i;
+						this.openIndex[i] = stack[s];
+						stack.length = s;
+						break;
+					}
+				}
+			}
+		}
+	}
+
+	/**
+	 * Skip a `<...>` generic list starting at `i`; returns the index after the closing `>`
+	 */
+	private skipGenerics(i: number): number {
+		let depth = 0;
+		let j = i;
+		while (j < this.tokens.length) {
+			if (this.isOpen(j)) {
+				j = this.closeIndex[j] + 1;
+				continue;
+			}
+			if (this.isPunct(j, "<")) {
+				depth++;
+			} else if (this.isPunct(j, ">")) {
+				depth--;
+				if (depth === 0) {
+					return j + 1;
+				}
+			} else if (this.isPunct(j, ";") || this.isPunct(j, "{")) {
+				return j;
+			}
+			j++;
+		}
+		return j;
+	}
+
+	/**
+	 * First `{` at the current nesting level from `i`, skipping `(..)` and `[..]` groups.
+	 * Returns -1 if a `;` or an unmatched closer is reached first.
+	 */
+	private findBlockStart(i: number): number {
+		let j = i;
+		while (j < this.tokens.length) {
+			const token = this.tokens[j];
+			if (token.kind === "punct") {
+				if (token.text === "{") {
+					return this.closeIndex[j] === -1 ? -1 : j;
+				}
+				if (token.text === "(" || token.text === "[") {
+					if (this.closeIndex[j] === -1) {
+						return -1;
+					}
+					j = this.closeIndex[j] + 1;
+					continue;
+				}
+				if ([";", ")", "]", "}"].includes(token.text)) {
+					return -1;
+				}
+			}
+			j++;
+		}
+		return -1;
+	}
+
+	/**
+	 * Count top-level arguments inside the group opened at `open`
+	 */
+	private countArguments(open: number): number {
+		const close = this.closeIndex[open];
+		if (close === open + 1) {
+			return 0;
+		}
+		let count = 1;
+		let argStart = true;
+		for (let j = open + 1; j < close; j++) {
+			if (argStart && this.isIdent(j, "move")) {
+				continue;
+			}
+			// Closure parameters: skip `|a, b|`
+			if (argStart && this.isPunct(j, "|")) {
+				j++;
+				while (j < close && !this.isPunct(j, "|")) {
+					j++;
+				}
+				argStart = false;
+				continue;
+			}
+			argStart = false;
+			if (this.isOpen(j)) {
+				j = this.closeIndex[j];
+			} else if (this.isPunct(j, "::") && this.isPunct(j + 1, "<")) {
+				j = this.skipGenerics(j + 1) - 1;
+			} else if (this.isPunct(j, ",")) {
+				count++;
+				argStart = true;
+			}
+		}
+		// Trailing comma
+		if (this.isPunct(close - 1, ",")) {
+			count--;
+		}
+		return count;
+	}
+
+	/**
+	 * Walk back from an item keyword over qualifiers and outer attributes
+	 */
+	private itemPrelude(keyword: number): {
+		start: number;
+		modifiers: string[];
+		attributes: string[];
+	} {
+		const modifiers: string[] = [];
+		const attributes: string[] = [];
+		let k = keyword - 1;
+		let first = keyword;
+
+		while (k >= 0) {
+			const token = this.tokens[k];
+			if (token.kind === "ident" && ITEM_MODIFIERS.has(token.text)) {
+				modifiers.unshift(token.text);
+				first = k;
+				k--;
+			} else if (token.kind === "string" && this.isIdent(k - 1, "extern")) {
+				first = k;
+				k--;
+			} else if (
+				this.isPunct(k, ")") &&
+				this.openIndex[k] > 0 &&
+				this.isIdent(this.openIndex[k] - 1, "pub")
+			) {
+				// pub(crate), pub(super), ...
+				k = this.openIndex[k] - 1;
+			} else {
+				break;
+			}
+		}
+
+		while (k >= 0 && this.isPunct(k, "]") && this.openIndex[k] > 0) {
+			const open = this.openIndex[k];
+			if (!this.isPunct(open - 1, "#")) {
+				break;
+			}
+			attributes.unshift(this.slice(open - 1, k));
+			first = open - 1;
+			k = open - 2;
+		}
+
+		return { start: this.tokens[first].start, modifiers, attributes };
+	}
+
+	/**
+	 * Walk back from the `.` of a method call to find the start of its receiver
+	 */
+	private receiverStart(dot: number): number {
+		let k = dot - 1;
+		let start = -1;
+
+		while (k >= 0) {
+			while (this.isPunct(k, "?")) {
+				k--;
+			}
+			const token = this.tokens[k];
+			if (!token) {
+				break;
+			}
+
+			if (token.kind === "punct" && (token.text === ")" || token.text === "]")) {
+				const open = this.openIndex[k];
+				if (open === -1) {
+					break;
+				}
+				start = this.tokens[open].start;
+				k = open - 1;
+				// Callee of a call or macro invocation
+				if (this.isPunct(k, "!") && this.isIdent(k - 1)) {
+					k--;
+				}
+				if (this.isPunct(k, ">")) {
+					k = this.skipTurbofishBack(k);
+				}
+				if (this.isIdent(k) && !KEYWORDS.has(this.tokens[k].text)) {
+					start = this.tokens[k].start;
+					k--;
+				}
+			} else if (
+				(token.kind === "ident" && !KEYWORDS.has(token.text)) ||
+				token.kind === "number" ||
+				token.kind === "string" ||
+				token.kind === "char"
+			) {
+				start = token.start;
+				k--;
+			} else {
+				break;
+			}
+
+			if (this.isPunct(k, ".") || this.isPunct(k, "::")) {
+				k--;
+				continue;
+			}
+			break;
+		}
+
+		return start;
+	}
+
+	/**
+	 * The variable, field or method name whose value a method is called on
+	 */
+	private receiverName(dot: number): Span | undefined {
+		let k = dot - 1;
+		while (this.isPunct(k, "?")) {
+			k--;
+		}
+		if (this.isPunct(k, ")") || this.isPunct(k, "]")) {
+			const open = this.openIndex[k];
+			if (open === -1) {
+				return undefined;
+			}
+			k = open - 1;
+			if (this.isPunct(k, ">")) {
+				k = this.skipTurbofishBack(k);
+			}
+		}
+		if (!this.isIdent(k) || KEYWORDS.has(this.tokens[k].text)) {
+			return undefined;
+		}
+		return this.span(k, k);
+	}
+
+	/**
+	 * From the `>` closing a turbofish, return the index of the callee before `::<`
+	 */
+	private skipTurbofishBack(k: number): number {
+		let depth = 0;
+		for (let j = k; j >= 0; j--) {
+			if (this.isPunct(j, ">")) {
+				depth++;
+			} else if (this.isPunct(j, "<")) {
+				depth--;
+				if (depth === 0) {
+					return this.isPunct(j - 1, "::") ? j - 2 : k;
+				}
+			}
+		}
+		return k;
+	}
+
+	private push(node: Omit<SyntaxNode, "children">): void {
+		this.nodes.push({ ...node, children: [] });
+	}
+
+	// Constructs
+
+	private parseAttribute(hash: number): void {
+		let open = hash + 1;
+		if (this.isPunct(open, "!")) {
+			open++;
+		}
+		if (!this.isPunct(open, "[") || !this.isOpen(open)) {
+			return;
+		}
+		const close = this.closeIndex[open];
+		const end = this.tokens[close].end;
+		this.push({
+			kind: "attribute",
+			start: this.tokens[hash].start,
+			end,
+			headEnd: end,
+			name: this.isIdent(open + 1) ? this.tokens[open + 1].text : undefined,
+			arguments: this.span(open + 1, close - 1),
+		});
+	}
+
+	private parseMethodCall(dot: number): void {
+		if (!this.isIdent(dot + 1) || this.isIdent(dot + 1, "await")) {
+			return;
+		}
+		let open = dot + 2;
+		if (this.isPunct(open, "::") && this.isPunct(open + 1, "<")) {
+			open = this.skipGenerics(open + 1);
+		}
+		if (!this.isPunct(open, "(") || !this.isOpen(open)) {
+			return;
+		}
+		const close = this.closeIndex[open];
+		const receiverStart = this.receiverStart(dot);
+		const end = this.tokens[close].end;
+		this.push({
+			kind: "method_call",
+			start: this.tokens[dot].start,
+			end,
+			headEnd: end,
+			name: this.tokens[dot + 1].text,
+			receiver:
+				receiverStart === -1
+					? undefined
+					: { start: receiverStart, end: this.tokens[dot - 1].end },
+			receiverName: this.receiverName(dot),
+			arguments: this.span(open + 1, close - 1),
+			argumentCount: this.countArguments(open),
+		});
+	}
+
+	private parseCallOrMacro(i: number): void {
+		if (this.isPunct(i - 1, ".")) {
+			return;
+		}
+
+		// Macro invocation: name!(..), name![..], name! { .. }
+		if (this.isPunct(i + 1, "!") && this.isOpen(i + 2)) {
+			const open = i + 2;
+			const close = this.closeIndex[open];
+			this.push({
+				kind: "macro_invocation",
+				start: this.tokens[i].start,
+				end: this.tokens[close].end,
+				headEnd: this.tokens[open].end,
+				name: this.tokens[i].text,
+				arguments: this.span(open + 1, close - 1),
+				argumentCount: this.countArguments(open),
+			});
+			return;
+		}
+
+		let open = i + 1;
+		if (this.isPunct(open, "::") && this.isPunct(open + 1, "<")) {
+			open = this.skipGenerics(open + 1);
+		}
+		if (!this.isPunct(open, "(") || !this.isOpen(open)) {
+			return;
+		}
+		// Declarations, not calls
+		if (
+			this.isIdent(i - 1, "fn") ||
+			this.isIdent(i - 1, "struct") ||
+			this.isIdent(i - 1, "enum") ||
+			this.isIdent(i - 1, "union")
+		) {
+			return;
+		}
+
+		// Include the path, e.g. `String::from`
+		let first = i;
+		while (this.isPunct(first - 1, "::") && this.isIdent(first - 2)) {
+			first -= 2;
+		}
+		const close = this.closeIndex[open];
+		this.push({
+			kind: "call_expression",
+			start: this.tokens[first].start,
+			end: this.tokens[close].end,
+			headEnd: this.tokens[open].end,
+			name: this.tokens[i].text,
+			arguments: this.span(open + 1, close - 1),
+			argumentCount: this.countArguments(open),
+		});
+	}
+
+	private parseFunction(fn: number): void {
+		if (!this.isIdent(fn + 1)) {
+			// `fn(u8) -> u8` pointer type
+			return;
+		}
+		let j = fn + 2;
+		if (this.isPunct(j, "<")) {
+			j = this.skipGenerics(j);
+		}
+		if (!this.isPunct(j, "(") || !this.isOpen(j)) {
+			return;
+		}
+		const paramsOpen = j;
+		const paramsClose = this.closeIndex[j];
+		j = paramsClose + 1;
+
+		let returnType: string | undefined;
+		if (this.isPunct(j, "->")) {
+			const typeStart = j + 1;
+			let depth = 0;
+			let k = typeStart;
+			while (k < this.tokens.length) {
+				const atEnd =
+					this.isPunct(k, "{") || this.isPunct(k, ";") || this.isIdent(k, "where");
+				if (depth === 0 && atEnd) {
+					break;
+				}
+				if (this.isOpen(k)) {
+					k = this.closeIndex[k] + 1;
+					continue;
+				}
+				if (this.isPunct(k, "<")) {
+					depth++;
+				} else if (this.isPunct(k, ">")) {
+					depth--;
+				}
+				k++;
+			}
+			returnType = this.slice(typeStart, k - 1);
+			j = k;
+		}
+
+		const prelude = this.itemPrelude(fn);
+		const bodyOpen = this.findBlockStart(j);
+		let end: number;
+		let headEnd: number;
+		let body: Span | undefined;
+
+		if (bodyOpen === -1) {
+			// Declaration without a body, e.g. in a trait
+			let k = j;
+			while (k < this.tokens.length && !this.isPunct(k, ";")) {
+				k++;
+			}
+			const last = Math.min(k, this.tokens.length - 1);
+			end = this.tokens[last].end;
+			headEnd = end;
+		} else {
+			const bodyClose = this.closeIndex[bodyOpen];
+			end = this.tokens[bodyClose].end;
+			headEnd = this.tokens[bodyOpen].end;
+			body = { start: this.tokens[bodyOpen].start, end };
+		}
+
+		this.push({
+			kind: "function_item",
+			start: prelude.start,
+			end,
+			headEnd,
+			name: this.tokens[fn + 1].text,
+			body,
+			parameters: this.span(paramsOpen + 1, paramsClose - 1),
+			returnType,
+			modifiers: prelude.modifiers,
+			attributes: prelude.attributes,
+		});
+	}
+
+	private parseImpl(impl: number): void {
+		// Only `impl` in item position, not `impl Trait` in a type
+		const prev = this.tokens[impl - 1];
+		const itemPosition =
+			prev === undefined ||
+			(prev.kind === "punct" && ["}", ";", "{", "]"].includes(prev.text)) ||
+			this.isIdent(impl - 1, "unsafe") ||
+			this.isIdent(impl - 1, "default");
+		if (!itemPosition) {
+			return;
+		}
+
+		let j = impl + 1;
+		if (this.isPunct(j, "<")) {
+			j = this.skipGenerics(j);
+		}
+		const bodyOpen = this.findBlockStart(j);
+		if (bodyOpen === -1) {
+			return;
+		}
+
+		// Split the header at a top-level `for` (but not `for<'a>`)
+		let forIndex = -1;
+		let depth = 0;
+		let headerEnd = bodyOpen - 1;
+		for (let k = j; k < bodyOpen; k++) {
+			if (this.isPunct(k, "<")) {
+				depth++;
+			} else if (this.isPunct(k, ">")) {
+				depth--;
+			} else if (depth === 0 && this.isIdent(k, "where")) {
+				headerEnd = k - 1;
+				break;
+			} else if (depth === 0 && this.isIdent(k, "for") && !this.isPunct(k + 1, "<")) {
+				forIndex = k;
+			}
+		}
+
+		let traitPath: string | undefined;
+		let name: string | undefined;
+		let selfType: string;
+		if (forIndex !== -1) {
+			traitPath = this.slice(j, forIndex - 1).replace(/^!\s*/, "");
+			selfType = this.slice(forIndex + 1, headerEnd);
+			name = traitPath.replace(/<[\s\S]*$/, "").split("::").pop()?.trim();
+		} else {
+			selfType = this.slice(j, headerEnd);
+		}
+
+		const prelude = this.itemPrelude(impl);
+		const bodyClose = this.closeIndex[bodyOpen];
+		this.push({
+			kind: "impl_item",
+			start: prelude.start,
+			end: this.tokens[bodyClose].end,
+			headEnd: this.tokens[bodyOpen].end,
+			name,
+			body: { start: this.tokens[bodyOpen].start, end: this.tokens[bodyClose].end },
+			traitPath,
+			selfType,
+			attributes: prelude.attributes,
+		});
+	}
+
+	private parseMod(mod: number): void {
+		if (!this.isIdent(mod + 1) || !this.isPunct(mod + 2, "{") || !this.isOpen(mod + 2)) {
+			return;
+		}
+		const prelude = this.itemPrelude(mod);
+		const bodyClose = this.closeIndex[mod + 2];
+		this.push({
+			kind: "mod_item",
+			start: prelude.start,
+			end: this.tokens[bodyClose].end,
+			headEnd: this.tokens[mod + 2].end,
+			name: this.tokens[mod + 1].text,
+			body: { start: this.tokens[mod + 2].start, end: this.tokens[bodyClose].end },
+			modifiers: prelude.modifiers,
+			attributes: prelude.attributes,
+		});
+	}
+
+	private parseFor(keyword: number): void {
+		// `for<'a>` bounds and `impl Trait for Type` are not loops
+		if (this.isPunct(keyword + 1, "<")) {
+			return;
+		}
+		const bodyOpen = this.findBlockStart(keyword + 1);
+		if (bodyOpen === -1) {
+			return;
+		}
+		let hasIn = false;
+		for (let k = keyword + 1; k < bodyOpen; k++) {
+			if (this.isIdent(k, "in")) {
+				hasIn = true;
+				break;
+			}
+		}
+		if (hasIn) {
+			this.pushBlockExpression(keyword, bodyOpen, "for_expression");
+		}
+	}
+
+	private parseBlockExpression(keyword: number, kind: SyntaxNodeKind): void {
+		const bodyOpen = this.findBlockStart(keyword + 1);
+		if (bodyOpen === -1) {
+			return;
+		}
+		if (kind === "loop_expression" && bodyOpen !== keyword + 1) {
+			return;
+		}
+		this.pushBlockExpression(keyword, bodyOpen, kind);
+	}
+
+	private pushBlockExpression(keyword: number, bodyOpen: number, kind: SyntaxNodeKind): void {
+		const bodyClose = this.closeIndex[bodyOpen];
+		this.push({
+			kind,
+			start: this.tokens[keyword].start,
+			end: this.tokens[bodyClose].end,
+			headEnd: this.tokens[bodyOpen].end,
+			body: { start: this.tokens[bodyOpen].start, end: this.tokens[bodyClose].end },
+		});
+	}
+
+	private parseMatch(keyword: number): void {
+		const bodyOpen = this.findBlockStart(keyword + 1);
+		if (bodyOpen === -1 || bodyOpen === keyword + 1) {
+			return;
+		}
+		const bodyClose = this.closeIndex[bodyOpen];
+		this.push({
+			kind: "match_expression",
+			start: this.tokens[keyword].start,
+			end: this.tokens[bodyClose].end,
+			headEnd: this.tokens[bodyOpen].end,
+			body: { start: this.tokens[bodyOpen].start, end: this.tokens[bodyClose].end },
+			scrutinee: this.classifyScrutinee(keyword + 1, bodyOpen - 1),
+		});
+	}
+
+	private classifyScrutinee(from: number, to: number): ScrutineeKind {
+		if (from === to && this.isIdent(from) && !KEYWORDS.has(this.tokens[from].text)) {
+			return "identifier";
+		}
+
+		let isField = this.isIdent(from);
+		for (let k = from + 1; k <= to && isField; k += 2) {
+			const segment = this.tokens[k + 1];
+			isField =
+				this.isPunct(k, ".") &&
+				segment !== undefined &&
+				(segment.kind === "ident" || segment.kind === "number");
+		}
+		if (isField && (to - from) % 2 === 0) {
+			return "field";
+		}
+
+		if (this.isPunct(to, ")") && this.openIndex[to] > from) {
+			const open = this.openIndex[to];
+			if (this.isIdent(open - 1) && this.isPunct(open - 2, ".")) {
+				return "method_call";
+			}
+			if (this.isIdent(open - 1) || this.isPunct(open - 1, ">")) {
+				return "call";
+			}
+		}
+
+		return "expression";
+	}
+}
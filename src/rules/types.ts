@@ -1,12 +1,76 @@
+import type { ScopePredicate, Span, SyntaxQuery } from "./syntax";
+
+/**
+ * One edit of a template fix.
+ *
+ * Templates can reference `$match` (or `$0`), `$target` (the current text of the edit's span),
+ * `$receiver`, `$args` and `$method` of the matched method call, and the rule's regex capture
+ * groups as `$1`…`$9` or `$name` / `${name}`. `$$` is a literal `$`.
+ */
+export interface RuleFixEdit {
+	/**
+	 * Span to replace: `"match"` (default), `"call"`, `"receiver"` or `"args"` of the matched
+	 * method call, `"receiverDefinition"` (the initializer of the `let` binding the receiver),
+	 * a capture group (`"$1"`, `"$name"`), or `"imports"` to add a line after the `use` items
+	 */
+	target?: string;
+	/** Replacement template */
+	text: string;
+}
+
 export interface RuleSuggestedFix {
 	description: string;
-	before: string;
-	after: string;
+	/** Literal text near the match to replace with `after` (simple form) */
+	before?: string;
+	after?: string;
+	/** Template that replaces the match, e.g. `"$receiver.peekable()"` */
+	replacement?: string;
+	/** Further template edits applied together with `replacement` */
+	edits?: RuleFixEdit[];
+	/**
+	 * Templates are snippets: `$1`, `${1:default}` and `$0` become tab stops,
+	 * so capture groups must be referenced by name
+	 */
+	snippet?: boolean;
 }
 
+/**
+ * Text around a match that, when it matches `pattern`, suppresses the match
+ */
+export interface RuleExclusionWindow {
+	pattern: string;
+	/** Characters before the match to include (default 0) */
+	before?: number;
+	/** Characters after the match to include (default 0) */
+	after?: number;
+	/** Search the whole enclosing function instead; `before`/`after` apply outside functions */
+	function?: boolean;
+}
+
+/**
+ * Kind of value a method-call rule is about, used to pick between overlapping rules
+ */
+export type ReceiverType = "iterator" | "option" | "result" | "string";
+
+/**
+ * Files a rule runs on
+ */
+export type RuleLanguage = "rust" | "cargo-toml";
+
 export interface Rule {
 	id: string;
-	pattern: string;
+	/** Regex matched against the document text (either this or `query` is required) */
+	pattern?: string;
+	/** Structural query matched against the parsed syntax tree instead of a regex */
+	query?: SyntaxQuery;
+	/** Only match inside (or outside) certain code, e.g. `{ "inTest": false }` */
+	scope?: ScopePredicate;
+	/** Skip matches whose line(s) match this regex, e.g. an existing type annotation */
+	excludeIfLineMatches?: string;
+	/** Skip matches when the match plus surrounding text matches a regex */
+	excludeIfWithin?: RuleExclusionWindow;
+	/** Skip the rule entirely in files matching this regex */
+	excludeIfFileMatches?: string;
 	title: string;
 	rustTerm: string;
 	explanation: string;
@@ -15,15 +79,99 @@ export interface Rule {
 	confidence: number;
 	contexts: string[];
 	suggestedFix?: RuleSuggestedFix;
+	/** Let `pattern` match inside comments and doc comments (masked out by default) */
+	matchInComments?: boolean;
+	/** Let `pattern` match inside attributes like `#[derive(...)]` (masked out by default) */
+	matchInAttributes?: boolean;
 	/** URL to official Rust documentation for this concept */
 	officialDoc?: string;
+	/**
+	 * Only show when the crate depends on at least one of these crates (e.g. `["tokio"]`),
+	 * optionally at a version (`{ "name": "clap", "version": ">=4" }`)
+	 */
+	requiresDependency?: string | DependencyRequirement | (string | DependencyRequirement)[];
+	/** Only show when a dependency category is in use (e.g. `"async"`, `"serialization"`) */
+	requiresCategory?: string | string[];
+	/**
+	 * What the matched code needs from Cargo.toml to compile, e.g. serde's `derive` feature.
+	 * Matches in a crate that doesn't meet it get a warning explaining what's missing.
+	 */
+	expectsDependency?: DependencyRequirement;
+	/** Receiver types the matched method call applies to, e.g. `["option", "result"]` */
+	receiverTypes?: ReceiverType[];
+	/**
+	 * Oldest Rust the advice works on, e.g. `"1.65"` for let-else. Crates whose `rust-version`
+	 * (or pinned toolchain) is older get `olderRust` instead, or no hint without it.
+	 */
+	minRustVersion?: string;
+	/** Replaces the advice for crates older than `minRustVersion` */
+	olderRust?: OlderRustAdvice;
+	/** Only show in crates on these editions, e.g. `["2021", "2024"]` */
+	editions?: string[];
+	/**
+	 * Edition that changes what the matched code means or whether it compiles, e.g. `"2024"`.
+	 * These rules only run for the edition migration report, never as everyday hints.
+	 */
+	editionChange?: string;
+	/**
+	 * Files the rule runs on: Rust source (default) or `Cargo.toml` manifests, where `pattern`
+	 * sees the TOML with comments blanked out
+	 */
+	language?: RuleLanguage;
+}
+
+/**
+ * What a rule says instead when the crate can't use the syntax it recommends
+ */
+export interface OlderRustAdvice {
+	explanation: string;
+	example?: string;
+	/** Fix that works on the older Rust; the rule's own fix is dropped either way */
+	suggestedFix?: RuleSuggestedFix;
 }
 
 export interface RuleFile {
+	$schema?: string;
 	category: string;
 	rules: Rule[];
 }
 
+/**
+ * A rule from a workspace rule pack that replaced an earlier rule with the same id
+ */
+export interface RuleOverride {
+	ruleId: string;
+	/** Rule file that won */
+	source: string;
+	/** Rule file whose rule was replaced */
+	overriddenSource: string;
+}
+
+/**
+ * A problem found while loading a rule file
+ */
+export interface RuleLoadIssue {
+	severity: "error" | "warning";
+	/** Absolute path of the rule file, or of the directory when it couldn't be read */
+	file: string;
+	ruleId?: string;
+	message: string;
+	/** Whether the rule (or the whole file, without a ruleId) was left out */
+	skipped: boolean;
+	/** Zero-based position of the rule's `"id"` in the file, when known */
+	line?: number;
+	character?: number;
+}
+
+/**
+ * What the last (re)load of rules did
+ */
+export interface RuleLoadReport {
+	files: Array<{ file: string; loaded: number; skipped: number; workspace: boolean }>;
+	issues: RuleLoadIssue[];
+	overrides: RuleOverride[];
+}
+
 export interface RuleMatch {
 	rule: Rule;
 	range: {
@@ -33,6 +181,72 @@ export interface RuleMatch {
 		character: number;
 	};
 	matchedText: string;
+	/** Offsets of the regex capture groups that took part, keyed by number and by name */
+	captures?: Record<string, Span>;
+}
+
+/**
+ * A crate a rule needs, with a Cargo version requirement such as `">=4"` or `"^1.2"` and
+ * features that must be enabled
+ */
+export interface DependencyRequirement {
+	name: string;
+	version?: string;
+	features?: string[];
+}
+
+/**
+ * Crates the workspace depends on and the Rust it targets, used to gate rules
+ */
+export interface ProjectDependencies {
+	/** Normalized crate names (lowercase, `-` instead of `_`) */
+	crates: string[];
+	/** Version per normalized crate name: from Cargo.lock, else the lowest the manifest allows */
+	versions: Map<string, string>;
+	/**
+	 * Enabled features per normalized crate name: as Cargo resolved them when `cargo metadata`
+	 * ran, else the ones Cargo.toml names (plus `default` unless turned off)
+	 */
+	features: Map<string, string[]>;
+	/** Normalized crate names whose `features` come from `cargo metadata` */
+	resolvedFeatures?: Set<string>;
+	/** Hint categories such as `async` or `serialization` */
+	categories: string[];
+	/** Edition of the crate owning the file; unknown outside a Cargo package */
+	edition?: string;
+	/** Oldest Rust the crate must build with, e.g. `1.65.0` (see `CargoCrate.rustVersion`) */
+	rustVersion?: string;
+}
+
+/**
+ * Severity for publishing a rule's matches as diagnostics; `"none"` publishes nothing
+ */
+export type RuleDiagnosticSeverity = "error" | "warning" | "information" | "hint" | "none";
+
+/**
+ * User overrides for one rule, from the `rustCompass.rules` setting
+ */
+export interface RuleUserSettings {
+	/** `false` turns the rule off everywhere */
+	enabled?: boolean;
+	/** Replaces the rule's own confidence */
+	confidence?: number;
+	/** Force the inline decoration on or off regardless of `minConfidence` */
+	showDecoration?: boolean;
+	/** Report matches (and teachable moments) in the Problems panel with this severity */
+	diagnosticSeverity?: RuleDiagnosticSeverity;
+	/** Include the rule's fix in the `source.fixAll.rustCompass` code action */
+	fixAll?: boolean;
+}
+
+/**
+ * Rule-related user settings
+ */
+export interface RuleSettings {
+	/** Rules below this confidence get no inline decoration */
+	minConfidence: number;
+	/** Overrides keyed by rule id */
+	rules: Record<string, RuleUserSettings>;
 }
 
 export type ProjectContext = "general" | "parser" | "web" | "cli" | "systems";
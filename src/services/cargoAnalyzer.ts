@@ -1,5 +1,22 @@
 import * as path from "node:path";
 import * as vscode from "vscode";
+import { minimumVersion, normalizeCrateName, type ProjectDependencies } from "../rules";
+import {
+	applyResolvedDependencies,
+	type CargoDependency,
+	type CargoManifest,
+	type DependencyLocation,
+	enabledFeatures,
+	isWorkspaceMember,
+	type LockedPackage,
+	parseLockfile,
+	parseManifest,
+	parseToolchainVersion,
+	resolveLockedVersions,
+	resolveWorkspaceDependencies,
+	resolveWorkspacePackage,
+} from "./cargoManifest";
+import { type ResolvedDependency, readCargoMetadata } from "./cargoMetadata";
 
 /**
  * Detected dependencies and their categories
@@ -31,12 +48,98 @@ const DEPENDENCY_PATTERNS: Record<keyof DetectedDependencies, string[]> = {
 	other: [],
 };
 
+/**
+ * One-line descriptions of well-known crates, for when `cargo metadata` can't supply the
+ * crate's own. Keyed by normalized crate name.
+ */
+const CRATE_DESCRIPTIONS: Record<string, string> = {
+	tokio: "Async runtime: tasks, timers, networking and file I/O",
+	"async-std": "Async runtime modelled on the standard library's API",
+	smol: "Small and fast async runtime",
+	futures: "Future and Stream traits, combinators and executor building blocks",
+	"async-trait": "Macro for async fns in traits that need `dyn` support",
+	"actix-web": "Web framework built on the actix runtime",
+	"actix-rt": "Tokio-based runtime for actix",
+	axum: "Web framework built on tokio, tower and hyper",
+	warp: "Web framework composing routes from filters",
+	rocket: "Web framework focused on ease of use and type-safe routing",
+	hyper: "Low-level HTTP/1 and HTTP/2 client and server",
+	reqwest: "High-level HTTP client",
+	tower: "Service trait and middleware for networking clients and servers",
+	serde: "Framework for serializing and deserializing Rust data structures",
+	"serde-json": "JSON support for serde",
+	"serde-yaml": "YAML support for serde",
+	toml: "TOML parser and serde support",
+	ron: "Rusty Object Notation, a serde format shaped like Rust syntax",
+	bincode: "Compact binary serde format",
+	postcard: "Compact serde format for embedded and constrained devices",
+	clap: "Command-line argument parser, with a derive API",
+	structopt: "Derive-based argument parsing (superseded by clap's derive)",
+	argh: "Small derive-based argument parser",
+	"pico-args": "Minimal argument parser with no dependencies",
+	lexopt: "Minimal, pedantic argument parser",
+	nom: "Parser combinators working on bytes or text",
+	pest: "Parser generator from PEG grammar files",
+	lalrpop: "LR(1) parser generator",
+	logos: "Fast lexer generated from a derive macro",
+	chumsky: "Parser combinators with error recovery",
+	winnow: "Parser combinators, a fork of nom",
+	combine: "Parser combinators in the style of Parsec",
+	anyhow: "Flexible error type for applications, with context",
+	thiserror: "Derive macro for std::error::Error on your own error types",
+	eyre: "Error reporting with customizable report handlers",
+	"color-eyre": "Colorful, detailed error reports for eyre",
+	miette: "Diagnostic error reports with source snippets",
+	sqlx: "Async SQL toolkit with compile-time checked queries",
+	diesel: "ORM and query builder for SQL databases",
+	"sea-orm": "Async ORM built on sqlx",
+	rusqlite: "SQLite bindings",
+	mongodb: "MongoDB driver",
+	redis: "Redis client",
+	proptest: "Property-based testing with shrinking",
+	quickcheck: "Property-based testing from random inputs",
+	criterion: "Statistics-driven benchmarking",
+	fake: "Generates fake data for tests",
+	mockall: "Mock objects generated from traits",
+	rand: "Random number generation",
+	regex: "Regular expressions with linear-time matching",
+	log: "Logging facade; a logger crate decides where messages go",
+	"env-logger": "Logger for the log crate configured by RUST_LOG",
+	tracing: "Structured, span-based diagnostics",
+	"tracing-subscriber": "Collects and formats tracing output",
+	chrono: "Dates, times and time zones",
+	itertools: "Extra iterator adaptors and functions",
+	rayon: "Data parallelism: parallel iterators over a thread pool",
+	"once-cell": "Lazily initialized values (mostly in std since Rust 1.80)",
+	"lazy-static": "Lazily initialized statics (superseded by std's LazyLock)",
+	bytes: "Cheaply cloneable byte buffers",
+	uuid: "Generate and parse UUIDs",
+};
+
+/**
+ * A package in the workspace, with `workspace = true` dependencies filled in from its root
+ */
+export interface CargoCrate {
+	name: string;
+	/** Folder holding the crate's Cargo.toml */
+	root: vscode.Uri;
+	manifest: CargoManifest;
+	dependencies: DetectedDependencies;
+	/** `edition`, or `"2015"` when the manifest doesn't set one */
+	edition: string;
+	/**
+	 * Oldest Rust the crate has to build with, as a full version: `rust-version`, else the
+	 * toolchain pinned by the closest rust-toolchain.toml
+	 */
+	rustVersion?: string;
+}
+
 /**
  * Service to analyze Cargo.toml and detect project dependencies
  */
 export class CargoAnalyzerService {
 	private static instance: CargoAnalyzerService;
-	private cachedDependencies: DetectedDependencies | null = null;
+	private crates: CargoCrate[] | null = null;
 	private fileWatcher: vscode.FileSystemWatcher | null = null;
 
 	// Event emitter for dependency changes
@@ -51,11 +154,13 @@ export class CargoAnalyzerService {
 	}
 
 	/**
-	 * Initialize the service and start watching Cargo.toml
+	 * Initialize the service and start watching Cargo.toml, Cargo.lock and rust-toolchain files
 	 */
 	public async initialize(): Promise<void> {
-		// Watch for Cargo.toml changes
-		this.fileWatcher = vscode.workspace.createFileSystemWatcher("**/Cargo.toml");
+		// Watch for Cargo.toml, Cargo.lock and rust-toolchain(.toml) changes
+		this.fileWatcher = vscode.workspace.createFileSystemWatcher(
+			"**/{Cargo.toml,Cargo.lock,rust-toolchain,rust-toolchain.toml}",
+		);
 		this.fileWatcher.onDidChange(() => this.invalidateCache());
 		this.fileWatcher.onDidCreate(() => this.invalidateCache());
 		this.fileWatcher.onDidDelete(() => this.invalidateCache());
@@ -65,91 +170,207 @@ export class CargoAnalyzerService {
 	}
 
 	/**
-	 * Get detected dependencies (cached)
+	 * Every package in the workspace (cached)
 	 */
-	public async getDependencies(): Promise<DetectedDependencies> {
-		if (!this.cachedDependencies) {
+	public async getCrates(): Promise<CargoCrate[]> {
+		if (!this.crates) {
 			await this.scanDependencies();
 		}
-		return this.cachedDependencies || this.emptyDependencies();
+		return this.crates ?? [];
 	}
 
 	/**
-	 * Check if a specific category of dependencies is used
+	 * The package a source file belongs to: the one whose Cargo.toml is in the closest folder
 	 */
-	public async hasCategory(category: keyof DetectedDependencies): Promise<boolean> {
-		const deps = await this.getDependencies();
-		return deps[category].length > 0;
+	public async getCrateFor(uri: vscode.Uri): Promise<CargoCrate | undefined> {
+		return this.findCrate(await this.getCrates(), uri);
 	}
 
 	/**
-	 * Get suggested project context based on dependencies
+	 * Dependencies of every crate in the workspace together (cached)
 	 */
-	public async suggestProjectContext(): Promise<{
-		context: string;
-		reason: string;
-	} | null> {
-		const deps = await this.getDependencies();
+	public async getDependencies(): Promise<DetectedDependencies> {
+		return this.combineDependencies(await this.getCrates());
+	}
 
-		// Priority order for context suggestion
-		if (deps.parsing.length > 0) {
-			return {
-				context: "parser",
-				reason: `Using ${deps.parsing.join(", ")} - parser/lexer project detected`,
-			};
-		}
-		if (deps.web.length > 0) {
-			return {
-				context: "web",
-				reason: `Using ${deps.web.join(", ")} - web project detected`,
-			};
-		}
-		if (deps.cli.length > 0) {
-			return {
-				context: "cli",
-				reason: `Using ${deps.cli.join(", ")} - CLI project detected`,
-			};
+	/**
+	 * Dependencies of the crate owning a file, or of the whole workspace if no crate does
+	 */
+	public async getDependenciesFor(uri: vscode.Uri): Promise<DetectedDependencies> {
+		const crates = await this.getCrates();
+		return this.findCrate(crates, uri)?.dependencies ?? this.combineDependencies(crates);
+	}
+
+	/**
+	 * Every dependency declared in Cargo.toml, with its kind, target, version and features.
+	 * With a file, only those of the crate owning it.
+	 */
+	public async getCargoDependencies(uri?: vscode.Uri): Promise<CargoDependency[]> {
+		const crates = await this.getCrates();
+		const owner = uri && this.findCrate(crates, uri);
+		return (owner ? [owner] : crates).flatMap((crate) => crate.manifest.dependencies);
+	}
+
+	/**
+	 * A dependency by crate name, following `package = ".."` renames
+	 */
+	public async getCargoDependency(
+		crate: string,
+		uri?: vscode.Uri,
+	): Promise<CargoDependency | undefined> {
+		const name = normalizeCrateName(crate);
+		const dependencies = await this.getCargoDependencies(uri);
+		return dependencies.find((dep) => normalizeCrateName(dep.package) === name);
+	}
+
+	/**
+	 * Features enabled on a crate (see `enabledFeatures`), for the crate owning `uri` if given;
+	 * undefined when it isn't a dependency
+	 */
+	public async getEnabledFeatures(
+		crate: string,
+		uri?: vscode.Uri,
+	): Promise<string[] | undefined> {
+		const dep = await this.getCargoDependency(crate, uri);
+		return dep && enabledFeatures(dep);
+	}
+
+	/**
+	 * Category of a crate in `DEPENDENCY_PATTERNS`: a listed name or one starting with it
+	 * and `-`, e.g. `tokio-util`; `"other"` when none fits
+	 */
+	public categoryOf(crate: string): keyof DetectedDependencies {
+		const name = normalizeCrateName(crate);
+		for (const [category, patterns] of Object.entries(DEPENDENCY_PATTERNS)) {
+			for (const pattern of patterns.map(normalizeCrateName)) {
+				if (name === pattern || name.startsWith(`${pattern}-`)) {
+					return category as keyof DetectedDependencies;
+				}
+			}
 		}
-		if (deps.async.length > 0 && deps.database.length > 0) {
-			return {
-				context: "web",
-				reason: `Using async runtime + database - likely a backend service`,
-			};
+		return "other";
+	}
+
+	/**
+	 * One-line description of a dependency: the crate's own when `cargo metadata` ran, else a
+	 * built-in one for well-known crates
+	 */
+	public describeDependency(dep: CargoDependency): string | undefined {
+		return dep.description ?? CRATE_DESCRIPTIONS[normalizeCrateName(dep.package)];
+	}
+
+	/**
+	 * The scanned dependency declared at a location in a Cargo.toml. Entries of
+	 * `[workspace.dependencies]` are looked up through the first member inheriting them.
+	 */
+	public async getDeclaredDependency(
+		manifest: vscode.Uri,
+		location: DependencyLocation,
+	): Promise<CargoDependency | undefined> {
+		const crates = await this.getCrates();
+		if (location.workspaceTable) {
+			return crates
+				.flatMap((crate) => crate.manifest.dependencies)
+				.find((dep) => dep.workspace && dep.name === location.name);
 		}
+		return this.findCrate(crates, manifest)?.manifest.dependencies.find(
+			(dep) =>
+				dep.name === location.name &&
+				dep.kind === location.kind &&
+				dep.target === location.target,
+		);
+	}
 
-		return null;
+	/**
+	 * Check if a specific category of dependencies is used
+	 */
+	public async hasCategory(
+		category: keyof DetectedDependencies,
+		uri?: vscode.Uri,
+	): Promise<boolean> {
+		const deps = uri ? await this.getDependenciesFor(uri) : await this.getDependencies();
+		return deps[category].length > 0;
 	}
 
 	/**
 	 * Get dependency-specific hints that should be surfaced
 	 */
-	public async getRelevantHintCategories(): Promise<string[]> {
-		const deps = await this.getDependencies();
-		const categories: string[] = ["general"]; // Always include general
+	public async getRelevantHintCategories(uri?: vscode.Uri): Promise<string[]> {
+		const deps = uri ? await this.getDependenciesFor(uri) : await this.getDependencies();
+		return this.hintCategories(deps);
+	}
 
-		if (deps.async.length > 0) {
-			categories.push("async");
-		}
-		if (deps.serialization.length > 0) {
-			categories.push("serialization");
+	/**
+	 * Crates, hint categories, edition and MSRV of the crate owning a file, for gating rules.
+	 * Read from the last scan so rule matching can look it up without waiting.
+	 */
+	public getProjectDependencies(uri: vscode.Uri): ProjectDependencies {
+		const crates = this.crates ?? [];
+		const owner = this.findCrate(crates, uri);
+		const deps = owner?.dependencies ?? this.combineDependencies(crates);
+
+		const versions = new Map<string, string>();
+		const features = new Map<string, string[]>();
+		const unresolved = new Set<string>();
+		for (const dep of (owner ? [owner] : crates).flatMap((c) => c.manifest.dependencies)) {
+			const version = dep.resolvedVersion ?? (dep.version && minimumVersion(dep.version));
+			const name = normalizeCrateName(dep.package);
+			if (version && !versions.has(name)) {
+				versions.set(name, version);
+			}
+			const enabled = [...(features.get(name) ?? []), ...enabledFeatures(dep)];
+			features.set(name, [...new Set(enabled)]);
+			if (!dep.enabledFeatures) {
+				unresolved.add(name);
+			}
 		}
-		if (deps.error.length > 0) {
-			categories.push("error-handling-advanced");
+
+		return {
+			crates: Object.values(deps).flat(),
+			versions,
+			features,
+			resolvedFeatures: new Set([...features.keys()].filter((name) => !unresolved.has(name))),
+			categories: this.hintCategories(deps),
+			edition: owner?.edition,
+			rustVersion: owner?.rustVersion,
+		};
+	}
+
+	/**
+	 * Point a docs.rs link for "latest" at the version the crate owning `uri` uses instead,
+	 * e.g. `https://docs.rs/clap/latest/clap/` → `https://docs.rs/clap/4.5.4/clap/`
+	 */
+	public async resolveDocUrl(url: string, uri?: vscode.Uri): Promise<string> {
+		const match = /^https:\/\/docs\.rs\/([\w-]+)\/latest\//.exec(url);
+		if (!match) {
+			return url;
 		}
-		if (deps.parsing.length > 0) {
-			categories.push("parser");
+		// Without a lockfile, docs.rs picks the newest release meeting the requirement itself
+		const dep = await this.getCargoDependency(match[1], uri);
+		const version = dep?.resolvedVersion ?? dep?.version;
+		if (!version) {
+			return url;
 		}
-
-		return categories;
+		const rest = url.slice(match[0].length);
+		return `https://docs.rs/${match[1]}/${encodeURIComponent(version)}/${rest}`;
 	}
 
 	/**
-	 * Get specific dependency info for display
+	 * Get specific dependency info for display, for the crate owning `uri` if given
 	 */
-	public async getDependencySummary(): Promise<string> {
-		const deps = await this.getDependencies();
+	public async getDependencySummary(uri?: vscode.Uri): Promise<string> {
+		const crates = await this.getCrates();
+		const owner = uri && this.findCrate(crates, uri);
+		const deps = owner ? owner.dependencies : this.combineDependencies(crates);
 		const parts: string[] = [];
 
+		if (owner && crates.length > 1) {
+			parts.push(`📁 Crate: ${owner.name}`);
+		}
+		if (owner) {
+			const msrv = owner.rustVersion ? `, Rust ${owner.rustVersion}+` : "";
+			parts.push(`🦀 Edition ${owner.edition}${msrv}`);
+		}
 		if (deps.async.length > 0) {
 			parts.push(`⚡ Async: ${deps.async.join(", ")}`);
 		}
@@ -177,79 +398,238 @@ export class CargoAnalyzerService {
 	 */
 	private async scanDependencies(): Promise<void> {
 		const cargoFiles = await vscode.workspace.findFiles("**/Cargo.toml", "**/target/**");
-
-		if (cargoFiles.length === 0) {
-			this.cachedDependencies = this.emptyDependencies();
-			return;
+		const manifests: { dir: string; manifest: CargoManifest }[] = [];
+		for (const file of cargoFiles) {
+			const manifest = await this.readManifest(file);
+			if (manifest) {
+				manifests.push({ dir: path.dirname(file.fsPath), manifest });
+			}
 		}
 
-		// Use the first Cargo.toml found (usually the root one)
-		// Sort to prioritize root Cargo.toml
-		const sortedFiles = cargoFiles.sort(
-			(a, b) => a.fsPath.split(path.sep).length - b.fsPath.split(path.sep).length,
+		const toolchainFiles = await vscode.workspace.findFiles(
+			"**/rust-toolchain*",
+			"**/target/**",
 		);
+		const toolchains: { dir: string; version?: string }[] = [];
+		for (const file of toolchainFiles) {
+			const name = path.basename(file.fsPath);
+			if (name === "rust-toolchain" || name === "rust-toolchain.toml") {
+				const version = await this.readToolchain(file);
+				toolchains.push({ dir: path.dirname(file.fsPath), version });
+			}
+		}
 
+		const crates: CargoCrate[] = [];
+		const lockfiles = new Map<string, Promise<LockedPackage[]>>();
+		const metadata = new Map<string, Promise<Map<string, ResolvedDependency[]> | null>>();
+		for (const { dir, manifest: declared } of manifests) {
+			if (declared.packageName === undefined) {
+				continue; // A virtual workspace manifest
+			}
+			const workspace = this.findWorkspaceRoot(dir, declared, manifests);
+			const manifest = resolveWorkspacePackage(declared, workspace?.manifest);
+			manifest.dependencies = resolveWorkspaceDependencies(
+				manifest.dependencies,
+				workspace?.manifest.workspaceDependencies ?? [],
+			);
+
+			// Cargo.lock sits next to the workspace root's manifest
+			const rootDir = workspace?.dir ?? dir;
+			let lock = lockfiles.get(rootDir);
+			if (!lock) {
+				lock = this.readLockfile(vscode.Uri.file(path.join(rootDir, "Cargo.lock")));
+				lockfiles.set(rootDir, lock);
+			}
+			manifest.dependencies = resolveLockedVersions(
+				declared.packageName,
+				manifest.dependencies,
+				await lock,
+			);
+
+			// Cargo's own resolution, when it can run, also knows which features are enabled
+			let resolved = metadata.get(rootDir);
+			if (!resolved) {
+				resolved = this.useCargoMetadata()
+					? readCargoMetadata(path.join(rootDir, "Cargo.toml"))
+					: Promise.resolve(null);
+				metadata.set(rootDir, resolved);
+			}
+			const members = await resolved;
+			const member = members && findMember(members, dir);
+			if (member) {
+				manifest.dependencies = applyResolvedDependencies(manifest.dependencies, member);
+			}
+
+			// rustup uses the closest rust-toolchain file above where cargo runs
+			const toolchain = toolchains
+				.filter((t) => isInside(t.dir, dir))
+				.sort((a, b) => b.dir.length - a.dir.length)[0];
+
+			crates.push({
+				name: declared.packageName,
+				root: vscode.Uri.file(dir),
+				manifest,
+				dependencies: this.categorizeDependencies(manifest.dependencies),
+				edition: manifest.edition ?? "2015",
+				rustVersion:
+					(manifest.rustVersion && minimumVersion(manifest.rustVersion)) ||
+					toolchain?.version,
+			});
+		}
+
+		this.crates = crates.sort((a, b) => a.root.fsPath.localeCompare(b.root.fsPath));
+		this._onDependenciesChanged.fire(this.combineDependencies(this.crates));
+	}
+
+	private async readManifest(uri: vscode.Uri): Promise<CargoManifest | null> {
 		try {
-			const content = await vscode.workspace.fs.readFile(sortedFiles[0]);
-			const tomlContent = Buffer.from(content).toString("utf-8");
-			this.cachedDependencies = this.parseDependencies(tomlContent);
-			this._onDependenciesChanged.fire(this.cachedDependencies);
+			const content = await vscode.workspace.fs.readFile(uri);
+			const manifest = parseManifest(Buffer.from(content).toString("utf-8"));
+			for (const error of manifest.errors) {
+				console.warn(`${uri.fsPath}:${error.line + 1}: ${error.message}`);
+			}
+			return manifest;
 		} catch (err) {
-			console.error("Failed to read Cargo.toml:", err);
-			this.cachedDependencies = this.emptyDependencies();
+			console.error(`Failed to read ${uri.fsPath}:`, err);
+			return null;
 		}
 	}
 
 	/**
-	 * Parse dependencies from TOML content
+	 * Packages locked in a Cargo.lock; none when there isn't one yet
 	 */
-	private parseDependencies(tomlContent: string): DetectedDependencies {
-		const deps = this.emptyDependencies();
+	private async readLockfile(uri: vscode.Uri): Promise<LockedPackage[]> {
+		try {
+			const content = await vscode.workspace.fs.readFile(uri);
+			return parseLockfile(Buffer.from(content).toString("utf-8"));
+		} catch {
+			return [];
+		}
+	}
 
-		// Simple regex-based parsing (works for most Cargo.toml files)
-		// Matches: dependency_name = "version" or dependency_name = { version = "x" }
-		const depSections = [
-			/\[dependencies\]([\s\S]*?)(?=\[|$)/,
-			/\[dev-dependencies\]([\s\S]*?)(?=\[|$)/,
-			/\[build-dependencies\]([\s\S]*?)(?=\[|$)/,
-		];
-
-		for (const sectionRegex of depSections) {
-			const match = tomlContent.match(sectionRegex);
-			if (match) {
-				const section = match[1];
-				// Match dependency names (handles various TOML formats)
-				const depMatches = section.matchAll(/^([a-zA-Z0-9_-]+)\s*=/gm);
-
-				for (const depMatch of depMatches) {
-					const depName = depMatch[1].toLowerCase().replace(/_/g, "-");
-					this.categorizeDependency(depName, deps);
-				}
+	/**
+	 * Rust version a rust-toolchain.toml or legacy rust-toolchain file pins, if any
+	 */
+	private async readToolchain(uri: vscode.Uri): Promise<string | undefined> {
+		try {
+			const content = await vscode.workspace.fs.readFile(uri);
+			return parseToolchainVersion(Buffer.from(content).toString("utf-8"));
+		} catch {
+			return undefined;
+		}
+	}
+
+	/**
+	 * Whether to ask cargo for resolved features; cargo reads config from the workspace, so only
+	 * in trusted workspaces
+	 */
+	private useCargoMetadata(): boolean {
+		const config = vscode.workspace.getConfiguration("rustCompass");
+		return vscode.workspace.isTrusted && config.get<boolean>("cargoMetadata", true);
+	}
+
+	/**
+	 * The workspace a package belongs to: the one `[package] workspace` points at, otherwise
+	 * the closest enclosing `[workspace]` whose `members` include it
+	 */
+	private findWorkspaceRoot(
+		dir: string,
+		manifest: CargoManifest,
+		manifests: { dir: string; manifest: CargoManifest }[],
+	): { dir: string; manifest: CargoManifest } | undefined {
+		if (manifest.workspacePath !== undefined) {
+			const root = path.resolve(dir, manifest.workspacePath);
+			return manifests.find((m) => m.dir === root);
+		}
+		const enclosing = manifests
+			.filter((m) => isInside(m.dir, dir))
+			.sort((a, b) => b.dir.length - a.dir.length);
+		for (const root of enclosing) {
+			const workspace = root.manifest.workspace;
+			if (workspace && isWorkspaceMember(workspace, relativePath(root.dir, dir))) {
+				return root;
 			}
 		}
+		return undefined;
+	}
 
-		return deps;
+	private findCrate(crates: CargoCrate[], uri: vscode.Uri): CargoCrate | undefined {
+		let owner: CargoCrate | undefined;
+		for (const crate of crates) {
+			const root = crate.root.fsPath;
+			if (isInside(root, uri.fsPath) && (!owner || root.length > owner.root.fsPath.length)) {
+				owner = crate;
+			}
+		}
+		return owner;
 	}
 
 	/**
-	 * Categorize a dependency into the appropriate category
+	 * Categories for a crate's dependencies, each named once under its real (`package`) name
 	 */
-	private categorizeDependency(depName: string, deps: DetectedDependencies): void {
-		for (const [category, patterns] of Object.entries(DEPENDENCY_PATTERNS)) {
-			if (category === "other") {
-				continue;
+	private categorizeDependencies(dependencies: CargoDependency[]): DetectedDependencies {
+		const deps = this.emptyDependencies();
+		const seen = new Set<string>();
+		for (const dep of dependencies) {
+			const name = normalizeCrateName(dep.package);
+			if (!seen.has(name)) {
+				seen.add(name);
+				this.categorizeDependency(name, deps);
 			}
+		}
+		return deps;
+	}
 
-			for (const pattern of patterns) {
-				// Check if dep name matches or starts with the pattern
-				if (depName === pattern || depName.startsWith(pattern + "-")) {
-					(deps[category as keyof DetectedDependencies] as string[]).push(depName);
-					return;
+	private combineDependencies(crates: CargoCrate[]): DetectedDependencies {
+		const combined = this.emptyDependencies();
+		for (const crate of crates) {
+			for (const key of Object.keys(combined) as (keyof DetectedDependencies)[]) {
+				for (const name of crate.dependencies[key]) {
+					if (!combined[key].includes(name)) {
+						combined[key].push(name);
+					}
 				}
 			}
 		}
-		// If no category matched, add to other
-		deps.other.push(depName);
+		return combined;
+	}
+
+	private hintCategories(deps: DetectedDependencies): string[] {
+		const categories: string[] = ["general"]; // Always include general
+
+		if (deps.async.length > 0) {
+			categories.push("async");
+		}
+		if (deps.web.length > 0) {
+			categories.push("web");
+		}
+		if (deps.serialization.length > 0) {
+			categories.push("serialization");
+		}
+		if (deps.error.length > 0) {
+			categories.push("error-handling-advanced");
+		}
+		if (deps.parsing.length > 0) {
+			categories.push("parser");
+		}
+		if (deps.cli.length > 0) {
+			categories.push("cli");
+		}
+		if (deps.database.length > 0) {
+			categories.push("database");
+		}
+		if (deps.testing.length > 0) {
+			categories.push("testing");
+		}
+
+		return categories;
+	}
+
+	/**
+	 * Categorize a dependency into the appropriate category
+	 */
+	private categorizeDependency(depName: string, deps: DetectedDependencies): void {
+		deps[this.categoryOf(depName)].push(depName);
 	}
 
 	/**
@@ -270,10 +650,9 @@ export class CargoAnalyzerService {
 	}
 
 	/**
-	 * Invalidate cache and re-scan
+	 * Re-scan, keeping the previous result in place until the new one is ready
 	 */
-	private async invalidateCache(): Promise<void> {
-		this.cachedDependencies = null;
+	public async invalidateCache(): Promise<void> {
 		await this.scanDependencies();
 	}
 
@@ -285,3 +664,26 @@ export class CargoAnalyzerService {
 		this._onDependenciesChanged.dispose();
 	}
 }
+
+/**
+ * Whether `child` is `parent` or a path inside it
+ */
+function isInside(parent: string, child: string): boolean {
+	const relative = path.relative(parent, child);
+	return relative !== ".." && !relative.startsWith(`..${path.sep}`) && !path.isAbsolute(relative);
+}
+
+function relativePath(from: string, to: string): string {
+	return path.relative(from, to).split(path.sep).join("/");
+}
+
+/**
+ * Resolved dependencies of the workspace member in `dir`; cargo may spell the path differently
+ */
+function findMember(
+	members: Map<string, ResolvedDependency[]>,
+	dir: string,
+): ResolvedDependency[] | undefined {
+	const member = [...members].find(([memberDir]) => path.relative(memberDir, dir) === "");
+	return members.get(dir) ?? member?.[1];
+}
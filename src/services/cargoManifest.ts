@@ -0,0 +1,512 @@
+import { maskTomlComments, satisfiesVersion } from "../rules";
+import type { ResolvedDependency } from "./cargoMetadata";
+import { isTomlTable, parseToml, type TomlError, type TomlTable, type TomlValue } from "./toml";
+
+export type DependencyKind = "normal" | "dev" | "build";
+
+/**
+ * One entry of a `[dependencies]`-style table
+ */
+export interface CargoDependency {
+	/** Key in the manifest, which is also the name code refers to the crate by */
+	name: string;
+	/** Crate being depended on; differs from `name` when renamed with `package = ".."` */
+	package: string;
+	kind: DependencyKind;
+	/** `cfg(..)` expression or target triple from `[target.<target>.dependencies]` */
+	target?: string;
+	/** Version requirement such as `"1.0"` or `">=4, <5"` */
+	version?: string;
+	/** Exact version Cargo.lock resolved the requirement to */
+	resolvedVersion?: string;
+	/** Features named in the manifest */
+	features: string[];
+	/**
+	 * Every feature Cargo enables on the crate, including defaults, implied features and ones
+	 * other crates turn on. Only known when `cargo metadata` could run.
+	 */
+	enabledFeatures?: string[];
+	/** False when declared with `default-features = false` */
+	defaultFeatures: boolean;
+	optional: boolean;
+	/** Declared with `workspace = true`, inheriting from `[workspace.dependencies]` */
+	workspace: boolean;
+	path?: string;
+	git?: string;
+	/** The crate's own `description`. Only known when `cargo metadata` could run. */
+	description?: string;
+}
+
+/**
+ * What Rust Compass reads from a `Cargo.toml`
+ */
+export interface CargoManifest {
+	/** `[package] name`; absent for a virtual workspace manifest */
+	packageName?: string;
+	/** `[package] workspace`, the path to the workspace root when it isn't a parent folder */
+	workspacePath?: string;
+	/** `[package] edition`; Cargo assumes 2015 when a package doesn't set one */
+	edition?: string;
+	/** `[package] rust-version`, the oldest Rust the package supports */
+	rustVersion?: string;
+	/** `[package]` keys taken from `[workspace.package]` with `key.workspace = true` */
+	inheritedFields: string[];
+	/** Targets the manifest declares; Cargo also finds them from files such as `src/lib.rs` */
+	targets: CargoTargets;
+	/** `[workspace]`, when this manifest is a workspace root */
+	workspace?: CargoWorkspace;
+	dependencies: CargoDependency[];
+	/** `[workspace.dependencies]`, which members inherit from with `workspace = true` */
+	workspaceDependencies: CargoDependency[];
+	/** `[workspace.package]` values members can inherit with `key.workspace = true` */
+	workspacePackage: { edition?: string; rustVersion?: string };
+	/** Lines the TOML reader had to skip */
+	errors: TomlError[];
+}
+
+/**
+ * Build targets declared in a `Cargo.toml`
+ */
+export interface CargoTargets {
+	/** Has a `[lib]` table */
+	lib: boolean;
+	/** `[lib] proc-macro = true` */
+	procMacro: boolean;
+	/** Names (or paths) of the `[[bin]]` entries */
+	bins: string[];
+	/** Names (or paths) of the `[[bench]]` entries */
+	benches: string[];
+	/** `[package] build`: a build script path, or false when build.rs is turned off */
+	build?: string | boolean;
+}
+
+/**
+ * The `[workspace]` table of a root manifest
+ */
+export interface CargoWorkspace {
+	/** Member folders relative to the root, possibly with globs such as `crates/*` */
+	members: string[];
+	exclude: string[];
+}
+
+/**
+ * One `[[package]]` of a Cargo.lock
+ */
+export interface LockedPackage {
+	name: string;
+	version: string;
+	/** Registry or git source; absent for workspace members and path dependencies */
+	source?: string;
+	/** `"name"`, or `"name version"` when several versions of the crate are locked */
+	dependencies: string[];
+}
+
+/**
+ * Where a dependency is declared in a Cargo.toml
+ */
+export interface DependencyLocation {
+	/** Key naming the dependency */
+	name: string;
+	kind: DependencyKind;
+	target?: string;
+	/** Declared in `[workspace.dependencies]` */
+	workspaceTable: boolean;
+	/** Zero-based line of the key, or of the header for a `[dependencies.<name>]` table */
+	startLine: number;
+	/** Last line of the entry, e.g. of a `[dependencies.<name>]` table */
+	endLine: number;
+}
+
+/**
+ * Dependency table names per kind, including the underscore spellings Cargo still accepts
+ */
+const DEPENDENCY_TABLES: [string, DependencyKind][] = [
+	["dependencies", "normal"],
+	["dev-dependencies", "dev"],
+	["dev_dependencies", "dev"],
+	["build-dependencies", "build"],
+	["build_dependencies", "build"],
+];
+
+/**
+ * Read the parts of a `Cargo.toml` Rust Compass uses
+ */
+export function parseManifest(source: string): CargoManifest {
+	const { root, errors } = parseToml(source);
+	const pkg = tableAt(root, "package");
+	const workspace = tableAt(root, "workspace");
+
+	const dependencies = readDependencyTables(root);
+	const targets = tableAt(root, "target");
+	for (const [target, table] of Object.entries(targets)) {
+		if (isTomlTable(table)) {
+			dependencies.push(...readDependencyTables(table, target));
+		}
+	}
+
+	return {
+		packageName: stringAt(pkg, "name"),
+		workspacePath: stringAt(pkg, "workspace"),
+		edition: stringAt(pkg, "edition"),
+		rustVersion: stringAt(pkg, "rust-version"),
+		inheritedFields: Object.keys(pkg).filter((key) => {
+			const value = pkg[key];
+			return isTomlTable(value) && value.workspace === true;
+		}),
+		targets: readTargets(root, pkg),
+		workspace: isTomlTable(root.workspace)
+			? { members: stringsAt(workspace, "members"), exclude: stringsAt(workspace, "exclude") }
+			: undefined,
+		dependencies,
+		workspaceDependencies: readDependencies(tableAt(workspace, "dependencies"), "normal"),
+		workspacePackage: {
+			edition: stringAt(tableAt(workspace, "package"), "edition"),
+			rustVersion: stringAt(tableAt(workspace, "package"), "rust-version"),
+		},
+		errors,
+	};
+}
+
+/**
+ * Find the dependency entries in a Cargo.toml's text, to point at them in the editor
+ */
+export function locateDependencies(source: string): DependencyLocation[] {
+	const lines = maskTomlComments(source).split("\n");
+	const locations: DependencyLocation[] = [];
+	let table: ReturnType<typeof dependencyTable>;
+	let current: DependencyLocation | undefined;
+
+	// An entry ends at the next key or table, not counting blank lines in between
+	const finish = (next: number) => {
+		if (current) {
+			let end = next - 1;
+			while (end > current.startLine && lines[end].trim() === "") {
+				end--;
+			}
+			locations.push({ ...current, endLine: end });
+			current = undefined;
+		}
+	};
+
+	lines.forEach((text, line) => {
+		if (/^\s*\[/.test(text)) {
+			finish(line);
+			// `[[bin]]` and the like aren't dependency tables
+			const header = /^\s*\[([^[\]]+)\]/.exec(text);
+			table = header ? dependencyTable(splitTableKeys(header[1])) : undefined;
+			if (table?.name !== undefined) {
+				current = { ...table, name: table.name, startLine: line, endLine: line };
+			}
+			return;
+		}
+		if (table && table.name === undefined) {
+			const key = /^\s*("[^"]*"|'[^']*'|[\w-]+)\s*[.=]/.exec(text);
+			if (key) {
+				finish(line);
+				const name = key[1].replace(/^["']|["']$/g, "");
+				current = { ...table, name, startLine: line, endLine: line };
+			}
+		}
+	});
+	finish(lines.length);
+
+	return locations;
+}
+
+/**
+ * Whether a folder belongs to a workspace, given its `/`-separated path relative to the root.
+ * The root itself always does; `exclude` wins over `members`, as in Cargo.
+ */
+export function isWorkspaceMember(workspace: CargoWorkspace, relativePath: string): boolean {
+	if (relativePath === "") {
+		return true;
+	}
+	const excluded = workspace.exclude.some((entry) => {
+		const prefix = normalizeMemberPath(entry);
+		return relativePath === prefix || relativePath.startsWith(`${prefix}/`);
+	});
+	return !excluded && workspace.members.some((member) => globToRegExp(member).test(relativePath));
+}
+
+/**
+ * Fill in `workspace = true` dependencies from the workspace's `[workspace.dependencies]`.
+ * Features add up, as they do in Cargo; `optional` always comes from the member.
+ */
+export function resolveWorkspaceDependencies(
+	dependencies: CargoDependency[],
+	workspaceDependencies: CargoDependency[],
+): CargoDependency[] {
+	return dependencies.map((dep) => {
+		const inherited = dep.workspace
+			? workspaceDependencies.find((w) => w.name === dep.name)
+			: undefined;
+		if (!inherited) {
+			return dep;
+		}
+		return {
+			...dep,
+			package: inherited.package,
+			version: inherited.version,
+			features: [...new Set([...inherited.features, ...dep.features])],
+			defaultFeatures: inherited.defaultFeatures,
+			path: inherited.path,
+			git: inherited.git,
+		};
+	});
+}
+
+/**
+ * Fill in `edition` and `rust-version` a package inherits with `key.workspace = true` from
+ * the `[workspace.package]` of its workspace root
+ */
+export function resolveWorkspacePackage(
+	manifest: CargoManifest,
+	root: CargoManifest | undefined,
+): CargoManifest {
+	const inherits = (key: string) => manifest.inheritedFields.includes(key);
+	return {
+		...manifest,
+		edition: inherits("edition") ? root?.workspacePackage.edition : manifest.edition,
+		rustVersion: inherits("rust-version")
+			? root?.workspacePackage.rustVersion
+			: manifest.rustVersion,
+	};
+}
+
+/**
+ * The Rust version a `rust-toolchain.toml` (or legacy `rust-toolchain`) file pins, e.g.
+ * `1.70.0` for `channel = "1.70"`; undefined for `stable`, `nightly` and the like
+ */
+export function parseToolchainVersion(source: string): string | undefined {
+	const { root } = parseToml(source);
+	// The legacy file is just the channel name
+	const channel = isTomlTable(root.toolchain)
+		? stringAt(root.toolchain, "channel")
+		: source.trim().split(/\s/)[0];
+	const match = channel && /^(\d+)\.(\d+)(?:\.(\d+))?/.exec(channel);
+	return match ? `${match[1]}.${match[2]}.${match[3] ?? 0}` : undefined;
+}
+
+/**
+ * Read the packages of a Cargo.lock
+ */
+export function parseLockfile(source: string): LockedPackage[] {
+	const packages = parseToml(source).root.package;
+	if (!Array.isArray(packages)) {
+		return [];
+	}
+	return packages.filter(isTomlTable).flatMap((pkg) => {
+		const name = stringAt(pkg, "name");
+		const version = stringAt(pkg, "version");
+		if (name === undefined || version === undefined) {
+			return [];
+		}
+		const source = stringAt(pkg, "source");
+		return [{ name, version, source, dependencies: stringsAt(pkg, "dependencies") }];
+	});
+}
+
+/**
+ * Fill in `resolvedVersion` for the dependencies of the package `packageName` from its lockfile
+ */
+export function resolveLockedVersions(
+	packageName: string,
+	dependencies: CargoDependency[],
+	lock: LockedPackage[],
+): CargoDependency[] {
+	const owner = lock.find((p) => p.name === packageName && p.source === undefined);
+	return dependencies.map((dep) => {
+		const candidates = lock.filter((p) => p.name === dep.package);
+		// The lockfile names the version only when it has to pick between several
+		const entry = owner?.dependencies.find((d) => d.split(" ")[0] === dep.package);
+		let resolved = entry?.split(" ")[1];
+		if (resolved === undefined && candidates.length === 1) {
+			resolved = candidates[0].version;
+		}
+		if (resolved === undefined && dep.version !== undefined) {
+			const requirement = dep.version;
+			const matching = candidates.filter((p) => satisfiesVersion(p.version, requirement));
+			resolved = matching[matching.length - 1]?.version;
+		}
+		return resolved === undefined ? dep : { ...dep, resolvedVersion: resolved };
+	});
+}
+
+/**
+ * Fill in `resolvedVersion`, `enabledFeatures` and `description` from what `cargo metadata`
+ * resolved for the package. With several versions of a crate, the one meeting the requirement
+ * is used.
+ */
+export function applyResolvedDependencies(
+	dependencies: CargoDependency[],
+	resolved: ResolvedDependency[],
+): CargoDependency[] {
+	return dependencies.map((dep) => {
+		const candidates = resolved.filter((r) => r.name === dep.package);
+		const requirement = dep.version;
+		const match =
+			candidates.length === 1 || requirement === undefined
+				? candidates[0]
+				: candidates.find((r) => satisfiesVersion(r.version, requirement));
+		if (!match) {
+			return dep;
+		}
+		return {
+			...dep,
+			resolvedVersion: match.version,
+			enabledFeatures: match.features,
+			description: match.description,
+		};
+	});
+}
+
+/**
+ * Features enabled on a dependency: as Cargo resolved them when known, otherwise the ones the
+ * manifest names plus `default` unless turned off
+ */
+export function enabledFeatures(dep: CargoDependency): string[] {
+	if (dep.enabledFeatures) {
+		return dep.enabledFeatures;
+	}
+	return dep.defaultFeatures ? ["default", ...dep.features] : dep.features;
+}
+
+/**
+ * What a table header such as `target.'cfg(unix)'.dependencies` or `dependencies.clap` holds,
+ * if it's a dependency table; `name` is set for a table declaring one dependency
+ */
+function dependencyTable(
+	keys: string[],
+): { kind: DependencyKind; target?: string; workspaceTable: boolean; name?: string } | undefined {
+	let rest = keys;
+	let target: string | undefined;
+	const workspaceTable = rest[0] === "workspace";
+	if (workspaceTable) {
+		rest = rest.slice(1);
+	} else if (rest[0] === "target" && rest.length > 2) {
+		target = rest[1];
+		rest = rest.slice(2);
+	}
+	const kind = DEPENDENCY_TABLES.find(([key]) => key === rest[0])?.[1];
+	if (!kind || rest.length > 2 || (workspaceTable && rest[0] !== "dependencies")) {
+		return undefined;
+	}
+	return { kind, target, workspaceTable, name: rest[1] };
+}
+
+/**
+ * The keys of a table header, unquoted: `target.'cfg(unix)'.dependencies` has three
+ */
+function splitTableKeys(header: string): string[] {
+	return [...header.matchAll(/"([^"]*)"|'([^']*)'|([^."'\s]+)/g)].map(
+		(m) => m[1] ?? m[2] ?? m[3],
+	);
+}
+
+function readDependencyTables(table: TomlTable, target?: string): CargoDependency[] {
+	return DEPENDENCY_TABLES.flatMap(([key, kind]) =>
+		readDependencies(tableAt(table, key), kind, target),
+	);
+}
+
+function readDependencies(
+	table: TomlTable,
+	kind: DependencyKind,
+	target?: string,
+): CargoDependency[] {
+	const dependencies: CargoDependency[] = [];
+	for (const [name, value] of Object.entries(table)) {
+		const dep: CargoDependency = {
+			name,
+			package: name,
+			kind,
+			features: [],
+			defaultFeatures: true,
+			optional: false,
+			workspace: false,
+		};
+		if (target !== undefined) {
+			dep.target = target;
+		}
+
+		if (typeof value === "string") {
+			dep.version = value;
+		} else if (isTomlTable(value)) {
+			dep.package = stringAt(value, "package") ?? name;
+			dep.version = stringAt(value, "version");
+			dep.features = stringsAt(value, "features");
+			dep.defaultFeatures = (value["default-features"] ?? value.default_features) !== false;
+			dep.optional = value.optional === true;
+			dep.workspace = value.workspace === true;
+			dep.path = stringAt(value, "path");
+			dep.git = stringAt(value, "git");
+		} else {
+			continue;
+		}
+		dependencies.push(dep);
+	}
+	return dependencies;
+}
+
+function readTargets(root: TomlTable, pkg: TomlTable): CargoTargets {
+	const lib = tableAt(root, "lib");
+	// `[[bin]]` and `[[bench]]` are arrays of tables
+	const names = (key: string) => {
+		const entries = root[key];
+		return (Array.isArray(entries) ? entries : [])
+			.filter(isTomlTable)
+			.map((entry) => stringAt(entry, "name") ?? stringAt(entry, "path") ?? "");
+	};
+	const build = pkg.build;
+	return {
+		lib: isTomlTable(root.lib),
+		procMacro: lib["proc-macro"] === true || lib.proc_macro === true,
+		bins: names("bin"),
+		benches: names("bench"),
+		build: typeof build === "string" || typeof build === "boolean" ? build : undefined,
+	};
+}
+
+function tableAt(table: TomlTable, key: string): TomlTable {
+	const value = table[key];
+	return isTomlTable(value) ? value : {};
+}
+
+function stringAt(table: TomlTable, key: string): string | undefined {
+	const value = table[key];
+	return typeof value === "string" ? value : undefined;
+}
+
+function stringsAt(table: TomlTable, key: string): string[] {
+	const value: TomlValue | undefined = table[key];
+	return Array.isArray(value) ? value.filter((v): v is string => typeof v === "string") : [];
+}
+
+function normalizeMemberPath(entry: string): string {
+	return entry.replace(/\\/g, "/").replace(/^\.\//, "").replace(/\/+$/, "");
+}
+
+/**
+ * A `members` glob as a regex: `*` and `?` stay within one folder, `**` spans any number
+ */
+function globToRegExp(glob: string): RegExp {
+	const source = normalizeMemberPath(glob)
+		.split(/(\*\*\/?|\*|\?)/)
+		.map((part) => {
+			if (part === "**/") {
+				return "(?:.*/)?";
+			}
+			if (part === "**") {
+				return ".*";
+			}
+			if (part === "*") {
+				return "[^/]*";
+			}
+			if (part === "?") {
+				return "[^/]";
+			}
+			return part.replace(/[.+^${}()|[\]\\]/g, "\\$&");
+		})
+		.join("");
+	return new RegExp(`^${source}$`);
+}
@@ -0,0 +1,98 @@
+import { execFile } from "node:child_process";
+import * as path from "node:path";
+
+/**
+ * A dependency as Cargo resolved it for one workspace member
+ */
+export interface ResolvedDependency {
+	/** Crate name (the `package`, not a rename) */
+	name: string;
+	version: string;
+	/** Every feature enabled on the crate, including defaults and ones other crates turn on */
+	features: string[];
+	/** `description` from the crate's Cargo.toml */
+	description?: string;
+}
+
+/**
+ * The parts of `cargo metadata --format-version 1` output Rust Compass reads
+ */
+interface MetadataOutput {
+	packages: {
+		id: string;
+		name: string;
+		version: string;
+		manifest_path: string;
+		description: string | null;
+	}[];
+	workspace_members: string[];
+	resolve: {
+		nodes: { id: string; features: string[]; deps: { pkg: string }[] }[];
+	} | null;
+}
+
+const TIMEOUT_MS = 30_000;
+const MAX_OUTPUT_BYTES = 256 * 1024 * 1024;
+
+/**
+ * Run `cargo metadata --offline --locked` for a workspace and return the resolved dependencies of
+ * each member, keyed by the folder holding the member's Cargo.toml. `--locked` keeps cargo from
+ * rewriting Cargo.lock. Resolves to `null` when cargo isn't on PATH or can't resolve the workspace
+ * offline from the existing lockfile.
+ */
+export function readCargoMetadata(
+	manifestPath: string,
+): Promise<Map<string, ResolvedDependency[]> | null> {
+	const args = ["metadata", "--offline", "--locked", "--format-version", "1"];
+	return new Promise((resolve) => {
+		execFile(
+			"cargo",
+			[...args, "--manifest-path", manifestPath],
+			{ cwd: path.dirname(manifestPath), timeout: TIMEOUT_MS, maxBuffer: MAX_OUTPUT_BYTES },
+			(error, stdout) => {
+				if (error) {
+					console.log(`cargo metadata unavailable for ${manifestPath}: ${error.message}`);
+					resolve(null);
+					return;
+				}
+				try {
+					resolve(resolveMembers(JSON.parse(stdout) as MetadataOutput));
+				} catch (err) {
+					console.error("Failed to read cargo metadata output:", err);
+					resolve(null);
+				}
+			},
+		);
+	});
+}
+
+function resolveMembers(metadata: MetadataOutput): Map<string, ResolvedDependency[]> | null {
+	if (!metadata.resolve) {
+		return null;
+	}
+	const packages = new Map(metadata.packages.map((p) => [p.id, p]));
+	const nodes = new Map(metadata.resolve.nodes.map((n) => [n.id, n]));
+
+	const members = new Map<string, ResolvedDependency[]>();
+	for (const id of metadata.workspace_members) {
+		const member = packages.get(id);
+		const node = nodes.get(id);
+		if (!member || !node) {
+			continue;
+		}
+		const dependencies: ResolvedDependency[] = [];
+		for (const dep of node.deps) {
+			const pkg = packages.get(dep.pkg);
+			if (pkg) {
+				dependencies.push({
+					name: pkg.name,
+					version: pkg.version,
+					features: nodes.get(dep.pkg)?.features ?? [],
+					description: pkg.description ?? undefined,
+				});
+			}
+		}
+		members.set(path.dirname(member.manifest_path), dependencies);
+	}
+	return members;
+}
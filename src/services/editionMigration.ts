@@ -0,0 +1,147 @@
+import * as vscode from "vscode";
+import {
+	mergeFixes,
+	type ResolvedFix,
+	type RuleEngine,
+	type RuleMatch,
+	withoutPlaceholders,
+} from "../rules";
+import { CargoAnalyzerService, type CargoCrate } from "./cargoAnalyzer";
+
+/** Edition the migration report checks crates against */
+export const MIGRATION_EDITION = "2024";
+
+/**
+ * Code whose meaning or legality changes in the new edition
+ */
+export interface EditionFinding {
+	match: RuleMatch;
+	uri: vscode.Uri;
+	/** Document version the match and fix were worked out against */
+	version: number;
+	/** The matched line, trimmed */
+	lineText: string;
+	/** Plain-text fix, when the change can be made mechanically */
+	fix?: ResolvedFix;
+}
+
+export interface CrateMigration {
+	crate: CargoCrate;
+	findings: EditionFinding[];
+}
+
+export interface EditionMigrationReport {
+	edition: string;
+	/** Crates on an older edition, with what changes for each */
+	crates: CrateMigration[];
+	/** Names of crates already on the edition */
+	upToDate: string[];
+}
+
+/**
+ * Scans the workspace for code an edition changes, using the `editionChange` rules and the
+ * `edition` each crate's Cargo.toml declares
+ */
+export class EditionMigration {
+	private readonly cargoAnalyzer = CargoAnalyzerService.getInstance();
+
+	constructor(private ruleEngine: RuleEngine) {}
+
+	/**
+	 * Check every .rs file of the crates still on an older edition
+	 */
+	public async scan(
+		edition: string,
+		progress?: vscode.Progress<{ message?: string; increment?: number }>,
+		token?: vscode.CancellationToken,
+	): Promise<EditionMigrationReport> {
+		const crates = await this.cargoAnalyzer.getCrates();
+		const migrations = new Map<CargoCrate, EditionFinding[]>();
+		for (const crate of crates) {
+			if (crate.edition < edition) {
+				migrations.set(crate, []);
+			}
+		}
+
+		const uris =
+			migrations.size > 0 ? await vscode.workspace.findFiles("**/*.rs", "**/target/**") : [];
+		for (const uri of uris) {
+			if (token?.isCancellationRequested) {
+				break;
+			}
+			progress?.report({
+				message: vscode.workspace.asRelativePath(uri),
+				increment: 100 / uris.length,
+			});
+
+			const crate = await this.cargoAnalyzer.getCrateFor(uri);
+			const findings = crate && migrations.get(crate);
+			if (!findings) {
+				continue;
+			}
+			const document = await vscode.workspace.openTextDocument(uri);
+			for (const match of this.ruleEngine.findEditionChanges(document, edition)) {
+				const fix = this.ruleEngine.resolveFix(document, match);
+				findings.push({
+					match,
+					uri,
+					version: document.version,
+					lineText: document.lineAt(match.range.line).text.trim(),
+					fix: fix && withoutPlaceholders(fix),
+				});
+			}
+		}
+
+		return {
+			edition,
+			crates: [...migrations].map(([crate, findings]) => ({ crate, findings })),
+			upToDate: crates.filter((c) => c.edition >= edition).map((c) => c.name),
+		};
+	}
+
+	/**
+	 * One edit making the fixes of these findings. Fixes that would clash are left out, as are
+	 * files edited since the scan (`stale`). With `preview`, each change needs confirming.
+	 */
+	public async createEdit(
+		findings: readonly EditionFinding[],
+		preview = false,
+	): Promise<{ edit: vscode.WorkspaceEdit; fixes: number; stale: number }> {
+		const edit = new vscode.WorkspaceEdit();
+		let fixes = 0;
+		let stale = 0;
+
+		const byFile = new Map<string, EditionFinding[]>();
+		for (const finding of findings) {
+			if (finding.fix) {
+				const key = finding.uri.toString();
+				byFile.set(key, [...(byFile.get(key) ?? []), finding]);
+			}
+		}
+
+		for (const fileFindings of byFile.values()) {
+			const { uri, version } = fileFindings[0];
+			const document = await vscode.workspace.openTextDocument(uri);
+			if (document.version !== version) {
+				stale += fileFindings.length;
+				continue;
+			}
+			const resolved = fileFindings.flatMap((f) => (f.fix ? [f.fix] : []));
+			for (const fix of mergeFixes(resolved)) {
+				const metadata = preview
+					? { label: fix.description, needsConfirmation: true }
+					: undefined;
+				for (const e of fix.edits) {
+					const range = new vscode.Range(
+						document.positionAt(e.start),
+						document.positionAt(e.end),
+					);
+					edit.replace(uri, range, e.text, metadata);
+				}
+				fixes++;
+			}
+		}
+
+		return { edit, fixes, stale };
+	}
+}
@@ -1,5 +1,27 @@
-export { CargoAnalyzerService, DetectedDependencies } from "./cargoAnalyzer";
+export { CargoAnalyzerService, CargoCrate, DetectedDependencies } from "./cargoAnalyzer";
+export {
+	CargoDependency,
+	CargoManifest,
+	CargoWorkspace,
+	DependencyKind,
+	DependencyLocation,
+	enabledFeatures,
+	isWorkspaceMember,
+	locateDependencies,
+	parseManifest,
+	parseToolchainVersion,
+	resolveWorkspaceDependencies,
+	resolveWorkspacePackage,
+} from "./cargoManifest";
+export { readCargoMetadata, ResolvedDependency } from "./cargoMetadata";
 export { CompilerErrorLinker } from "./compilerErrorLinker";
+export {
+	CrateMigration,
+	EditionFinding,
+	EditionMigration,
+	EditionMigrationReport,
+	MIGRATION_EDITION,
+} from "./editionMigration";
 export {
 	ExplanationSimplifier,
 	explanationSimplifier,
@@ -15,6 +37,23 @@ export {
 	TeachableMoment,
 } from "./intentAnalyzer";
 export { PatternStats, PatternTracker, SessionSummary } from "./patternTracker";
+export {
+	buildProjectProfile,
+	describeProfile,
+	DetectedTrait,
+	DomainTrait,
+	findTrait,
+	LIKELY_CONFIDENCE,
+	mergeProfiles,
+	primaryDomain,
+	ProfileInput,
+	ProjectProfile,
+	ProjectProfileService,
+	ProjectTrait,
+	suggestProjectContext,
+} from "./projectProfile";
+export { RuleLoadReporter } from "./ruleLoadReporter";
+export { RulePackWatcher } from "./rulePackWatcher";
 export { RustAnalyzerService, RustAnalyzerTypeInfo } from "./rustAnalyzer";
 export {
 	FetchedDocContent,
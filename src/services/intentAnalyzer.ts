@@ -1,4 +1,12 @@
 import * as vscode from "vscode";
+import type { ScopeInfo } from "../rules";
+import {
+	type DomainTrait,
+	findTrait,
+	type ProjectProfile,
+	ProjectProfileService,
+	primaryDomain,
+} from "./projectProfile";
 
 /**
  * Detected project/code intent
@@ -14,15 +22,8 @@ export interface CodeIntent {
 	confidence: number;
 }
 
-export type ProjectType =
-	| "parser"
-	| "lexer"
-	| "compiler"
-	| "cli"
-	| "web-server"
-	| "game"
-	| "library"
-	| "unknown";
+/** What the project is built for, from its `ProjectProfile` */
+export type ProjectType = DomainTrait | "library" | "unknown";
 
 export type FileIntent =
 	| "lexer"
@@ -57,122 +58,24 @@ export interface TeachableMoment {
 	severity: "hint" | "info";
 	// Why we're showing this, based on what user is doing
 	contextReason: string;
+	// Concrete change that acts on the suggestion, when there is an obvious one
+	fix?: TeachableFix;
+}
+
+/**
+ * Edits offered as a quick fix on a teachable moment's diagnostic
+ */
+export interface TeachableFix {
+	title: string;
+	edits: vscode.TextEdit[];
 }
 
 /**
  * Analyzes code to understand what the user is trying to do
  */
 export class IntentAnalyzer {
-	private projectTypeCache: ProjectType | null = null;
 	private lastAnalysis: Map<string, CodeIntent> = new Map();
 
-	/**
-	 * Analyze the entire workspace to understand project type
-	 */
-	async analyzeProject(workspaceFolder: vscode.Uri): Promise<ProjectType> {
-		if (this.projectTypeCache) {
-			return this.projectTypeCache;
-		}
-
-		// Check Cargo.toml for project name hints
-		const cargoPath = vscode.Uri.joinPath(workspaceFolder, "Cargo.toml");
-		try {
-			const cargoContent = await vscode.workspace.fs.readFile(cargoPath);
-			const cargoText = Buffer.from(cargoContent).toString("utf8");
-
-			const projectType = this.inferProjectTypeFromCargo(cargoText);
-			if (projectType !== "unknown") {
-				this.projectTypeCache = projectType;
-				return projectType;
-			}
-		} catch {
-			// No Cargo.toml
-		}
-
-		// Check file names in src/
-		const srcFiles = await vscode.workspace.findFiles("src/**/*.rs", null, 50);
-		const fileNames = srcFiles.map((f) => f.path.split("/").pop()?.toLowerCase() || "");
-
-		this.projectTypeCache = this.inferProjectTypeFromFiles(fileNames);
-		return this.projectTypeCache;
-	}
-
-	private inferProjectTypeFromCargo(content: string): ProjectType {
-		const nameLower = content.toLowerCase();
-
-		// Check package name
-		const nameMatch = nameLower.match(/name\s*=\s*"([^"]+)"/);
-		if (nameMatch) {
-			const name = nameMatch[1];
-			if (name.includes("parser") || name.includes("parse")) {
-				return "parser";
-			}
-			if (name.includes("lexer") || name.includes("lex")) {
-				return "lexer";
-			}
-			if (name.includes("compiler") || name.includes("lang")) {
-				return "compiler";
-			}
-			if (name.includes("cli") || name.includes("cmd")) {
-				return "cli";
-			}
-			if (name.includes("server") || name.includes("api")) {
-				return "web-server";
-			}
-		}
-
-		// Check dependencies
-		if (
-			nameLower.includes("nom") ||
-			nameLower.includes("pest") ||
-			nameLower.includes("lalrpop")
-		) {
-			return "parser";
-		}
-		if (nameLower.includes("clap") || nameLower.includes("structopt")) {
-			return "cli";
-		}
-		if (
-			nameLower.includes("actix") ||
-			nameLower.includes("axum") ||
-			nameLower.includes("rocket") ||
-			nameLower.includes("warp")
-		) {
-			return "web-server";
-		}
-		if (
-			nameLower.includes("bevy") ||
-			nameLower.includes("ggez") ||
-			nameLower.includes("macroquad")
-		) {
-			return "game";
-		}
-
-		return "unknown";
-	}
-
-	private inferProjectTypeFromFiles(fileNames: string[]): ProjectType {
-		const hasLexer = fileNames.some(
-			(f) =>
-				f.includes("lexer") ||
-				f.includes("lex") ||
-				f.includes("tokenizer") ||
-				f.includes("scanner"),
-		);
-		const hasParser = fileNames.some((f) => f.includes("parser") || f.includes("parse"));
-		if (hasLexer && hasParser) {
-			return "compiler";
-		}
-		if (hasLexer) {
-			return "lexer";
-		}
-		if (hasParser) {
-			return "parser";
-		}
-
-		return "unknown";
-	}
-
 	/**
 	 * Analyze a single file to understand its purpose
 	 */
@@ -315,15 +218,28 @@ export class IntentAnalyzer {
 	}
 
 	/**
-	 * Find teachable moments in a document based on intent
+	 * Find teachable moments in a document based on intent.
+	 * `getScopeAt` tells fixes what encloses a position (e.g. whether the function returns Result).
+	 * `profile` describes the crate the document belongs to.
 	 */
-	findTeachableMoments(document: vscode.TextDocument): TeachableMoment[] {
+	findTeachableMoments(
+		document: vscode.TextDocument,
+		getScopeAt?: (position: vscode.Position) => ScopeInfo,
+		profile?: ProjectProfile,
+	): TeachableMoment[] {
 		const moments: TeachableMoment[] = [];
 		const text = document.getText();
 		const fileIntent = this.analyzeFile(document);
+		// In a parser or compiler project, lexing advice applies beyond files named for it
+		const languageProject =
+			profile &&
+			(["lexer", "parser", "compiler"] as const)
+				.map((trait) => findTrait(profile, trait))
+				.find((detected) => detected !== undefined);
+		const lexingFile = fileIntent === "lexer" || fileIntent === "parser";
 
 		// Pattern: Using .next() in a lexer/parser without peekable
-		if (fileIntent === "lexer" || fileIntent === "parser") {
+		if (lexingFile || (languageProject && fileIntent !== "tests")) {
 			// Find .chars().next() patterns that could use peekable
 			const charsNextPattern = /\.chars\(\)(?!\.peekable)/g;
 			let match;
@@ -332,14 +248,22 @@ export class IntentAnalyzer {
 				const after = text.substring(match.index, Math.min(text.length, match.index + 200));
 				if (after.includes(".next()") && !after.includes(".peekable()")) {
 					const pos = document.positionAt(match.index);
+					const end = pos.translate(0, match[0].length);
 					moments.push({
-						range: new vscode.Range(pos, pos.translate(0, match[0].length)),
+						range: new vscode.Range(pos, end),
 						intent: "iterating-chars",
 						suggestion:
 							"Building a lexer? Consider .peekable() to look ahead without consuming",
 						ruleId: "iterator-next-without-peekable",
 						severity: "hint",
-						contextReason: `Detected lexer pattern: iterating chars with .next()`,
+						contextReason:
+							lexingFile || !languageProject
+								? "Detected lexer pattern: iterating chars with .next()"
+								: `${languageProject.reason}, and this iterates chars with .next()`,
+						fix: {
+							title: "Make it peekable: .chars().peekable()",
+							edits: [vscode.TextEdit.insert(end, ".peekable()")],
+						},
 					});
 				}
 			}
@@ -386,22 +310,38 @@ export class IntentAnalyzer {
 			});
 		}
 
-		// Pattern: .unwrap() in non-test files
-		if (!document.fileName.includes("test")) {
+		// Pattern: .unwrap() in non-test files. Build scripts report errors by panicking.
+		const buildScript =
+			profile &&
+			findTrait(profile, "build-script") &&
+			/(?:^|[\\/])build\.rs$/.test(document.fileName);
+		const library = profile && findTrait(profile, "library") && fileIntent !== "main-entry";
+		if (!document.fileName.includes("test") && !buildScript) {
 			const unwrapPattern = /\.unwrap\(\)/g;
 			let unwrapCount = 0;
 			while ((match = unwrapPattern.exec(text)) !== null) {
 				unwrapCount++;
 				if (unwrapCount >= 2) {
 					const pos = document.positionAt(match.index);
+					const range = new vscode.Range(pos, pos.translate(0, 9));
+					// `?` only works where the function itself returns a Result
+					const returnsResult = getScopeAt?.(pos).returnType === "Result";
 					moments.push({
-						range: new vscode.Range(pos, pos.translate(0, 9)),
+						range,
 						intent: "error-handling",
 						suggestion:
 							"Multiple .unwrap() calls - consider proper error handling with ? or match",
 						ruleId: "unwrap-usage",
 						severity: "hint",
-						contextReason: "Production code with unwrap() calls",
+						contextReason: library
+							? "Library code with unwrap() calls: a panic takes the caller down too"
+							: "Production code with unwrap() calls",
+						fix: returnsResult
+							? {
+									title: "Propagate the error with ?",
+									edits: [vscode.TextEdit.replace(range, "?")],
+								}
+							: undefined,
 					});
 					break; // Only one hint for this
 				}
@@ -409,16 +349,30 @@ export class IntentAnalyzer {
 		}
 
 		// Pattern: String vs &str confusion - functions taking String instead of &str
-		const fnStringParam = /fn\s+\w+\s*\([^)]*:\s*String(?!\s*,|\s*\))/g;
+		const fnStringParam = /fn\s+\w+\s*\([^)]*?(\bmut\s+)?\b\w+\s*:\s*String(?=\s*[,)])/g;
 		while ((match = fnStringParam.exec(text)) !== null) {
 			const pos = document.positionAt(match.index);
+			// Parameter lists can span lines
+			const end = document.positionAt(match.index + match[0].length);
 			moments.push({
-				range: new vscode.Range(pos, pos.translate(0, match[0].length)),
+				range: new vscode.Range(pos, end),
 				intent: "api-design",
 				suggestion: "Function takes String - consider &str for more flexibility",
 				ruleId: "string-vs-str",
 				severity: "hint",
 				contextReason: "Function parameter uses String instead of &str",
+				// A `mut` parameter is changed in place, which a &str can't be
+				fix: match[1]
+					? undefined
+					: {
+							title: "Take &str instead of String",
+							edits: [
+								vscode.TextEdit.replace(
+									new vscode.Range(end.translate(0, -"String".length), end),
+									"&str",
+								),
+							],
+						},
 			});
 		}
 
@@ -432,15 +386,11 @@ export class IntentAnalyzer {
 		document: vscode.TextDocument,
 		position: vscode.Position,
 	): Promise<CodeIntent> {
-		const workspaceFolder = vscode.workspace.getWorkspaceFolder(document.uri);
-		let projectType: ProjectType = "unknown";
-
-		if (workspaceFolder) {
-			projectType = await this.analyzeProject(workspaceFolder.uri);
-		}
+		const profile = await ProjectProfileService.getInstance().getProfile(document.uri);
+		const library = findTrait(profile, "library") ? "library" : "unknown";
 
 		return {
-			projectType,
+			projectType: primaryDomain(profile)?.trait ?? library,
 			fileIntent: this.analyzeFile(document),
 			blockIntent: this.analyzeBlock(document, position),
 			confidence: 0.7, // TODO: Calculate based on matches
@@ -451,7 +401,6 @@ export class IntentAnalyzer {
 	 * Clear caches (e.g., when project changes)
 	 */
 	clearCache() {
-		this.projectTypeCache = null;
 		this.lastAnalysis.clear();
 	}
 }
@@ -0,0 +1,348 @@
+import * as vscode from "vscode";
+import { normalizeCrateName, type ProjectContext } from "../rules";
+import { CargoAnalyzerService, type CargoCrate, type DetectedDependencies } from "./cargoAnalyzer";
+import type { CargoManifest } from "./cargoManifest";
+
+/**
+ * Something a crate is or does. The first five come from its targets, the rest describe what
+ * it's built for.
+ */
+export type ProjectTrait =
+	| "library"
+	| "binary"
+	| "proc-macro"
+	| "build-script"
+	| "benchmarks"
+	| "parser"
+	| "lexer"
+	| "compiler"
+	| "cli"
+	| "web-server"
+	| "game";
+
+/** Traits describing what a crate is built for, rather than how it's laid out */
+export type DomainTrait = Extract<
+	ProjectTrait,
+	"parser" | "lexer" | "compiler" | "cli" | "web-server" | "game"
+>;
+
+const DOMAIN_TRAITS: readonly DomainTrait[] = [
+	"parser",
+	"lexer",
+	"compiler",
+	"web-server",
+	"cli",
+	"game",
+];
+
+export interface DetectedTrait {
+	trait: ProjectTrait;
+	/** 0-1: dependencies and declared targets are near certain, names are hints */
+	confidence: number;
+	/** What gave it away, e.g. "Depends on nom" */
+	reason: string;
+}
+
+/**
+ * What Rust Compass knows about the kind of project a crate is
+ */
+export interface ProjectProfile {
+	/** Package the profile describes; absent when it covers the whole workspace */
+	crate?: string;
+	/** Most confident first, each trait once */
+	traits: DetectedTrait[];
+}
+
+/**
+ * What a profile is built from: a crate's manifest, its categorized dependencies and the
+ * `/`-separated paths of its files relative to the crate root
+ */
+export interface ProfileInput {
+	manifest: CargoManifest;
+	dependencies: DetectedDependencies;
+	files: string[];
+}
+
+/** Confidence from which a trait is taken as fact, e.g. to suggest a project context */
+export const LIKELY_CONFIDENCE = 0.6;
+
+const WEB_FRAMEWORKS = ["actix-web", "axum", "warp", "rocket", "poem", "tide"];
+const GAME_ENGINES = ["bevy", "ggez", "macroquad", "piston", "fyrox", "tetra"];
+const LEXER_CRATES = ["logos"];
+
+/** Words in package and file names that hint at a trait */
+const NAME_HINTS: [DomainTrait, string[]][] = [
+	["parser", ["parser", "parse", "parsing"]],
+	["lexer", ["lexer", "lex", "tokenizer", "scanner"]],
+	["compiler", ["compiler", "interpreter", "lang", "vm"]],
+	["web-server", ["server"]],
+	["cli", ["cli", "cmd"]],
+];
+
+const CONTEXT_FOR_TRAIT: Partial<Record<ProjectTrait, ProjectContext>> = {
+	parser: "parser",
+	lexer: "parser",
+	compiler: "parser",
+	"web-server": "web",
+	cli: "cli",
+};
+
+/**
+ * Work out a crate's traits. Names are split into words, so `anonymous` says nothing about
+ * nom and `language-server` is a server.
+ */
+export function buildProjectProfile(input: ProfileInput): ProjectProfile {
+	const { manifest, dependencies, files } = input;
+	const traits: DetectedTrait[] = [];
+	const add = (trait: ProjectTrait, confidence: number, reason: string) =>
+		traits.push({ trait, confidence, reason });
+	const list = (names: string[]) => names.join(", ");
+
+	// Targets, declared or found where Cargo looks for them
+	const { targets } = manifest;
+	if (targets.procMacro) {
+		add("proc-macro", 1, "`proc-macro = true` in [lib]");
+	}
+	if (targets.lib || files.includes("src/lib.rs")) {
+		add("library", 1, targets.lib ? "Has a [lib] section" : "Has src/lib.rs");
+	}
+	const binFiles = files.filter((f) => f === "src/main.rs" || /^src\/bin\/[^/]+\.rs$/.test(f));
+	if (targets.bins.length > 0 || binFiles.length > 0) {
+		const bins = targets.bins.length > 0 ? targets.bins.map((b) => `[[bin]] ${b}`) : binFiles;
+		add("binary", 1, `Has ${list(bins)}`);
+	}
+	if (typeof targets.build === "string") {
+		add("build-script", 1, `Runs build script ${targets.build}`);
+	} else if (targets.build !== false && files.includes("build.rs")) {
+		add("build-script", 1, "Has build.rs");
+	}
+	const benchFiles = files.filter((f) => f.startsWith("benches/"));
+	if (targets.benches.length > 0 || benchFiles.length > 0) {
+		const benches = targets.benches.length > 0 ? targets.benches : benchFiles;
+		add("benchmarks", 1, `Has benchmarks ${list(benches)}`);
+	} else if (dependencies.testing.includes("criterion")) {
+		add("benchmarks", 0.7, "Depends on criterion");
+	}
+
+	// Dependencies, by their exact names
+	const lexers = dependencies.parsing.filter((d) => LEXER_CRATES.includes(d));
+	const parsers = dependencies.parsing.filter((d) => !LEXER_CRATES.includes(d));
+	if (parsers.length > 0) {
+		add("parser", 0.9, `Depends on ${list(parsers)}`);
+	}
+	if (lexers.length > 0) {
+		add("lexer", 0.9, `Depends on ${list(lexers)}`);
+	}
+	const frameworks = dependencies.web.filter((d) => WEB_FRAMEWORKS.includes(d));
+	if (frameworks.length > 0) {
+		add("web-server", 0.9, `Depends on ${list(frameworks)}`);
+	} else if (dependencies.async.length > 0 && dependencies.database.length > 0) {
+		add("web-server", 0.6, "Uses an async runtime and a database, likely a backend service");
+	}
+	if (dependencies.cli.length > 0) {
+		add("cli", 0.9, `Depends on ${list(dependencies.cli)}`);
+	}
+	const engines = manifest.dependencies
+		.map((d) => normalizeCrateName(d.package))
+		.filter((name) => GAME_ENGINES.includes(name));
+	if (engines.length > 0) {
+		add("game", 0.9, `Depends on ${list([...new Set(engines)])}`);
+	}
+
+	// Names: the package's, then its source files'
+	const packageWords = nameWords(manifest.packageName ?? "");
+	for (const [trait, hints] of NAME_HINTS) {
+		if (hints.some((hint) => packageWords.includes(hint))) {
+			add(trait, 0.6, `Package is named "${manifest.packageName}"`);
+		}
+	}
+	const sourceFiles = files.filter((f) => f.startsWith("src/") && f.endsWith(".rs"));
+	const filesFor = (trait: DomainTrait) => {
+		const hints = NAME_HINTS.find(([t]) => t === trait)?.[1] ?? [];
+		// `src/parser/mod.rs` counts as much as `src/parser.rs`
+		return sourceFiles.filter((f) => {
+			const words = f.slice("src/".length, -".rs".length).split("/").flatMap(nameWords);
+			return hints.some((hint) => words.includes(hint));
+		});
+	};
+	const lexerFiles = filesFor("lexer");
+	const parserFiles = filesFor("parser");
+	if (lexerFiles.length > 0 && parserFiles.length > 0) {
+		add("compiler", 0.6, `Has ${list([lexerFiles[0], parserFiles[0]])}`);
+	}
+	if (lexerFiles.length > 0) {
+		add("lexer", 0.5, `Has ${list(lexerFiles)}`);
+	}
+	if (parserFiles.length > 0) {
+		add("parser", 0.5, `Has ${list(parserFiles)}`);
+	}
+
+	return { crate: manifest.packageName, traits: strongestPerTrait(traits) };
+}
+
+/**
+ * One profile for several crates: each trait from the crate showing it most clearly
+ */
+export function mergeProfiles(profiles: ProjectProfile[]): ProjectProfile {
+	return {
+		traits: strongestPerTrait(
+			profiles.flatMap(({ crate, traits }) =>
+				traits.map((t) => ({ ...t, reason: crate ? `${crate}: ${t.reason}` : t.reason })),
+			),
+		),
+	};
+}
+
+/**
+ * A trait of the profile, if detected with at least `minConfidence`
+ */
+export function findTrait(
+	profile: ProjectProfile,
+	trait: ProjectTrait,
+	minConfidence = LIKELY_CONFIDENCE,
+): DetectedTrait | undefined {
+	return profile.traits.find((t) => t.trait === trait && t.confidence >= minConfidence);
+}
+
+/**
+ * The most likely thing the crate is built for
+ */
+export function primaryDomain(
+	profile: ProjectProfile,
+	minConfidence = LIKELY_CONFIDENCE,
+): (DetectedTrait & { trait: DomainTrait }) | undefined {
+	return profile.traits.find(
+		(t): t is DetectedTrait & { trait: DomainTrait } =>
+			DOMAIN_TRAITS.includes(t.trait as DomainTrait) && t.confidence >= minConfidence,
+	);
+}
+
+/**
+ * The project context matching a profile's most likely domain, with the evidence for it
+ */
+export function suggestProjectContext(
+	profile: ProjectProfile,
+): { context: ProjectContext; reason: string } | null {
+	const domain = primaryDomain(profile);
+	const context = domain && CONTEXT_FOR_TRAIT[domain.trait];
+	return domain && context
+		? { context, reason: `${domain.reason} - looks like a ${domain.trait} project` }
+		: null;
+}
+
+/**
+ * A sentence describing the project for AI prompts, e.g. "My project is a library and binary
+ * crate; it looks like a parser (Depends on nom)."
+ */
+export function describeProfile(profile: ProjectProfile): string {
+	// A proc-macro crate is a library too, but of a kind worth naming
+	const kinds = findTrait(profile, "proc-macro")
+		? ["proc-macro"]
+		: (["library", "binary"] as const).filter((t) => findTrait(profile, t));
+	const domain = primaryDomain(profile);
+	if (kinds.length === 0 && !domain) {
+		return "";
+	}
+	const kind = kinds.length > 0 ? `a ${kinds.join(" and ")} crate` : "a Rust crate";
+	const purpose = domain ? `; it looks like a ${domain.trait} (${domain.reason})` : "";
+	return `My project is ${kind}${purpose}.`;
+}
+
+/**
+ * Profiles of the workspace's crates, built from their manifests and files
+ */
+export class ProjectProfileService {
+	private static instance: ProjectProfileService;
+	private cargoAnalyzer = CargoAnalyzerService.getInstance();
+	// Profiles per crate root
+	private profiles = new Map<string, Promise<ProjectProfile>>();
+	private disposables: vscode.Disposable[] = [];
+
+	public static getInstance(): ProjectProfileService {
+		if (!ProjectProfileService.instance) {
+			ProjectProfileService.instance = new ProjectProfileService();
+		}
+		return ProjectProfileService.instance;
+	}
+
+	private constructor() {
+		// Manifests were rescanned, or targets such as src/lib.rs came or went
+		const clear = () => this.profiles.clear();
+		const watcher = vscode.workspace.createFileSystemWatcher("**/*.rs", false, true, false);
+		this.disposables.push(
+			this.cargoAnalyzer.onDependenciesChanged(clear),
+			watcher,
+			watcher.onDidCreate(clear),
+			watcher.onDidDelete(clear),
+		);
+	}
+
+	/**
+	 * Profile of the crate owning `uri`, or of every crate in the workspace together
+	 */
+	public async getProfile(uri?: vscode.Uri): Promise<ProjectProfile> {
+		const owner = uri && (await this.cargoAnalyzer.getCrateFor(uri));
+		if (owner) {
+			return this.getCrateProfile(owner);
+		}
+		const crates = await this.cargoAnalyzer.getCrates();
+		return mergeProfiles(await Promise.all(crates.map((c) => this.getCrateProfile(c))));
+	}
+
+	private getCrateProfile(crate: CargoCrate): Promise<ProjectProfile> {
+		const key = crate.root.toString();
+		let profile = this.profiles.get(key);
+		if (!profile) {
+			profile = this.buildProfile(crate);
+			this.profiles.set(key, profile);
+		}
+		return profile;
+	}
+
+	private async buildProfile(crate: CargoCrate): Promise<ProjectProfile> {
+		const found = await vscode.workspace.findFiles(
+			new vscode.RelativePattern(crate.root, "{build.rs,src/**/*.rs,benches/**/*.rs}"),
+			"**/target/**",
+			500,
+		);
+		const root = crate.root.path.replace(/\/$/, "");
+		const files = found.map((uri) => uri.path.slice(root.length + 1));
+		return buildProjectProfile({
+			manifest: crate.manifest,
+			dependencies: crate.dependencies,
+			files,
+		});
+	}
+
+	public dispose(): void {
+		for (const disposable of this.disposables) {
+			disposable.dispose();
+		}
+		this.profiles.clear();
+	}
+}
+
+/**
+ * Lowercase words of a crate or file name, split at `-`, `_` and camel case
+ */
+function nameWords(name: string): string[] {
+	return name
+		.replace(/([a-z0-9])([A-Z])/g, "$1-$2")
+		.toLowerCase()
+		.split(/[-_.\s]+/)
+		.filter(Boolean);
+}
+
+/**
+ * Keep the most confident detection of each trait, most confident trait first
+ */
+function strongestPerTrait(traits: DetectedTrait[]): DetectedTrait[] {
+	const strongest = new Map<ProjectTrait, DetectedTrait>();
+	for (const detected of traits) {
+		const current = strongest.get(detected.trait);
+		if (!current || detected.confidence > current.confidence) {
+			strongest.set(detected.trait, detected);
+		}
+	}
+	return [...strongest.values()].sort((a, b) => b.confidence - a.confidence);
+}
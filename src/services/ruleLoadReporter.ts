@@ -0,0 +1,166 @@
+import * as path from "node:path";
+import * as vscode from "vscode";
+import type { RuleEngine, RuleLoadIssue, RuleLoadReport } from "../rules";
+
+/**
+ * Surfaces problems from loading rule files: diagnostics on the offending JSON,
+ * a notification for workspace overrides, and an on-demand load report
+ */
+export class RuleLoadReporter implements vscode.Disposable {
+	private diagnosticCollection: vscode.DiagnosticCollection;
+
+	constructor(private ruleEngine: RuleEngine) {
+		this.diagnosticCollection =
+			vscode.languages.createDiagnosticCollection("rust-compass-rules");
+	}
+
+	/**
+	 * Publish the engine's latest load report. Call after every (re)load.
+	 */
+	public update(): void {
+		const report = this.ruleEngine.getLoadReport();
+		this.publishDiagnostics(report);
+		this.notify(report);
+	}
+
+	private publishDiagnostics(report: RuleLoadReport): void {
+		this.diagnosticCollection.clear();
+
+		const byFile = new Map<string, vscode.Diagnostic[]>();
+		for (const issue of report.issues) {
+			const diagnostics = byFile.get(issue.file) ?? [];
+			diagnostics.push(this.createDiagnostic(issue));
+			byFile.set(issue.file, diagnostics);
+		}
+
+		for (const [file, diagnostics] of byFile) {
+			this.diagnosticCollection.set(vscode.Uri.file(file), diagnostics);
+		}
+	}
+
+	private createDiagnostic(issue: RuleLoadIssue): vscode.Diagnostic {
+		const line = issue.line ?? 0;
+		const character = issue.character ?? 0;
+		// Underline the `"id": "..."` entry when we know where it is
+		const length =
+			issue.line !== undefined && issue.ruleId ? `"id": "${issue.ruleId}"`.length : 1;
+		const range = new vscode.Range(line, character, line, character + length);
+
+		const diagnostic = new vscode.Diagnostic(
+			range,
+			issue.skipped ? `${issue.message} (rule not loaded)` : issue.message,
+			issue.severity === "error"
+				? vscode.DiagnosticSeverity.Error
+				: vscode.DiagnosticSeverity.Warning,
+		);
+		diagnostic.source = "Rust Compass";
+		return diagnostic;
+	}
+
+	private notify(report: RuleLoadReport): void {
+		const skippedRules = report.files.reduce((sum, f) => sum + f.skipped, 0);
+		const brokenFiles = report.issues.filter((i) => i.skipped && !i.ruleId).length;
+		if (skippedRules > 0 || brokenFiles > 0) {
+			const parts: string[] = [];
+			if (skippedRules > 0) {
+				parts.push(`${skippedRules} rule(s)`);
+			}
+			if (brokenFiles > 0) {
+				parts.push(`${brokenFiles} rule file(s)`);
+			}
+			vscode.window
+				.showWarningMessage(
+					`🦀 Rust Compass skipped ${parts.join(" and ")} with errors`,
+					"Show Report",
+				)
+				.then((choice) => {
+					if (choice === "Show Report") {
+						this.showReport();
+					}
+				});
+		}
+
+		if (report.overrides.length > 0) {
+			const ids = report.overrides.map((o) => o.ruleId).join(", ");
+			vscode.window.showInformationMessage(
+				`🦀 Workspace rules override ${report.overrides.length} bundled rule(s): ${ids}`,
+			);
+		}
+	}
+
+	/**
+	 * Open the last load report as a markdown document
+	 */
+	public async showReport(): Promise<void> {
+		const doc = await vscode.workspace.openTextDocument({
+			language: "markdown",
+			content: formatReport(this.ruleEngine.getLoadReport()),
+		});
+		await vscode.window.showTextDocument(doc, { preview: true });
+	}
+
+	dispose(): void {
+		this.diagnosticCollection.dispose();
+	}
+}
+
+function formatReport(report: RuleLoadReport): string {
+	const lines: string[] = ["# Rust Compass: Rule Load Report", ""];
+
+	const loaded = report.files.reduce((sum, f) => sum + f.loaded, 0);
+	const skipped = report.files.reduce((sum, f) => sum + f.skipped, 0);
+	lines.push(
+		`Loaded **${loaded}** rule(s) from ${report.files.length} file(s), skipped **${skipped}**.`,
+		"",
+	);
+
+	lines.push("## Files", "");
+	lines.push("| File | Origin | Loaded | Skipped |", "| --- | --- | --- | --- |");
+	for (const file of report.files) {
+		const origin = file.workspace ? "workspace" : "bundled";
+		lines.push(`| ${displayPath(file.file)} | ${origin} | ${file.loaded} | ${file.skipped} |`);
+	}
+	lines.push("");
+
+	const errors = report.issues.filter((i) => i.severity === "error");
+	const warnings = report.issues.filter((i) => i.severity === "warning");
+
+	if (errors.length > 0) {
+		lines.push("## Skipped", "");
+		for (const issue of errors) {
+			lines.push(`- ${describeIssue(issue)}`);
+		}
+		lines.push("");
+	}
+
+	if (warnings.length > 0) {
+		lines.push("## Warnings", "");
+		for (const issue of warnings) {
+			lines.push(`- ${describeIssue(issue)}`);
+		}
+		lines.push("");
+	}
+
+	if (report.overrides.length > 0) {
+		lines.push("## Overrides", "");
+		for (const override of report.overrides) {
+			lines.push(
+				`- \`${override.ruleId}\` from ${displayPath(override.source)} replaces ${displayPath(override.overriddenSource)}`,
+			);
+		}
+		lines.push("");
+	}
+
+	return lines.join("\n");
+}
+
+function describeIssue(issue: RuleLoadIssue): string {
+	const location = issue.line !== undefined ? `:${issue.line + 1}` : "";
+	const rule = issue.ruleId ? `\`${issue.ruleId}\` ` : "";
+	return `${rule}(${displayPath(issue.file)}${location}): ${issue.message}`;
+}
+
+function displayPath(file: string): string {
+	const relative = vscode.workspace.asRelativePath(file, true);
+	return relative === file ? path.basename(file) : relative;
+}
@@ -0,0 +1,123 @@
+import * as path from "node:path";
+import * as vscode from "vscode";
+
+/**
+ * Workspace-relative folder every workspace can drop house rules into
+ */
+const WORKSPACE_RULES_DIR = ".rust-compass/rules";
+
+/**
+ * Locates workspace rule packs and watches them so rules can be hot-reloaded
+ */
+export class RulePackWatcher implements vscode.Disposable {
+	private watchers: vscode.FileSystemWatcher[] = [];
+	private disposables: vscode.Disposable[] = [];
+	private debounceTimer: NodeJS.Timeout | null = null;
+
+	// Event emitter for rule file changes (debounced)
+	private _onDidChange = new vscode.EventEmitter<void>();
+	public readonly onDidChange = this._onDidChange.event;
+
+	constructor() {
+		this.createWatchers();
+
+		this.disposables.push(
+			vscode.workspace.onDidChangeConfiguration((e) => {
+				if (e.affectsConfiguration("rustCompass.ruleDirectories")) {
+					this.createWatchers();
+					this.scheduleChange();
+				}
+			}),
+			vscode.workspace.onDidChangeWorkspaceFolders(() => {
+				this.createWatchers();
+				this.scheduleChange();
+			}),
+			vscode.workspace.onDidGrantWorkspaceTrust(() => {
+				this.createWatchers();
+				this.scheduleChange();
+			}),
+		);
+	}
+
+	/**
+	 * Absolute directories to load workspace rules from: `.rust-compass/rules` in each
+	 * workspace folder plus `rustCompass.ruleDirectories` (relative entries resolve per folder).
+	 * None until the workspace is trusted, since rule packs come with the repository.
+	 */
+	public static getRuleDirectories(): string[] {
+		if (!vscode.workspace.isTrusted) {
+			return [];
+		}
+		const folders = vscode.workspace.workspaceFolders ?? [];
+		const dirs = new Set<string>();
+
+		for (const folder of folders) {
+			dirs.add(path.join(folder.uri.fsPath, WORKSPACE_RULES_DIR));
+		}
+		for (const dir of RulePackWatcher.getConfiguredDirectories()) {
+			if (path.isAbsolute(dir)) {
+				dirs.add(dir);
+			} else {
+				for (const folder of folders) {
+					dirs.add(path.join(folder.uri.fsPath, dir));
+				}
+			}
+		}
+
+		return Array.from(dirs);
+	}
+
+	private static getConfiguredDirectories(): string[] {
+		const config = vscode.workspace.getConfiguration("rustCompass");
+		return config.get<string[]>("ruleDirectories") ?? [];
+	}
+
+	private createWatchers(): void {
+		this.watchers.forEach((w) => w.dispose());
+		this.watchers = [];
+		if (!vscode.workspace.isTrusted) {
+			return;
+		}
+
+		// Patterns are rooted at the workspace folder so the rules folder may be created later
+		const patterns: vscode.RelativePattern[] = [];
+		for (const folder of vscode.workspace.workspaceFolders ?? []) {
+			patterns.push(new vscode.RelativePattern(folder, `${WORKSPACE_RULES_DIR}/*.json`));
+		}
+		for (const dir of RulePackWatcher.getConfiguredDirectories()) {
+			if (path.isAbsolute(dir)) {
+				patterns.push(new vscode.RelativePattern(vscode.Uri.file(dir), "*.json"));
+			} else {
+				const relative = dir.replace(/\\/g, "/").replace(/\/+$/, "");
+				for (const folder of vscode.workspace.workspaceFolders ?? []) {
+					patterns.push(new vscode.RelativePattern(folder, `${relative}/*.json`));
+				}
+			}
+		}
+
+		for (const pattern of patterns) {
+			const watcher = vscode.workspace.createFileSystemWatcher(pattern);
+			watcher.onDidChange(() => this.scheduleChange());
+			watcher.onDidCreate(() => this.scheduleChange());
+			watcher.onDidDelete(() => this.scheduleChange());
+			this.watchers.push(watcher);
+		}
+	}
+
+	private scheduleChange(): void {
+		if (this.debounceTimer) {
+			clearTimeout(this.debounceTimer);
+		}
+		// Editors often write a file several times in quick succession
+		this.debounceTimer = setTimeout(() => this._onDidChange.fire(), 300);
+	}
+
+	dispose(): void {
+		if (this.debounceTimer) {
+			clearTimeout(this.debounceTimer);
+		}
+		this.watchers.forEach((w) => w.dispose());
+		this.disposables.forEach((d) => d.dispose());
+		this._onDidChange.dispose();
+	}
+}
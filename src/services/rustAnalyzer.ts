@@ -1,4 +1,5 @@
 import * as vscode from "vscode";
+import type { ReceiverType } from "../rules";
 
 /**
  * Interface for rust-analyzer hover information
@@ -108,6 +109,21 @@ export class RustAnalyzerService {
 		return this.parseTypeString(hoverInfo.type);
 	}
 
+	/**
+	 * Classify the value (or method result) at a position as an iterator, Option, Result or string
+	 */
+	public async getReceiverType(
+		document: vscode.TextDocument,
+		position: vscode.Position,
+	): Promise<ReceiverType | undefined> {
+		const hoverInfo = await this.getHoverInfo(document, position);
+		if (!hoverInfo?.type) {
+			return undefined;
+		}
+
+		return classifyReceiverType(hoverInfo.type);
+	}
+
 	/**
 	 * Parse hover content from rust-analyzer
 	 */
@@ -218,30 +234,39 @@ export class RustAnalyzerService {
 			return undefined;
 		}
 	}
+}
 
-	/**
-	 * Check if we're inside a loop construct
-	 */
-	public async isInsideLoop(
-		document: vscode.TextDocument,
-		position: vscode.Position,
-	): Promise<boolean> {
-		// Simple heuristic: look backwards for loop keywords
-		const text = document.getText(
-			new vscode.Range(new vscode.Position(Math.max(0, position.line - 20), 0), position),
-		);
-
-		// Count open braces and loop keywords
-		const loopPattern = /\b(for|while|loop)\b[^{]*\{/g;
-		let loopCount = 0;
-		while (loopPattern.exec(text) !== null) {
-			loopCount++;
-		}
+const RECEIVER_TYPE_PATTERNS: [ReceiverType, RegExp][] = [
+	["option", /\bOption</],
+	["result", /\b(io::)?Result</],
+	["string", /\b(String|str)\b/],
+	[
+		"iterator",
+		/\b(impl Iterator|Iter\w*|IntoIter|Chars|Bytes|Lines|Split\w*|Map|Filter\w*|Peekable|Skip\w*|Take\w*|Rev|Enumerate|Zip|Chain|Range\w*)\b/,
+	],
+];
 
-		// Count closing braces after loop keywords
-		const closeBraces = (text.match(/\}/g) || []).length;
+/**
+ * `let s: &str` → string, `pub fn iter(&self) -> Iter<'_, T>` → iterator.
+ * Picks whichever known type appears first, i.e. the outermost one.
+ */
+function classifyReceiverType(typeStr: string): ReceiverType | undefined {
+	const declaration = typeStr
+		.split("\n")
+		.map((line) => line.trim())
+		.filter((line) => line && !line.startsWith("//"))
+		.join(" ")
+		.replace(/\bwhere\b.*$/, "");
+	const type = declaration.includes("->")
+		? declaration.slice(declaration.lastIndexOf("->") + 2)
+		: declaration.replace(/^.*?\b\w+\s*:\s*/, "");
 
-		// Rough heuristic: if we have more loop openings than closes, we're likely in a loop
-		return loopCount > closeBraces;
+	let best: { kind: ReceiverType; index: number } | undefined;
+	for (const [kind, pattern] of RECEIVER_TYPE_PATTERNS) {
+		const index = type.search(pattern);
+		if (index !== -1 && (!best || index < best.index)) {
+			best = { kind, index };
+		}
 	}
+	return best?.kind;
 }
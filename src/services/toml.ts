@@ -0,0 +1,453 @@
+export type TomlValue = string | number | boolean | TomlValue[] | TomlTable;
+
+export interface TomlTable {
+	[key: string]: TomlValue;
+}
+
+export interface TomlError {
+	/** Zero-based line the problem was found on */
+	line: number;
+	message: string;
+}
+
+export interface TomlDocument {
+	root: TomlTable;
+	errors: TomlError[];
+}
+
+const BARE_KEY = /[A-Za-z0-9_-]/;
+const SCALAR_CHAR = /[A-Za-z0-9_+\-.:]/;
+const ESCAPES: Record<string, string> = {
+	b: "\b",
+	t: "\t",
+	n: "\n",
+	f: "\f",
+	r: "\r",
+	'"': '"',
+	"\\": "\\",
+};
+
+/**
+ * Whether a value is a (non-array) table
+ */
+export function isTomlTable(value: TomlValue | undefined): value is TomlTable {
+	return typeof value === "object" && !Array.isArray(value);
+}
+
+/**
+ * An empty table. It has no prototype, so keys like `constructor` or `__proto__` are plain keys.
+ */
+function createTable(): TomlTable {
+	return Object.create(null);
+}
+
+/**
+ * Small TOML reader, enough for Cargo manifests and `rust-toolchain.toml`.
+ * A line it can't make sense of is reported in `errors` and skipped rather than failing the
+ * whole file, so a half-edited manifest still yields everything else. Dates and times are
+ * kept as their source text.
+ */
+export function parseToml(source: string): TomlDocument {
+	return new TomlParser(source).parse();
+}
+
+class TomlSyntaxError extends Error {}
+
+class TomlParser {
+	private pos = 0;
+	private readonly root = createTable();
+	private readonly errors: TomlError[] = [];
+	/** Tables created by a `[header]` or a dotted key, which a later `[header]` may not redefine */
+	private readonly defined = new Set<TomlTable>();
+	/** Inline tables and arrays, which are closed to later additions */
+	private readonly frozen = new Set<TomlValue>();
+
+	constructor(private readonly source: string) {}
+
+	public parse(): TomlDocument {
+		let current = this.root;
+
+		for (;;) {
+			this.skipBlank(true);
+			if (this.pos >= this.source.length) {
+				break;
+			}
+			try {
+				if (this.source.startsWith("[[", this.pos)) {
+					this.pos += 2;
+					// Until the header parses, keys go nowhere rather than into the previous table
+					current = createTable();
+					const path = this.parseKey();
+					this.expect("]]");
+					current = this.appendTable(path);
+				} else if (this.source[this.pos] === "[") {
+					this.pos++;
+					current = createTable();
+					const path = this.parseKey();
+					this.expect("]");
+					current = this.defineTable(path);
+				} else {
+					this.parseKeyValue(current);
+				}
+				this.skipBlank(false);
+				if (this.pos < this.source.length && this.source[this.pos] !== "\n") {
+					this.fail("Expected the end of the line");
+				}
+			} catch (err) {
+				if (!(err instanceof TomlSyntaxError)) {
+					throw err;
+				}
+				this.errors.push({ line: this.lineAt(this.pos), message: err.message });
+				const newline = this.source.indexOf("\n", this.pos);
+				this.pos = newline === -1 ? this.source.length : newline;
+			}
+		}
+
+		return { root: this.root, errors: this.errors };
+	}
+
+	private parseKeyValue(table: TomlTable): void {
+		const path = this.parseKey();
+		this.skipBlank(false);
+		this.expect("=");
+		this.skipBlank(false);
+		const value = this.parseValue();
+
+		const parent = this.walk(table, path.slice(0, -1), true);
+		const key = path[path.length - 1];
+		if (key in parent) {
+			this.fail(`Duplicate key \`${path.join(".")}\``);
+		}
+		parent[key] = value;
+	}
+
+	/**
+	 * A dotted key such as `target.'cfg(unix)'.dependencies`
+	 */
+	private parseKey(): string[] {
+		const path: string[] = [];
+		for (;;) {
+			this.skipBlank(false);
+			const ch = this.source[this.pos];
+			if (ch === '"') {
+				path.push(this.parseBasicString());
+			} else if (ch === "'") {
+				path.push(this.parseLiteralString());
+			} else {
+				const start = this.pos;
+				while (this.pos < this.source.length && BARE_KEY.test(this.source[this.pos])) {
+					this.pos++;
+				}
+				if (this.pos === start) {
+					this.fail("Expected a key");
+				}
+				path.push(this.source.slice(start, this.pos));
+			}
+			this.skipBlank(false);
+			if (this.source[this.pos] !== ".") {
+				return path;
+			}
+			this.pos++;
+		}
+	}
+
+	private parseValue(): TomlValue {
+		const ch = this.source[this.pos];
+		if (this.source.startsWith('"""', this.pos)) {
+			return this.parseMultilineString('"""');
+		}
+		if (this.source.startsWith("'''", this.pos)) {
+			return this.parseMultilineString("'''");
+		}
+		if (ch === '"') {
+			return this.parseBasicString();
+		}
+		if (ch === "'") {
+			return this.parseLiteralString();
+		}
+		if (ch === "[") {
+			return this.parseArray();
+		}
+		if (ch === "{") {
+			return this.parseInlineTable();
+		}
+		return this.parseScalar();
+	}
+
+	private parseBasicString(): string {
+		this.pos++;
+		let value = "";
+		for (;;) {
+			const ch = this.source[this.pos];
+			if (ch === undefined || ch === "\n") {
+				this.fail("Unterminated string");
+			}
+			this.pos++;
+			if (ch === '"') {
+				return value;
+			}
+			value += ch === "\\" ? this.parseEscape() : ch;
+		}
+	}
+
+	private parseLiteralString(): string {
+		const end = this.source.indexOf("'", this.pos + 1);
+		const newline = this.source.indexOf("\n", this.pos);
+		if (end === -1 || (newline !== -1 && newline < end)) {
+			this.fail("Unterminated string");
+		}
+		const value = this.source.slice(this.pos + 1, end);
+		this.pos = end + 1;
+		return value;
+	}
+
+	private parseMultilineString(quotes: string): string {
+		this.pos += 3;
+		// A newline right after the opening quotes isn't part of the string
+		if (this.source.startsWith("\r\n", this.pos)) {
+			this.pos += 2;
+		} else if (this.source[this.pos] === "\n") {
+			this.pos++;
+		}
+
+		let value = "";
+		for (;;) {
+			if (this.pos >= this.source.length) {
+				this.fail("Unterminated string");
+			}
+			if (this.source.startsWith(quotes, this.pos)) {
+				// Up to two quotes may sit right before the closing ones
+				let end = this.pos + 3;
+				while (end < this.pos + 5 && this.source[end] === quotes[0]) {
+					end++;
+				}
+				value += quotes[0].repeat(end - this.pos - 3);
+				this.pos = end;
+				return value;
+			}
+			const ch = this.source[this.pos++];
+			if (ch !== "\\" || quotes === "'''") {
+				value += ch;
+			} else if (/^[ \t]*\r?\n/.test(this.source.slice(this.pos, this.pos + 64))) {
+				// A line-ending backslash trims the newline and the indentation that follows
+				while (/\s/.test(this.source[this.pos] ?? "")) {
+					this.pos++;
+				}
+			} else {
+				value += this.parseEscape();
+			}
+		}
+	}
+
+	/**
+	 * The character an escape stands for; `pos` is just past the backslash
+	 */
+	private parseEscape(): string {
+		const ch = this.source[this.pos++];
+		if (ch in ESCAPES) {
+			return ESCAPES[ch];
+		}
+		if (ch === "u" || ch === "U") {
+			const length = ch === "u" ? 4 : 8;
+			const hex = this.source.slice(this.pos, this.pos + length);
+			const code = Number.parseInt(hex, 16);
+			if (!/^[0-9A-Fa-f]+$/.test(hex) || hex.length !== length || code > 0x10ffff) {
+				this.fail(`Invalid unicode escape \`\\${ch}${hex}\``);
+			}
+			this.pos += length;
+			return String.fromCodePoint(code);
+		}
+		this.fail(`Invalid escape \`\\${ch ?? ""}\``);
+	}
+
+	private parseArray(): TomlValue[] {
+		this.pos++;
+		const values: TomlValue[] = [];
+		for (;;) {
+			this.skipBlank(true);
+			if (this.source[this.pos] === "]") {
+				this.pos++;
+				break;
+			}
+			values.push(this.parseValue());
+			this.skipBlank(true);
+			if (this.source[this.pos] === ",") {
+				this.pos++;
+			} else if (this.source[this.pos] !== "]") {
+				this.fail("Expected `,` or `]` in array");
+			}
+		}
+		this.frozen.add(values);
+		return values;
+	}
+
+	private parseInlineTable(): TomlTable {
+		this.pos++;
+		const table = createTable();
+		this.skipBlank(false);
+		if (this.source[this.pos] === "}") {
+			this.pos++;
+			this.frozen.add(table);
+			return table;
+		}
+		for (;;) {
+			this.parseKeyValue(table);
+			this.skipBlank(false);
+			const ch = this.source[this.pos++];
+			if (ch === "}") {
+				break;
+			}
+			if (ch !== ",") {
+				this.fail("Expected `,` or `}` in inline table");
+			}
+		}
+		this.frozen.add(table);
+		return table;
+	}
+
+	/**
+	 * Booleans, numbers, and dates (kept as text)
+	 */
+	private parseScalar(): TomlValue {
+		const start = this.pos;
+		while (this.pos < this.source.length && SCALAR_CHAR.test(this.source[this.pos])) {
+			this.pos++;
+		}
+		// Local date-times may use a space instead of `T`
+		if (
+			/^\d{4}-\d{2}-\d{2}$/.test(this.source.slice(start, this.pos)) &&
+			/^ \d{2}:/.test(this.source.slice(this.pos, this.pos + 4))
+		) {
+			this.pos++;
+			while (this.pos < this.source.length && SCALAR_CHAR.test(this.source[this.pos])) {
+				this.pos++;
+			}
+		}
+
+		const text = this.source.slice(start, this.pos);
+		if (text === "true" || text === "false") {
+			return text === "true";
+		}
+		if (/^[+-]?(inf|nan)$/.test(text)) {
+			return text.endsWith("nan") ? Number.NaN : text.startsWith("-") ? -Infinity : Infinity;
+		}
+		const digits = text.replace(/_/g, "");
+		if (/^0x[0-9A-Fa-f]+$/.test(digits)) {
+			return Number.parseInt(digits.slice(2), 16);
+		}
+		if (/^0o[0-7]+$/.test(digits)) {
+			return Number.parseInt(digits.slice(2), 8);
+		}
+		if (/^0b[01]+$/.test(digits)) {
+			return Number.parseInt(digits.slice(2), 2);
+		}
+		if (/^[+-]?\d+(\.\d+)?([eE][+-]?\d+)?$/.test(digits)) {
+			return Number(digits);
+		}
+		if (/^\d{4}-\d{2}-\d{2}|^\d{2}:\d{2}:\d{2}/.test(text)) {
+			return text;
+		}
+		this.pos = start;
+		this.fail(text ? `Invalid value \`${text}\`` : "Expected a value");
+	}
+
+	/**
+	 * The table a `[header]` opens, created along with any missing parents
+	 */
+	private defineTable(path: string[]): TomlTable {
+		const parent = this.walk(this.root, path.slice(0, -1), false);
+		const key = path[path.length - 1];
+		const existing = parent[key];
+		if (existing === undefined) {
+			const table = createTable();
+			parent[key] = table;
+			this.defined.add(table);
+			return table;
+		}
+		if (!isTomlTable(existing) || this.defined.has(existing) || this.frozen.has(existing)) {
+			this.fail(`Table \`${path.join(".")}\` is defined more than once`);
+		}
+		this.defined.add(existing);
+		return existing;
+	}
+
+	/**
+	 * A new table appended to the array a `[[header]]` names
+	 */
+	private appendTable(path: string[]): TomlTable {
+		const parent = this.walk(this.root, path.slice(0, -1), false);
+		const key = path[path.length - 1];
+		const existing = parent[key] ?? [];
+		if (!Array.isArray(existing) || this.frozen.has(existing)) {
+			this.fail(`\`${path.join(".")}\` is not an array of tables`);
+		}
+		const table = createTable();
+		existing.push(table);
+		parent[key] = existing;
+		return table;
+	}
+
+	/**
+	 * Follow a key path from a table, creating missing tables. A `[[header]]` array resolves to
+	 * its last table, as TOML specifies for headers nested under it.
+	 */
+	private walk(table: TomlTable, path: string[], dotted: boolean): TomlTable {
+		let current = table;
+		for (const key of path) {
+			let next = current[key];
+			if (next === undefined) {
+				next = createTable();
+				current[key] = next;
+				if (dotted) {
+					this.defined.add(next);
+				}
+			} else if (Array.isArray(next) && !this.frozen.has(next)) {
+				next = next[next.length - 1];
+			}
+			if (!isTomlTable(next) || this.frozen.has(next)) {
+				this.fail(`\`${key}\` is not a table`);
+			}
+			current = next;
+		}
+		return current;
+	}
+
+	/**
+	 * Skip spaces and comments, and also newlines when `newlines` is set
+	 */
+	private skipBlank(newlines: boolean): void {
+		while (this.pos < this.source.length) {
+			const ch = this.source[this.pos];
+			if (ch === " " || ch === "\t" || ch === "\r" || (newlines && ch === "\n")) {
+				this.pos++;
+			} else if (ch === "#") {
+				const newline = this.source.indexOf("\n", this.pos);
+				this.pos = newline === -1 ? this.source.length : newline;
+			} else {
+				break;
+			}
+		}
+	}
+
+	private expect(text: string): void {
+		this.skipBlank(false);
+		if (!this.source.startsWith(text, this.pos)) {
+			this.fail(`Expected \`${text}\``);
+		}
+		this.pos += text.length;
+	}
+
+	private fail(message: string): never {
+		throw new TomlSyntaxError(message);
+	}
+
+	private lineAt(offset: number): number {
+		let line = 0;
+		for (let i = 0; i < offset && i < this.source.length; i++) {
+			if (this.source[i] === "\n") {
+				line++;
+			}
+		}
+		return line;
+	}
+}
@@ -0,0 +1,207 @@
+import * as assert from "node:assert";
+import { satisfiesVersion, unmetDependency } from "../rules";
+import {
+	applyResolvedDependencies,
+	enabledFeatures,
+	isWorkspaceMember,
+	locateDependencies,
+	parseLockfile,
+	parseManifest,
+	parseToolchainVersion,
+	resolveLockedVersions,
+	resolveWorkspaceDependencies,
+	resolveWorkspacePackage,
+} from "../services/cargoManifest";
+
+const MANIFEST = `
+[package]
+name = "server" # trailing comments are fine
+
+[dependencies]
+tokio = { version = "1", features = ["full"] }
+serde = "1.0"
+json = { package = "serde_json", version = "1", optional = true }
+anyhow.workspace = true
+
+[dependencies.clap]
+version = "4.5"
+default-features = false
+features = [
+    "derive", # arrays may span lines
+    "env",
+]
+
+[dev-dependencies]
+proptest = "1"
+
+[target.'cfg(unix)'.dependencies]
+nix = "0.29"
+
+[workspace.dependencies]
+anyhow = { version = "1.0.86", features = ["backtrace"] }
+`;
+
+suite("Cargo manifest", () => {
+	test("reads every kind of dependency table", () => {
+		const manifest = parseManifest(MANIFEST);
+		assert.deepStrictEqual(manifest.errors, []);
+		assert.strictEqual(manifest.packageName, "server");
+
+		const summary = manifest.dependencies.map((d) => `${d.kind}:${d.name}:${d.package}`);
+		assert.deepStrictEqual(summary, [
+			"normal:tokio:tokio",
+			"normal:serde:serde",
+			"normal:json:serde_json",
+			"normal:anyhow:anyhow",
+			"normal:clap:clap",
+			"dev:proptest:proptest",
+			"normal:nix:nix",
+		]);
+
+		const byName = new Map(manifest.dependencies.map((d) => [d.name, d]));
+		assert.deepStrictEqual(byName.get("tokio")?.features, ["full"]);
+		assert.strictEqual(byName.get("json")?.optional, true);
+		assert.strictEqual(byName.get("nix")?.target, "cfg(unix)");
+
+		const clap = byName.get("clap");
+		assert.strictEqual(clap?.version, "4.5");
+		assert.strictEqual(clap?.defaultFeatures, false);
+		assert.deepStrictEqual(clap?.features, ["derive", "env"]);
+	});
+
+	test("dependency entries are located by line", () => {
+		const spans = locateDependencies(MANIFEST).map(
+			(l) => `${l.workspaceTable ? "workspace:" : ""}${l.name} ${l.startLine}-${l.endLine}`,
+		);
+		assert.deepStrictEqual(spans, [
+			"tokio 5-5",
+			"serde 6-6",
+			"json 7-7",
+			"anyhow 8-8",
+			"clap 10-16",
+			"proptest 19-19",
+			"nix 22-22",
+			"workspace:anyhow 25-25",
+		]);
+	});
+
+	test("workspace = true inherits from [workspace.dependencies]", () => {
+		const manifest = parseManifest(MANIFEST);
+		const anyhow = resolveWorkspaceDependencies(
+			manifest.dependencies,
+			manifest.workspaceDependencies,
+		).find((d) => d.name === "anyhow");
+		assert.strictEqual(anyhow?.workspace, true);
+		assert.strictEqual(anyhow?.version, "1.0.86");
+		assert.deepStrictEqual(anyhow?.features, ["backtrace"]);
+	});
+
+	test("a broken line doesn't hide the rest of the manifest", () => {
+		const manifest = parseManifest('[dependencies]\nserde = "1\nregex = "1"\n');
+		assert.strictEqual(manifest.errors.length, 1);
+		assert.strictEqual(manifest.errors[0].line, 1);
+		assert.deepStrictEqual(manifest.dependencies.map((d) => d.name), ["regex"]);
+	});
+
+	test("workspace members follow globs and exclude", () => {
+		const { workspace } = parseManifest(
+			'[workspace]\nmembers = ["crates/*", "tools/cli"]\nexclude = ["crates/legacy"]\n',
+		);
+		assert.ok(workspace);
+		assert.ok(isWorkspaceMember(workspace, ""));
+		assert.ok(isWorkspaceMember(workspace, "crates/server"));
+		assert.ok(isWorkspaceMember(workspace, "tools/cli"));
+		assert.ok(!isWorkspaceMember(workspace, "crates/server/nested"));
+		assert.ok(!isWorkspaceMember(workspace, "crates/legacy"));
+		assert.ok(!isWorkspaceMember(workspace, "tools/other"));
+	});
+
+	test("Cargo.lock pins the version each crate uses", () => {
+		const lock = parseLockfile(`
+version = 3
+
+[[package]]
+name = "clap"
+version = "2.34.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "clap"
+version = "4.5.4"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "serde"
+version = "1.0.203"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "server"
+version = "0.1.0"
+dependencies = [
+ "clap 4.5.4",
+ "serde",
+]
+`);
+		const manifest = parseManifest('[dependencies]\nclap = "4"\nserde = "1"\n');
+		const resolved = resolveLockedVersions("server", manifest.dependencies, lock);
+		assert.deepStrictEqual(
+			resolved.map((d) => `${d.name} ${d.resolvedVersion}`),
+			["clap 4.5.4", "serde 1.0.203"],
+		);
+	});
+
+	test("version requirements follow Cargo's rules", () => {
+		assert.ok(satisfiesVersion("4.5.4", ">=4"));
+		assert.ok(!satisfiesVersion("2.34.0", ">=4"));
+		assert.ok(satisfiesVersion("1.9.0", "1.2"));
+		assert.ok(!satisfiesVersion("0.4.0", "0.3"));
+		assert.ok(satisfiesVersion("1.2.9", "~1.2.3"));
+		assert.ok(!satisfiesVersion("1.8.0", "^1.2, <1.8"));
+	});
+
+	test("features come from cargo metadata, else from the manifest", () => {
+		const manifest = parseManifest('[dependencies]\nserde = "1"\ntokio = { version = "1" }\n');
+		const features = ["default", "macros", "rt-multi-thread"];
+		const [serde, tokio] = applyResolvedDependencies(manifest.dependencies, [
+			{ name: "tokio", version: "1.38.0", features },
+		]);
+		assert.deepStrictEqual(enabledFeatures(serde), ["default"]);
+		assert.strictEqual(tokio.resolvedVersion, "1.38.0");
+		assert.deepStrictEqual(enabledFeatures(tokio), features);
+
+		const dependencies = {
+			crates: ["serde", "tokio"],
+			versions: new Map(),
+			features: new Map([
+				["serde", enabledFeatures(serde)],
+				["tokio", enabledFeatures(tokio)],
+			]),
+			categories: [],
+		};
+		assert.strictEqual(
+			unmetDependency(dependencies, { name: "serde", features: ["derive"] }),
+			"serde's `derive` feature isn't enabled",
+		);
+		assert.strictEqual(
+			unmetDependency(dependencies, { name: "tokio", features: ["rt-multi-thread"] }),
+			undefined,
+		);
+	});
+
+	test("edition and rust-version follow the workspace and toolchain", () => {
+		const root = parseManifest(
+			'[workspace.package]\nedition = "2021"\nrust-version = "1.70"\n',
+		);
+		const member = resolveWorkspacePackage(
+			parseManifest('[package]\nedition.workspace = true\nrust-version = "1.65"\n'),
+			root,
+		);
+		assert.strictEqual(member.edition, "2021");
+		assert.strictEqual(member.rustVersion, "1.65");
+
+		assert.strictEqual(parseToolchainVersion('[toolchain]\nchannel = "1.70"\n'), "1.70.0");
+		assert.strictEqual(parseToolchainVersion("1.65.0\n"), "1.65.0");
+		assert.strictEqual(parseToolchainVersion('[toolchain]\nchannel = "stable"\n'), undefined);
+	});
+});
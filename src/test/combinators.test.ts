@@ -0,0 +1,132 @@
+import * as assert from "node:assert";
+import { findCombinatorRefactorings } from "../rules/refactors";
+import { SyntaxTree } from "../rules/syntax";
+import { applyEdits, inFn } from "./helpers";
+
+/** Titles and rewritten sources of the refactorings offered at `needle` */
+function refactor(source: string, needle: string): [string, string][] {
+	const refactorings = findCombinatorRefactorings(
+		SyntaxTree.parse(source),
+		source.indexOf(needle),
+	);
+	return refactorings.map((r): [string, string] => [r.title, applyEdits(source, r.edits)]);
+}
+
+suite("Combinator refactorings", () => {
+	test("map_or_else and its match convert into each other", () => {
+		const combinator = inFn(
+			"greet(name: Option<String>) -> String",
+			'name.map_or_else(|| String::from("Hello!"), |name| format!("Hello, {name}!"))',
+		);
+		const expanded = inFn(
+			"greet(name: Option<String>) -> String",
+			"match name {",
+			'    Some(name) => format!("Hello, {name}!"),',
+			'    None => String::from("Hello!"),',
+			"}",
+		);
+		assert.deepStrictEqual(refactor(combinator, "map_or_else"), [
+			["Expand .map_or_else() into a match", expanded],
+		]);
+		assert.deepStrictEqual(refactor(expanded, "match"), [
+			["Convert match to .map_or_else()", combinator],
+		]);
+	});
+
+	test("if let picks the simplest combinator", () => {
+		const cases: [string, string][] = [
+			["if let Some(x) = opt { Some(x + 1) } else { None }", "opt.map(|x| x + 1)"],
+			["if let Some(x) = opt { g(x) } else { None }", "opt.and_then(|x| g(x))"],
+			["if let Some(x) = opt { x } else { compute() }", "opt.unwrap_or_else(|| compute())"],
+			["if let Some(x) = opt { x } else { 0 }", "opt.unwrap_or_default()"],
+			["if let Some(x) = opt { x * 2 } else { 1 }", "opt.map_or(1, |x| x * 2)"],
+		];
+		for (const [ifLet, combinator] of cases) {
+			const [[, rewritten]] = refactor(inFn("f(opt: Option<u8>) -> u8", ifLet), "if let");
+			assert.strictEqual(rewritten, inFn("f(opt: Option<u8>) -> u8", combinator), ifLet);
+		}
+	});
+
+	test("a used error stays bound, including in format strings", () => {
+		const source = inFn(
+			"port(s: &str) -> u16",
+			"match s.parse::<u16>() {",
+			"    Ok(n) => n,",
+			'    Err(e) => panic!("{e}"),',
+			"}",
+		);
+		assert.deepStrictEqual(refactor(source, "match"), [
+			[
+				"Convert match to .unwrap_or_else()",
+				inFn("port(s: &str) -> u16", 's.parse::<u16>().unwrap_or_else(|e| panic!("{e}"))'),
+			],
+		]);
+	});
+
+	test("matching on a reference goes through as_ref", () => {
+		const source = inFn(
+			"len(o: &Option<String>) -> usize",
+			"match o {",
+			"    Some(s) => s.len(),",
+			"    None => 0,",
+			"}",
+		);
+		const [[, rewritten]] = refactor(source, "match");
+		assert.strictEqual(
+			rewritten,
+			inFn("len(o: &Option<String>) -> usize", "o.as_ref().map_or(0, |s| s.len())"),
+		);
+	});
+
+	test("expanded matches keep the Result error and parenthesize before methods", () => {
+		const [[, map]] = refactor(
+			inFn("f(s: &str) -> Result<u8, E>", "s.parse::<u8>().map(|n| n + 1)"),
+			"map(",
+		);
+		assert.strictEqual(
+			map,
+			inFn(
+				"f(s: &str) -> Result<u8, E>",
+				"match s.parse::<u8>() {",
+				"    Ok(n) => Ok(n + 1),",
+				"    Err(e) => Err(e),",
+				"}",
+			),
+		);
+
+		const [[, chained]] = refactor(
+			inFn("f(v: &[u8]) -> u8", "v.first().copied().unwrap_or(0).pow(2)"),
+			"unwrap_or",
+		);
+		assert.strictEqual(
+			chained,
+			inFn(
+				"f(v: &[u8]) -> u8",
+				"(match v.first().copied() {",
+				"    Some(value) => value,",
+				"    None => 0,",
+				"}).pow(2)",
+			),
+		);
+	});
+
+	test("not offered when the rewrite could change behaviour", () => {
+		const matchOpt = (arm: string) =>
+			inFn("f(opt: Option<u8>) -> u8", "match opt {", `    ${arm}`, "    None => 0,", "}");
+		const refused: [string, string][] = [
+			// Early returns can't move into a closure
+			[matchOpt("Some(x) => return x,"), "match"],
+			// Comments would be dropped
+			[matchOpt("Some(x) => x, // why"), "match"],
+			// Statements in a branch
+			[matchOpt("Some(x) => { log(x); x }"), "match"],
+			// Three branches
+			["fn f() { if let Some(x) = a { x } else if b { 1 } else { 2 }; }", "if let"],
+			// Unknown whether the field is an Option or a Result
+			[inFn("f(t: Thing) -> u8", "t.value.unwrap_or(0)"), "unwrap_or"],
+		];
+		for (const [source, needle] of refused) {
+			assert.deepStrictEqual(refactor(source, needle), [], source);
+		}
+	});
+});
@@ -0,0 +1,367 @@
+import * as assert from "node:assert";
+import * as vscode from "vscode";
+import { EditionReportPanel, LearnPanel } from "../webview";
+
+/**
+ * A `command:` link or code action command, with its arguments decoded the way VS Code does
+ */
+interface CommandInvocation {
+	command: string;
+	args: unknown[];
+}
+
+const EXTENSION_ID = "JohnK.rust-compass";
+
+suite("Command wiring", () => {
+	const restore: (() => void)[] = [];
+	let main: vscode.TextDocument;
+	let lexer: vscode.TextDocument;
+
+	suiteSetup(async () => {
+		// Commands end in notifications and pickers that would wait for a click
+		stub(vscode.window, "showInformationMessage", async () => undefined);
+		stub(vscode.window, "showWarningMessage", async () => undefined);
+		// Pickers take the first item, e.g. "Restore All"
+		stub(
+			vscode.window,
+			"showQuickPick",
+			async (items: readonly unknown[] | Thenable<readonly unknown[]>) => (await items)[0],
+		);
+
+		const folder = vscode.workspace.workspaceFolders?.[0];
+		assert.ok(folder, "tests run with test-workspace open");
+		main = await vscode.workspace.openTextDocument(
+			vscode.Uri.joinPath(folder.uri, "src", "main.rs"),
+		);
+		lexer = await vscode.workspace.openTextDocument(
+			vscode.Uri.joinPath(folder.uri, "src", "lexer.rs"),
+		);
+		await vscode.window.showTextDocument(lexer);
+		await vscode.extensions.getExtension(EXTENSION_ID)?.activate();
+	});
+
+	suiteTeardown(async () => {
+		for (const undo of restore.reverse()) {
+			undo();
+		}
+		LearnPanel.currentPanel?.dispose();
+		EditionReportPanel.currentPanel?.dispose();
+		await vscode.commands.executeCommand("workbench.action.closeAllEditors");
+	});
+
+	test("every contributed command is registered", async () => {
+		const registered = new Set(await vscode.commands.getCommands(true));
+		const extension = vscode.extensions.getExtension(EXTENSION_ID);
+		const contributed: { command: string }[] = extension?.packageJSON.contributes.commands;
+
+		assert.ok(contributed.length > 0);
+		for (const { command } of contributed) {
+			assert.ok(registered.has(command), `${command} is not registered`);
+		}
+	});
+
+	test("hover links run", async () => {
+		const invocations: CommandInvocation[] = [];
+		for (const text of [".iter()", ".enumerate(", ".peekable()"]) {
+			invocations.push(...(await hoverCommands(main, positionOf(main, text))));
+		}
+
+		const commands = new Set(invocations.map((i) => i.command));
+		assert.ok(commands.has("rust-compass.learnMore"), "hovers link to learnMore");
+		assert.ok(commands.has("rust-compass.askAI"), "hovers link to askAI");
+		await runAll(invocations);
+	});
+
+	test("Learn more opens the learn panel on the hovered rule", async () => {
+		const [learnMore] = (await hoverCommands(main, positionOf(main, ".enumerate("))).filter(
+			(i) => i.command === "rust-compass.learnMore",
+		);
+		assert.ok(learnMore);
+
+		await vscode.commands.executeCommand(learnMore.command, ...learnMore.args);
+		assert.strictEqual(LearnPanel.currentPanel?.rule?.id, "enumerate");
+	});
+
+	test("Learn about opens the learn panel on the rule at the cursor", async () => {
+		const actions = await codeActions(lexer, rangeAt(positionOf(lexer, ".unwrap()")));
+		const learn = actions.find((a) => a.title.startsWith("📖") && !a.diagnostics?.length);
+		assert.ok(learn?.command, "the match offers Learn about");
+		assert.strictEqual(learn.command.command, "rust-compass.showRuleDetails");
+
+		const { command, args } = toInvocation(learn.command);
+		await vscode.commands.executeCommand(command, ...args);
+		assert.strictEqual(LearnPanel.currentPanel?.rule?.id, "unwrap-usage");
+
+		await runAll(actions.flatMap((a) => (a.command ? [toInvocation(a.command)] : [])));
+	});
+
+	test("diagnostic code links and actions run", async () => {
+		const diagnostics = await waitForDiagnostics(lexer);
+		assert.ok(diagnostics.length > 0, "lexer.rs has teachable moments");
+
+		const invocations: CommandInvocation[] = [];
+		for (const diagnostic of diagnostics) {
+			// `code.target` is the link on the diagnostic code in the Problems panel
+			const target = typeof diagnostic.code === "object" ? diagnostic.code.target : undefined;
+			if (target?.scheme === "command") {
+				invocations.push({ command: target.path, args: [JSON.parse(target.query)] });
+			}
+
+			const actions = await codeActions(lexer, diagnostic.range);
+			const learn = actions.find(
+				(a) =>
+					a.title.startsWith("📖") &&
+					a.diagnostics?.some((d) => d.range.isEqual(diagnostic.range)),
+			);
+			assert.ok(learn?.command, `"${diagnostic.message}" offers Learn about`);
+			invocations.push(toInvocation(learn.command));
+		}
+
+		assert.ok(invocations.some((i) => i.command === "rust-compass.learnMore"));
+		await runAll(invocations);
+	});
+
+	test("teachable moments carry fixes", async () => {
+		const diagnostics = await waitForDiagnostics(lexer);
+		const fixes = new Map<string, string>();
+		for (const diagnostic of diagnostics) {
+			const actions = await codeActions(lexer, diagnostic.range);
+			const fix = actions.find(
+				(a) =>
+					a.isPreferred && a.diagnostics?.some((d) => d.range.isEqual(diagnostic.range)),
+			);
+			const code = typeof diagnostic.code === "object" ? String(diagnostic.code.value) : "";
+			if (fix?.edit) {
+				fixes.set(code, fix.edit.get(lexer.uri).map((e) => e.newText).join());
+			}
+		}
+
+		assert.strictEqual(fixes.get("string-vs-str"), "&str");
+		assert.strictEqual(fixes.get("iterator-next-without-peekable"), ".peekable()");
+		assert.strictEqual(fixes.get("unwrap-usage"), "?");
+	});
+
+	test("loop refactorings rewrite the loop and explain it", async () => {
+		const folder = vscode.workspace.workspaceFolders?.[0];
+		assert.ok(folder);
+		const loops = await vscode.workspace.openTextDocument(
+			vscode.Uri.joinPath(folder.uri, "src", "loops.rs"),
+		);
+		const actions = await codeActions(loops, rangeAt(positionOf(loops, "for i")));
+		const refactors = actions.filter((a) =>
+			a.kind?.contains(vscode.CodeActionKind.RefactorRewrite),
+		);
+		const titles = refactors.map((a) => a.title);
+		const edits = refactors.map((a) => a.edit?.get(loops.uri).map((e) => e.newText) ?? []);
+
+		assert.deepStrictEqual(titles, [
+			"Rust Compass: Convert loop to .map().collect()",
+			"Rust Compass: Loop over the elements instead of indexing",
+		]);
+		assert.deepStrictEqual(edits[0], [
+			"let lengths: Vec<_> = words.iter().map(|word| word.len()).collect();",
+		]);
+		assert.deepStrictEqual(edits[1], ["word in words.iter()", "word"]);
+
+		await runAll(refactors.flatMap((a) => (a.command ? [toInvocation(a.command)] : [])));
+	});
+
+	test("combinator refactorings convert in both directions", async () => {
+		const folder = vscode.workspace.workspaceFolders?.[0];
+		assert.ok(folder);
+		const greeting = await vscode.workspace.openTextDocument(
+			vscode.Uri.joinPath(folder.uri, "src", "greeting.rs"),
+		);
+		const refactors = [
+			...(await codeActions(greeting, rangeAt(positionOf(greeting, "match")))),
+			...(await codeActions(greeting, rangeAt(positionOf(greeting, ".unwrap_or")))),
+		].filter((a) => a.kind?.contains(vscode.CodeActionKind.RefactorRewrite));
+		const titles = refactors.map((a) => a.title);
+
+		assert.deepStrictEqual(titles, [
+			"Rust Compass: Convert match to .map_or_else()",
+			"Rust Compass: Expand .unwrap_or() into a match",
+		]);
+		await runAll(refactors.flatMap((a) => (a.command ? [toInvocation(a.command)] : [])));
+	});
+
+	test("Propagate with ? changes the function to return Result", async () => {
+		const folder = vscode.workspace.workspaceFolders?.[0];
+		assert.ok(folder);
+		const config = await vscode.workspace.openTextDocument(
+			vscode.Uri.joinPath(folder.uri, "src", "config.rs"),
+		);
+		await vscode.window.showTextDocument(config);
+		const actions = await codeActions(config, rangeAt(positionOf(config, ".unwrap()")));
+		const propagate = actions.find((a) => a.title === "Rust Compass: Propagate with ?");
+		assert.ok(propagate?.command);
+
+		const { command, args } = toInvocation(propagate.command);
+		await vscode.commands.executeCommand(command, ...args);
+		try {
+			const text = config.getText();
+			assert.ok(text.includes("-> Result<String, Box<dyn std::error::Error>> {"));
+			assert.ok(text.includes("fs::read_to_string(path)?;"));
+			assert.ok(text.includes("Ok(text.trim().to_string())"));
+		} finally {
+			await vscode.commands.executeCommand("workbench.action.files.revert");
+		}
+	});
+
+	test("dismissRule and restoreAllHints run", async () => {
+		await vscode.commands.executeCommand("rust-compass.dismissRule", { ruleId: "enumerate" });
+		await vscode.commands.executeCommand("rust-compass.restoreAllHints");
+	});
+
+	test("every command in the command palette runs", async () => {
+		const extension = vscode.extensions.getExtension(EXTENSION_ID);
+		const contributes = extension?.packageJSON.contributes;
+		const hidden = new Set(
+			(contributes.menus.commandPalette as { command: string; when: string }[])
+				.filter((m) => m.when === "false")
+				.map((m) => m.command),
+		);
+		const commands = (contributes.commands as { command: string }[])
+			.map((c) => c.command)
+			.filter((command) => !hidden.has(command));
+		assert.ok(commands.includes("rust-compass.applyFixForRule"));
+
+		// Settings changes are recorded instead of written to test-workspace
+		const updates: [string, unknown][] = [];
+		const getConfiguration = vscode.workspace.getConfiguration;
+		stub(vscode.workspace, "getConfiguration", (...args: Parameters<typeof getConfiguration>) =>
+			Object.assign(Object.create(getConfiguration(...args)), {
+				update: async (key: string, value: unknown) => {
+					updates.push([key, value]);
+				},
+			}),
+		);
+		// Nothing to fix here, so Apply Fix for Rule stops before previewing an edit
+		const empty = await vscode.workspace.openTextDocument({
+			language: "rust",
+			content: "fn main() {}\n",
+		});
+		await vscode.window.showTextDocument(empty);
+
+		for (const command of commands) {
+			await vscode.commands.executeCommand(command);
+		}
+		assert.deepStrictEqual(updates, [
+			["enabled", false],
+			["projectContext", "general"],
+		]);
+	});
+
+	/**
+	 * Replace a function on an API namespace until the suite ends
+	 */
+	function stub<T extends object, K extends keyof T>(target: T, key: K, value: unknown): void {
+		const original = target[key];
+		(target as Record<K, unknown>)[key] = value;
+		restore.push(() => {
+			target[key] = original;
+		});
+	}
+});
+
+function positionOf(document: vscode.TextDocument, text: string): vscode.Position {
+	const offset = document.getText().indexOf(text);
+	assert.notStrictEqual(offset, -1, `${text} is in ${document.fileName}`);
+	// On the method name rather than the dot
+	return document.positionAt(offset + 1);
+}
+
+async function hoverCommands(
+	document: vscode.TextDocument,
+	position: vscode.Position,
+): Promise<CommandInvocation[]> {
+	const hovers = await vscode.commands.executeCommand<vscode.Hover[]>(
+		"vscode.executeHoverProvider",
+		document.uri,
+		position,
+	);
+	return hovers
+		.flatMap((h) => h.contents)
+		.map((c) => (typeof c === "string" ? c : c.value))
+		.flatMap(commandLinks);
+}
+
+function rangeAt(position: vscode.Position): vscode.Range {
+	return new vscode.Range(position, position);
+}
+
+async function codeActions(
+	document: vscode.TextDocument,
+	range: vscode.Range,
+): Promise<vscode.CodeAction[]> {
+	return vscode.commands.executeCommand<vscode.CodeAction[]>(
+		"vscode.executeCodeActionProvider",
+		document.uri,
+		range,
+	);
+}
+
+/**
+ * Rust Compass diagnostics for a document, once the (debounced) analysis has published them
+ */
+async function waitForDiagnostics(document: vscode.TextDocument): Promise<vscode.Diagnostic[]> {
+	for (let attempt = 0; attempt < 50; attempt++) {
+		const diagnostics = vscode.languages
+			.getDiagnostics(document.uri)
+			.filter((d) => d.source === "Rust Compass");
+		if (diagnostics.length > 0) {
+			return diagnostics;
+		}
+		await new Promise((resolve) => setTimeout(resolve, 100));
+	}
+	return [];
+}
+
+/**
+ * Every `[label](command:id?args)` link in a markdown string. Link targets may contain
+ * balanced parentheses, since `encodeURIComponent` leaves them alone.
+ */
+function commandLinks(markdown: string): CommandInvocation[] {
+	const links: CommandInvocation[] = [];
+	const marker = "](command:";
+	for (let i = markdown.indexOf(marker); i !== -1; i = markdown.indexOf(marker, i + 1)) {
+		const start = i + 2;
+		let depth = 0;
+		let end = start;
+		for (; end < markdown.length; end++) {
+			const c = markdown[end];
+			if (c === "(") {
+				depth++;
+			} else if (c === ")" && depth-- === 0) {
+				break;
+			}
+		}
+		links.push(parseCommandUri(markdown.slice(start, end)));
+	}
+	return links;
+}
+
+/**
+ * `command:id?%7B...%7D` → the command and its arguments (a JSON array is spread)
+ */
+function parseCommandUri(uri: string): CommandInvocation {
+	const match = /^command:([\w.-]+)(?:\?(.*))?$/s.exec(uri);
+	assert.ok(match, `${uri} is a command link`);
+	if (!match[2]) {
+		return { command: match[1], args: [] };
+	}
+	const args: unknown = JSON.parse(decodeURIComponent(match[2]));
+	return { command: match[1], args: Array.isArray(args) ? args : [args] };
+}
+
+function toInvocation(command: vscode.Command): CommandInvocation {
+	return { command: command.command, args: command.arguments ?? [] };
+}
+
+async function runAll(invocations: CommandInvocation[]): Promise<void> {
+	const registered = new Set(await vscode.commands.getCommands(true));
+	for (const { command, args } of invocations) {
+		assert.ok(registered.has(command), `${command} is not registered`);
+		await vscode.commands.executeCommand(command, ...args);
+	}
+}
@@ -0,0 +1,145 @@
+import * as assert from "node:assert";
+import * as vscode from "vscode";
+import { RuleEngine, withoutPlaceholders } from "../rules";
+import { type EditionFinding, EditionMigration } from "../services";
+import { applyEdits } from "./helpers";
+
+const EXTENSION_ID = "JohnK.rust-compass";
+
+suite("Edition 2024 migration", () => {
+	let engine: RuleEngine;
+	let edition = "2021";
+
+	suiteSetup(() => {
+		const extension = vscode.extensions.getExtension(EXTENSION_ID);
+		assert.ok(extension);
+		engine = new RuleEngine(extension.extensionPath);
+		engine.setDependencies(() => ({
+			crates: [],
+			versions: new Map(),
+			features: new Map(),
+			categories: [],
+			edition,
+		}));
+	});
+
+	teardown(() => {
+		edition = "2021";
+	});
+
+	/** Findings in a new document, built the way the scan builds them */
+	async function scan(source: string): Promise<EditionFinding[]> {
+		const document = await vscode.workspace.openTextDocument({
+			language: "rust",
+			content: source,
+		});
+		return engine.findEditionChanges(document, "2024").map((match) => {
+			const fix = engine.resolveFix(document, match);
+			return {
+				match,
+				uri: document.uri,
+				version: document.version,
+				lineText: document.lineAt(match.range.line).text.trim(),
+				fix: fix && withoutPlaceholders(fix),
+			};
+		});
+	}
+
+	test("each rule matches the code the edition changes", async () => {
+		const cases: [string, string, string][] = [
+			[
+				"rpit-capture-2024",
+				"fn ids(&self) -> impl Iterator<Item = u32> {}",
+				"fn ids(&self) -> impl",
+			],
+			["unsafe-extern-2024", 'extern "C" {\n    fn abs(x: i32) -> i32;\n}', 'extern "C" {'],
+			["gen-keyword-2024", "fn main() {\n    let n: u8 = rng.gen();\n}", "gen"],
+			[
+				"tail-expr-drop-order-2024",
+				"fn len(c: &RefCell<Vec<u8>>) -> usize {\n    c.borrow().len()\n}",
+				"c.borrow().len()",
+			],
+			[
+				"if-let-rescope-2024",
+				"fn f() {\n    if let Some(v) = m.lock().unwrap().get(&k) {\n    } else {\n    }\n}",
+				"if let Some(v) = m.lock().unwrap().get(&k) {",
+			],
+			[
+				"macro-expr-fragment-2024",
+				"macro_rules! m {\n    ($e:expr) => { $e };\n}",
+				"$e:expr",
+			],
+			[
+				"unsafe-attributes-2024",
+				'#[no_mangle]\npub extern "C" fn init() {}',
+				"#[no_mangle]",
+			],
+			["unsafe-env-2024", 'fn main() {\n    env::set_var("A", "b");\n}', "env::set_var("],
+		];
+		for (const [ruleId, source, matched] of cases) {
+			const findings = await scan(source);
+			assert.deepStrictEqual(
+				findings.map((f) => [f.match.rule.id, f.match.matchedText]),
+				[[ruleId, matched]],
+				source,
+			);
+		}
+	});
+
+	test("code already written for the new edition is skipped", async () => {
+		const sources = [
+			'unsafe extern "C" {\n    pub safe fn abs(x: i32) -> i32;\n}',
+			"extern crate alloc;",
+			"fn main() {\n    let r#gen = rng.r#gen();\n}",
+			"fn first<'gen>(s: &'gen str) -> &'gen str {\n    s\n}",
+			'fn main() {\n    let s = "gen"; // gen\n}',
+			'#[unsafe(no_mangle)]\npub extern "C" fn init() {}',
+			"macro_rules! m {\n    ($e:expr_2021) => { $e };\n}",
+			'fn main() {\n    unsafe { std::env::set_var("A", "b") };\n}',
+			"fn ids<'a>(s: &'a str) -> impl Fn() + 'a {}",
+			"fn ids(s: &str) -> impl Fn() + use<> {}",
+		];
+		for (const source of sources) {
+			assert.deepStrictEqual(await scan(source), [], source);
+		}
+	});
+
+	test("mechanical fixes rewrite to the new syntax", async () => {
+		const cases: [string, string][] = [
+			['extern "C" {\n}', 'unsafe extern "C" {\n}'],
+			["fn main() {\n    rng.gen();\n}", "fn main() {\n    rng.r#gen();\n}"],
+			['#[export_name = "start"]\nfn s() {}', '#[unsafe(export_name = "start")]\nfn s() {}'],
+			[
+				"macro_rules! m {\n    ($e:expr) => {};\n}",
+				"macro_rules! m {\n    ($e:expr_2021) => {};\n}",
+			],
+		];
+		for (const [source, fixed] of cases) {
+			const [finding] = await scan(source);
+			assert.ok(finding?.fix, source);
+			assert.strictEqual(applyEdits(source, finding.fix.edits), fixed);
+		}
+	});
+
+	test("crates already on the edition have nothing to migrate", async () => {
+		edition = "2024";
+		assert.deepStrictEqual(await scan('extern "C" {\n}\n#[no_mangle]\nfn f() {}'), []);
+	});
+
+	test("createEdit skips documents edited since the scan", async () => {
+		const fresh = await scan("#[no_mangle]\nfn a() {}");
+		const edited = await scan('extern "C" {\n}\nfn main() {\n    rng.gen();\n}');
+		const change = new vscode.WorkspaceEdit();
+		change.insert(edited[0].uri, new vscode.Position(0, 0), "// edited\n");
+		assert.ok(await vscode.workspace.applyEdit(change));
+
+		const migration = new EditionMigration(engine);
+		const { edit, fixes, stale } = await migration.createEdit([...fresh, ...edited]);
+		assert.strictEqual(fixes, 1);
+		assert.strictEqual(stale, 2);
+		assert.deepStrictEqual(
+			edit.entries().map(([uri, edits]) => [uri.toString(), edits.map((e) => e.newText)]),
+			[[fresh[0].uri.toString(), ["#[unsafe(no_mangle)]"]]],
+		);
+	});
+});
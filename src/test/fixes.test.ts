@@ -0,0 +1,153 @@
+import * as assert from "node:assert";
+import {
+	type FixEdit,
+	importEdit,
+	mergeFixes,
+	type ResolvedFix,
+	resolveFix,
+	withoutPlaceholders,
+} from "../rules/fixes";
+import { SyntaxTree } from "../rules/syntax";
+import type { Rule, RuleMatch, RuleSuggestedFix } from "../rules/types";
+import { applyEdits } from "./helpers";
+
+/** Resolve a fix for the first match of `pattern`, the way the rule engine builds matches */
+function fixFor(source: string, pattern: RegExp, fix: RuleSuggestedFix) {
+	const match = new RegExp(pattern.source, "d").exec(source);
+	assert.ok(match?.indices, `${pattern} doesn't match`);
+
+	const captures: NonNullable<RuleMatch["captures"]> = {};
+	match.indices.forEach((span, i) => {
+		if (i > 0 && span) {
+			captures[String(i)] = { start: span[0], end: span[1] };
+		}
+	});
+	for (const [name, span] of Object.entries(match.indices.groups ?? {})) {
+		if (span) {
+			captures[name] = { start: span[0], end: span[1] };
+		}
+	}
+
+	const rule = { id: "test-rule", suggestedFix: fix } as Rule;
+	const start = match.index;
+	return resolveFix(SyntaxTree.parse(source), {
+		rule,
+		range: { start, end: start + match[0].length, line: 0, character: start },
+		matchedText: match[0],
+		captures,
+	});
+}
+
+suite("Fixes", () => {
+	test("numbered and named captures fill in templates", () => {
+		const source = "if items.len() == 0 { return; }";
+		const numbered = fixFor(source, /(\w+)\.len\(\) == 0/, {
+			description: "Use is_empty",
+			replacement: "$1.is_empty()",
+		});
+		assert.strictEqual(
+			applyEdits(source, numbered?.edits ?? []),
+			"if items.is_empty() { return; }",
+		);
+
+		const named = fixFor("let b = a.clone();", /(?<rc>\w+)\.clone\(\)/, {
+			description: "Make the Rc clone explicit",
+			replacement: "Rc::clone(&${rc})$$",
+		});
+		assert.deepStrictEqual(named?.edits, [{ start: 8, end: 17, text: "Rc::clone(&a)$" }]);
+	});
+
+	test("a fix referencing a group that didn't take part isn't offered", () => {
+		const fix = fixFor("x.unwrap()", /(\w+)\.unwrap\(\)(\?)?/, {
+			description: "Keep the question mark",
+			replacement: "$1$2",
+		});
+		assert.strictEqual(fix, undefined);
+	});
+
+	test("edits target the receiver's definition and add imports once", () => {
+		const source = [
+			"use std::fmt;",
+			"",
+			"fn lex(input: &str) {",
+			"    let mut it = input.chars();",
+			"    it.next();",
+			"}",
+		].join("\n");
+		const fix = fixFor(source, /\.next\(\)/, {
+			description: "Make the iterator peekable",
+			edits: [
+				{ target: "receiverDefinition", text: "$target.peekable()" },
+				{ target: "imports", text: "use std::iter::Peekable;" },
+				{ target: "imports", text: "use std::fmt;" },
+			],
+		});
+		assert.strictEqual(
+			applyEdits(source, fix?.edits ?? []),
+			[
+				"use std::fmt;",
+				"use std::iter::Peekable;",
+				"",
+				"fn lex(input: &str) {",
+				"    let mut it = input.chars().peekable();",
+				"    it.next();",
+				"}",
+			].join("\n"),
+		);
+	});
+
+	test("snippets keep tab stops and escape inserted text", () => {
+		const fix = fixFor('let v = "$x".chars().collect();', /\.collect\(\)/, {
+			description: "Name the collection type",
+			snippet: true,
+			replacement: ".collect::<${1:Vec<_>}>() /* $receiver */",
+		});
+		assert.strictEqual(fix?.edits[0].text, '.collect::<${1:Vec<_>}>() /* "\\$x".chars() */');
+		assert.strictEqual(
+			withoutPlaceholders(fix as ResolvedFix).edits[0].text,
+			'.collect::<Vec<_>>() /* "$x".chars() */',
+		);
+	});
+
+	test("mergeFixes drops repeated edits and skips clashing fixes", () => {
+		const fix = (...edits: FixEdit[]): ResolvedFix => ({
+			description: "fix",
+			snippet: false,
+			edits,
+		});
+		const importLine = { start: 0, end: 0, text: "use std::rc::Rc;\n" };
+		const merged = mergeFixes([
+			fix(importLine, { start: 10, end: 15, text: "a" }),
+			fix(importLine, { start: 20, end: 25, text: "b" }),
+			fix({ start: 12, end: 18, text: "clash" }),
+			fix(importLine),
+		]);
+		assert.deepStrictEqual(
+			merged.map((f) => f.edits),
+			[[importLine, { start: 10, end: 15, text: "a" }], [{ start: 20, end: 25, text: "b" }]],
+		);
+	});
+
+	test("imports go after the last top-level use", () => {
+		const source = "use a;\nuse b::{c, d};\n\nfn f() {\n    use e;\n}\n";
+		const edit = importEdit(SyntaxTree.parse(source), "use x;");
+		assert.strictEqual(
+			applyEdits(source, [edit]),
+			"use a;\nuse b::{c, d};\nuse x;\n\nfn f() {\n    use e;\n}\n",
+		);
+	});
+
+	test("without use items, imports go after inner docs and attributes", () => {
+		const source = "//! Crate docs\n#![allow(dead_code)]\n\nfn f() {}\n";
+		const edit = importEdit(SyntaxTree.parse(source), "use x;");
+		assert.strictEqual(
+			applyEdits(source, [edit]),
+			"//! Crate docs\n#![allow(dead_code)]\nuse x;\n\nfn f() {}\n",
+		);
+		assert.deepStrictEqual(importEdit(SyntaxTree.parse("fn f() {}\n"), "use x;"), {
+			start: 0,
+			end: 0,
+			text: "use x;\n",
+		});
+	});
+});
@@ -0,0 +1,18 @@
+import type { FixEdit } from "../rules/fixes";
+
+/**
+ * Apply non-overlapping edits to a source. Edits at the same offset land in the order given.
+ */
+export function applyEdits(source: string, edits: readonly FixEdit[]): string {
+	let text = source;
+	// From the end, so the offsets of the edits still to apply stay valid
+	for (const edit of [...edits].reverse().sort((a, b) => b.start - a.start)) {
+		text = text.slice(0, edit.start) + edit.text + text.slice(edit.end);
+	}
+	return text;
+}
+
+/** Wrap a function body, indented by four spaces */
+export function inFn(signature: string, ...body: string[]): string {
+	return [`fn ${signature} {`, ...body.map((line) => `    ${line}`), "}", ""].join("\n");
+}
@@ -0,0 +1,188 @@
+import * as assert from "node:assert";
+import { findLoopRefactorings } from "../rules/refactors";
+import { SyntaxTree } from "../rules/syntax";
+import { applyEdits, inFn } from "./helpers";
+
+/** Titles, rule ids and rewritten sources of the refactorings offered at the `for` */
+function refactor(source: string): { title: string; ruleIds: string[]; text: string }[] {
+	const refactorings = findLoopRefactorings(SyntaxTree.parse(source), source.indexOf("for "));
+	return refactorings.map((r) => ({
+		title: r.title,
+		ruleIds: r.ruleIds,
+		text: applyEdits(source, r.edits),
+	}));
+}
+
+suite("Loop refactorings", () => {
+	test("a typed total becomes sum, an untyped one fold", () => {
+		const [typed] = refactor(
+			inFn(
+				"f(v: &[u32]) -> u32",
+				"let mut total: u32 = 0;",
+				"for x in v {",
+				"    total += x;",
+				"}",
+				"total",
+			),
+		);
+		assert.strictEqual(typed.title, "Convert loop to .sum()");
+		assert.deepStrictEqual(typed.ruleIds, ["map-filter-fold"]);
+		assert.strictEqual(
+			typed.text,
+			inFn("f(v: &[u32]) -> u32", "let total: u32 = v.into_iter().sum();", "total"),
+		);
+
+		const [untyped] = refactor(
+			inFn(
+				"f(v: &[u32]) -> u32",
+				"let mut total = 0;",
+				"for x in v {",
+				"    total += x * 2;",
+				"}",
+				"total",
+			),
+		);
+		assert.strictEqual(
+			untyped.text,
+			inFn(
+				"f(v: &[u32]) -> u32",
+				"let total = v.into_iter().fold(0, |total, x| total + x * 2);",
+				"total",
+			),
+		);
+	});
+
+	test("operators looser than + are parenthesized in fold", () => {
+		const [fold, elements] = refactor(
+			inFn(
+				"f(v: &[u32]) -> u32",
+				"let mut total = 0;",
+				"for i in 0..v.len() {",
+				"    total += v[i] << 1;",
+				"}",
+				"total",
+			),
+		);
+		assert.strictEqual(
+			fold.text,
+			inFn(
+				"f(v: &[u32]) -> u32",
+				"let total = v.iter().fold(0, |total, item| total + (*item << 1));",
+				"total",
+			),
+		);
+		assert.strictEqual(elements.title, "Loop over the elements instead of indexing");
+		assert.deepStrictEqual(elements.ruleIds, ["for-in-loop"]);
+	});
+
+	test("pushing copies of the elements becomes copied", () => {
+		const [collect] = refactor(
+			inFn(
+				"f(v: &[u32]) -> Vec<u32>",
+				"let mut out = Vec::new();",
+				"for i in 0..v.len() {",
+				"    out.push(v[i]);",
+				"}",
+				"out",
+			),
+		);
+		assert.strictEqual(collect.title, "Convert loop to .collect()");
+		assert.strictEqual(
+			collect.text,
+			inFn(
+				"f(v: &[u32]) -> Vec<u32>",
+				"let out: Vec<_> = v.iter().copied().collect();",
+				"out",
+			),
+		);
+	});
+
+	test("a used index turns into enumerate, also from format strings", () => {
+		const source = inFn(
+			"f(names: &[String]) -> Vec<String>",
+			"let mut out = Vec::new();",
+			"for i in 0..names.len() {",
+			'    out.push(format!("{i}: {}", names[i]));',
+			"}",
+			"out",
+		);
+		const [collect, elements] = refactor(source);
+		assert.deepStrictEqual(collect.ruleIds, ["map-filter-fold", "enumerate"]);
+		assert.strictEqual(
+			collect.text,
+			inFn(
+				"f(names: &[String]) -> Vec<String>",
+				'let out: Vec<_> = names.iter().enumerate().map(|(i, name)| format!("{i}: {}", *name)).collect();',
+				"out",
+			),
+		);
+		assert.strictEqual(elements.title, "Loop with .iter().enumerate() instead of indexing");
+		assert.strictEqual(
+			elements.text,
+			inFn(
+				"f(names: &[String]) -> Vec<String>",
+				"let mut out = Vec::new();",
+				"for (i, name) in names.iter().enumerate() {",
+				'    out.push(format!("{i}: {}", *name));',
+				"}",
+				"out",
+			),
+		);
+	});
+
+	test("the accumulator stays mut when changed after the loop", () => {
+		const [collect] = refactor(
+			inFn(
+				"f(v: &[u32]) -> Vec<u32>",
+				"let mut out = Vec::new();",
+				"for x in v.iter() {",
+				"    out.push(x + 1);",
+				"}",
+				"out.push(0);",
+				"out",
+			),
+		);
+		assert.strictEqual(
+			collect.text,
+			inFn(
+				"f(v: &[u32]) -> Vec<u32>",
+				"let mut out: Vec<_> = v.iter().map(|x| x + 1).collect();",
+				"out.push(0);",
+				"out",
+			),
+		);
+	});
+
+	test("not offered when the body doesn't translate exactly", () => {
+		const refused = [
+			// `break 'outer` could leave from anywhere inside
+			inFn("f(v: &[u32])", "'outer: for i in 0..v.len() {", "    g(v[i]);", "}"),
+			// `v[i]` would read a different element
+			inFn("f(v: &[u32])", "for i in 0..v.len() {", "    let i = 2;", "    g(v[i]);", "}"),
+			// Writes need iter_mut
+			inFn("f(v: &mut [u32])", "for i in 0..v.len() {", "    v[i] = 0;", "}"),
+			// Other statements would have to run in the closure
+			inFn(
+				"f(v: &[u32]) -> Vec<u32>",
+				"let mut out = Vec::new();",
+				"for x in v {",
+				"    log(x);",
+				"    out.push(*x);",
+				"}",
+				"out",
+			),
+			// Early exits
+			inFn(
+				"f(v: &[u32]) -> u32",
+				"let mut total = 0;",
+				"for x in v {",
+				"    total += g(x)?;",
+				"}",
+				"total",
+			),
+		];
+		for (const source of refused) {
+			assert.deepStrictEqual(refactor(source), [], source);
+		}
+	});
+});
@@ -0,0 +1,66 @@
+import * as assert from "node:assert";
+import { maskSource, maskTomlComments, SyntaxTree } from "../rules/syntax";
+
+function mask(source: string, comments = true, attributes = true): string {
+	return maskSource(SyntaxTree.parse(source), { comments, attributes });
+}
+
+suite("Masking", () => {
+	test("nested block comments are masked as a whole", () => {
+		assert.strictEqual(
+			mask("let a = 1; /* outer /* inner */ still comment */ let b = 2;"),
+			"let a = 1; /*                                 */ let b = 2;",
+		);
+	});
+
+	test("raw strings keep their hashes and hide quotes and slashes", () => {
+		assert.strictEqual(
+			mask('let s = r#"has "quotes" and // slashes"#; x.unwrap();'),
+			'let s = r#"                           "#; x.unwrap();',
+		);
+	});
+
+	test("lifetimes are code, char literals are not", () => {
+		assert.strictEqual(
+			mask("fn f<'a>(s: &'a str) -> char { 'a' }"),
+			"fn f<'a>(s: &'a str) -> char { ' ' }",
+		);
+	});
+
+	test("byte strings and byte chars keep their prefix", () => {
+		assert.strictEqual(
+			mask('let b = b"bytes\\n"; let c = b\'x\'; let r = br"raw";'),
+			'let b = b"       "; let c = b\' \'; let r = br"   ";',
+		);
+	});
+
+	test("offsets and line breaks are preserved", () => {
+		const source = '// line\nlet x = "a\nb"; #[derive(Debug)]\nstruct S;\r\n/// doc\nfn g() {}';
+		const masked = mask(source);
+		assert.strictEqual(
+			masked,
+			'//     \nlet x = " \n "; ' + " ".repeat(16) + "\nstruct S;\r\n//     \nfn g() {}",
+		);
+		assert.strictEqual(masked.length, source.length);
+		assert.deepStrictEqual(
+			[...masked.matchAll(/\r?\n/g)].map((m) => m.index),
+			[...source.matchAll(/\r?\n/g)].map((m) => m.index),
+		);
+	});
+
+	test("comments and attributes are only masked when asked", () => {
+		const source = "// keep\n#[derive(Debug)]";
+		assert.strictEqual(mask(source, false, false), source);
+	});
+
+	test("an unterminated string is masked to the end", () => {
+		assert.strictEqual(mask('let t = "unterminated'), 'let t = "            ');
+	});
+
+	test("TOML comments are masked, # in strings is not", () => {
+		assert.strictEqual(
+			maskTomlComments('a = "#1" # note\nb = \'\'\'\n# text\n\'\'\' # end\n'),
+			'a = "#1"       \nb = \'\'\'\n# text\n\'\'\'      \n',
+		);
+	});
+});
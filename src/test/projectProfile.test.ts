@@ -0,0 +1,65 @@
+import * as assert from "node:assert";
+import type { DetectedDependencies } from "../services/cargoAnalyzer";
+import { parseManifest } from "../services/cargoManifest";
+import {
+	buildProjectProfile,
+	describeProfile,
+	findTrait,
+	suggestProjectContext,
+} from "../services/projectProfile";
+
+const NO_DEPENDENCIES: DetectedDependencies = {
+	async: [],
+	web: [],
+	serialization: [],
+	cli: [],
+	parsing: [],
+	error: [],
+	database: [],
+	testing: [],
+	other: [],
+};
+
+suite("Project profile", () => {
+	test("targets come from the manifest and Cargo's default paths", () => {
+		const profile = buildProjectProfile({
+			manifest: parseManifest('[package]\nname = "macros"\n\n[lib]\nproc-macro = true\n'),
+			dependencies: NO_DEPENDENCIES,
+			files: ["src/lib.rs", "build.rs", "benches/throughput.rs"],
+		});
+		const traits = profile.traits.map((t) => `${t.trait} ${t.confidence}`);
+		assert.deepStrictEqual(traits, [
+			"proc-macro 1",
+			"library 1",
+			"build-script 1",
+			"benchmarks 1",
+		]);
+		assert.strictEqual(describeProfile(profile), "My project is a proc-macro crate.");
+	});
+
+	test("dependencies outweigh names, and names match whole words", () => {
+		const profile = buildProjectProfile({
+			manifest: parseManifest('[package]\nname = "anonymous"\n[dependencies]\nclap = "4"\n'),
+			dependencies: { ...NO_DEPENDENCIES, cli: ["clap"], other: ["phenomenal"] },
+			files: ["src/main.rs", "src/lexical_scope.rs"],
+		});
+		assert.strictEqual(findTrait(profile, "parser", 0), undefined);
+		assert.strictEqual(findTrait(profile, "lexer", 0), undefined);
+		assert.deepStrictEqual(suggestProjectContext(profile), {
+			context: "cli",
+			reason: "Depends on clap - looks like a cli project",
+		});
+	});
+
+	test("file names are weak evidence on their own", () => {
+		const profile = buildProjectProfile({
+			manifest: parseManifest('[package]\nname = "calc"\n'),
+			dependencies: NO_DEPENDENCIES,
+			files: ["src/main.rs", "src/lexer.rs", "src/parser/mod.rs"],
+		});
+		assert.strictEqual(findTrait(profile, "parser", 0)?.reason, "Has src/parser/mod.rs");
+		assert.strictEqual(findTrait(profile, "parser"), undefined);
+		assert.strictEqual(suggestProjectContext(profile)?.context, "parser");
+		assert.strictEqual(findTrait(profile, "compiler")?.confidence, 0.6);
+	});
+});
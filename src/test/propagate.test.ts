@@ -0,0 +1,186 @@
+import * as assert from "node:assert";
+import { type ErrorStyle, findCallerEdit, findPropagation } from "../rules/refactors";
+import { SyntaxTree } from "../rules/syntax";
+import { applyEdits } from "./helpers";
+
+/** Source after propagating the call at `needle`, or `undefined` when not offered */
+function propagate(source: string, needle: string, style: ErrorStyle): string | undefined {
+	const propagation = findPropagation(SyntaxTree.parse(source), source.indexOf(needle), style);
+	return propagation && applyEdits(source, propagation.edits);
+}
+
+/** Source after updating the call at `needle` to a function that now returns `Result` */
+function updateCaller(source: string, needle: string, style: ErrorStyle): string | undefined {
+	const edit = findCallerEdit(SyntaxTree.parse(source), source.indexOf(needle), style);
+	return edit && applyEdits(source, [edit]);
+}
+
+suite("Propagate with ?", () => {
+	test("a Result unwrap becomes ? and the function returns Result", () => {
+		const source = [
+			"use std::fs;",
+			"",
+			"fn read_config(path: &str) -> String {",
+			"    fs::read_to_string(path).unwrap()",
+			"}",
+		].join("\n");
+		const propagation = findPropagation(
+			SyntaxTree.parse(source),
+			source.indexOf("unwrap"),
+			"anyhow",
+		);
+		assert.strictEqual(
+			propagation && applyEdits(source, propagation.edits),
+			[
+				"use std::fs;",
+				"",
+				"fn read_config(path: &str) -> anyhow::Result<String> {",
+				"    Ok(fs::read_to_string(path)?)",
+				"}",
+			].join("\n"),
+		);
+		assert.strictEqual(propagation?.changedFunction, source.indexOf("read_config"));
+	});
+
+	test("an Option receiver gets context with anyhow", () => {
+		assert.strictEqual(
+			propagate(
+				"fn first(v: &[u8]) -> u8 {\n    *v.first().unwrap()\n}\n",
+				"unwrap",
+				"anyhow",
+			),
+			[
+				"use anyhow::Context;",
+				"fn first(v: &[u8]) -> anyhow::Result<u8> {",
+				'    Ok(*v.first().context("`v.first()` was None")?)',
+				"}",
+				"",
+			].join("\n"),
+		);
+	});
+
+	test("expect keeps its message with a boxed error", () => {
+		assert.strictEqual(
+			propagate(
+				'fn port(s: &str) -> u16 {\n    s.parse().expect("bad port")\n}\n',
+				"expect",
+				"box",
+			),
+			[
+				"fn port(s: &str) -> Result<u16, Box<dyn std::error::Error>> {",
+				'    Ok(s.parse().map_err(|e| format!("bad port: {e}"))?)',
+				"}",
+				"",
+			].join("\n"),
+		);
+	});
+
+	test("returns inside loops are wrapped in Ok too", () => {
+		const source = [
+			"fn find(xs: &[&str]) -> usize {",
+			"    for x in xs {",
+			"        if !x.is_empty() {",
+			"            return x.parse::<usize>().unwrap();",
+			"        }",
+			"    }",
+			"    0",
+			"}",
+		].join("\n");
+		assert.strictEqual(
+			propagate(source, "unwrap", "anyhow"),
+			[
+				"fn find(xs: &[&str]) -> anyhow::Result<usize> {",
+				"    for x in xs {",
+				"        if !x.is_empty() {",
+				"            return Ok(x.parse::<usize>()?);",
+				"        }",
+				"    }",
+				"    Ok(0)",
+				"}",
+			].join("\n"),
+		);
+	});
+
+	test("main gets Ok(()) and no caller updates", () => {
+		const source = [
+			"fn main() {",
+			'    let n: u8 = "1".parse().unwrap();',
+			'    println!("{n}");',
+			"}",
+			"",
+		].join("\n");
+		const propagation = findPropagation(
+			SyntaxTree.parse(source),
+			source.indexOf("unwrap"),
+			"box",
+		);
+		assert.strictEqual(
+			propagation && applyEdits(source, propagation.edits),
+			[
+				"fn main() -> Result<(), Box<dyn std::error::Error>> {",
+				'    let n: u8 = "1".parse()?;',
+				'    println!("{n}");',
+				"    Ok(())",
+				"}",
+				"",
+			].join("\n"),
+		);
+		assert.strictEqual(propagation?.changedFunction, undefined);
+	});
+
+	test("not offered inside closures or trait impls", () => {
+		const closure = [
+			"fn all(xs: &[&str]) -> Vec<u8> {",
+			"    xs.iter().map(|x| x.parse().unwrap()).collect()",
+			"}",
+		].join("\n");
+		assert.strictEqual(propagate(closure, "unwrap", "anyhow"), undefined);
+
+		const impl = [
+			"impl FromStr for Port {",
+			"    fn from_str(s: &str) -> Self {",
+			"        Port(s.parse().unwrap())",
+			"    }",
+			"}",
+		].join("\n");
+		assert.strictEqual(propagate(impl, "unwrap", "anyhow"), undefined);
+	});
+
+	test("callers unwrap unless they already return a matching Result", () => {
+		assert.strictEqual(
+			updateCaller(
+				'fn config_len() -> usize {\n    read_config("app.toml").len()\n}\n',
+				"read_config",
+				"anyhow",
+			),
+			'fn config_len() -> usize {\n    read_config("app.toml").unwrap().len()\n}\n',
+		);
+		assert.strictEqual(
+			updateCaller(
+				'fn load() -> anyhow::Result<usize> {\n    Ok(read_config("app.toml").len())\n}\n',
+				"read_config",
+				"anyhow",
+			),
+			'fn load() -> anyhow::Result<usize> {\n    Ok(read_config("app.toml")?.len())\n}\n',
+		);
+		// A boxed error doesn't take the anyhow error without a conversion
+		assert.strictEqual(
+			updateCaller(
+				"fn load() -> Result<u8, Box<dyn Error>> {\n    Ok(read_config(p).len())\n}\n",
+				"read_config",
+				"anyhow",
+			),
+			"fn load() -> Result<u8, Box<dyn Error>> {\n    Ok(read_config(p).unwrap().len())\n}\n",
+		);
+		assert.strictEqual(
+			updateCaller(
+				'fn load() -> anyhow::Result<()> {\n    let f = || read_config("a");\n}\n',
+				"read_config",
+				"anyhow",
+			),
+			'fn load() -> anyhow::Result<()> {\n    let f = || read_config("a").unwrap();\n}\n',
+		);
+		const definition = "fn read_config(path: &str) {}\n";
+		assert.strictEqual(updateCaller(definition, "read_config", "anyhow"), undefined);
+	});
+});
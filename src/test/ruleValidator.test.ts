@@ -0,0 +1,38 @@
+import * as assert from "node:assert";
+import { validateRule } from "../rules/ruleValidator";
+import type { Rule } from "../rules/types";
+
+/** A complete rule with the given pattern */
+function ruleWith(pattern: string): Partial<Rule> {
+	return {
+		id: "test-rule",
+		pattern,
+		title: "Title",
+		rustTerm: "Term",
+		explanation: "Explanation",
+		example: "let x = opt.unwrap();",
+		deepExplanation: "More",
+		contexts: ["general"],
+		confidence: 0.5,
+	};
+}
+
+suite("Rule validation", () => {
+	test("patterns that can match an empty string are rejected", () => {
+		for (const pattern of ["\\b", "x*", "(?=unwrap)", "(?:)"]) {
+			const { errors, compiledPattern } = validateRule(ruleWith(pattern));
+			assert.deepStrictEqual(
+				errors,
+				[`"pattern" can match an empty string, so it would mark nothing`],
+				pattern,
+			);
+			assert.ok(compiledPattern, pattern);
+		}
+	});
+
+	test("patterns that always consume text load", () => {
+		for (const pattern of ["\\.unwrap\\(\\)", "\\bx+", "(?<=\\.)unwrap"]) {
+			assert.deepStrictEqual(validateRule(ruleWith(pattern)).errors, [], pattern);
+		}
+	});
+});
@@ -0,0 +1,102 @@
+import * as assert from "node:assert";
+import { getScope, matchesScope, SyntaxTree } from "../rules/syntax";
+
+/** Scope at the last `?` in the source */
+function scopeAtQuestionMark(source: string) {
+	return getScope(SyntaxTree.parse(source), source.lastIndexOf("?"));
+}
+
+/** Scope at the `HERE` marker in the source */
+function scopeAtMarker(source: string) {
+	return getScope(SyntaxTree.parse(source), source.indexOf("HERE"));
+}
+
+suite("Scope", () => {
+	test("fnReturns is unknown inside closures and async blocks", () => {
+		for (const source of [
+			"fn main() { let c = || { f()?; Ok(()) }; }",
+			"fn main() { let c = |x| f(x)?; }",
+			"fn main() { let t = async move { f().await?; }; }",
+			"fn main() { items.for_each(|i| { if i.ok { h(i)?; } }); }",
+		]) {
+			const scope = scopeAtQuestionMark(source);
+			assert.strictEqual(scope.inClosure, true, source);
+			assert.strictEqual(matchesScope(scope, { fnReturns: "()" }), false, source);
+		}
+	});
+
+	test("closures that ended before the offset don't count", () => {
+		for (const source of [
+			"fn main() { v.iter().map(|x| x.parse::<u8>()).count(); g()?; }",
+			"fn main() { match x { A | B => h()?, _ => {} } }",
+			"fn main() { if a || b { h()?; } }",
+		]) {
+			const scope = scopeAtQuestionMark(source);
+			assert.strictEqual(scope.inClosure, false, source);
+			assert.strictEqual(matchesScope(scope, { fnReturns: "()" }), true, source);
+		}
+	});
+
+	test("loops count within the innermost function only", () => {
+		const inner = scopeAtMarker("fn f() { for x in xs { while a { HERE; } } }");
+		assert.strictEqual(inner.inLoop, true);
+		assert.strictEqual(scopeAtMarker("fn f() { for x in xs {} HERE; }").inLoop, false);
+
+		const nested = scopeAtMarker("fn f() { for x in xs { fn inner() { HERE; } } }");
+		assert.strictEqual(nested.function?.name, "inner");
+		assert.strictEqual(nested.inLoop, false);
+	});
+
+	test("test attributes apply to everything under them", () => {
+		const helper = scopeAtMarker("#[cfg(test)]\nmod tests { fn helper() { HERE } }");
+		assert.strictEqual(helper.inTest, true);
+		assert.strictEqual(scopeAtMarker("#[test]\nfn parses() { HERE }").inTest, true);
+		assert.strictEqual(scopeAtMarker("#[cfg(not(test))]\nfn f() { HERE }").inTest, false);
+
+		const tokio = scopeAtMarker("#[tokio::test]\nasync fn t() { HERE }");
+		assert.strictEqual(tokio.inTest, true);
+		assert.strictEqual(tokio.inAsyncFn, true);
+	});
+
+	test("fnReturns compares the outer return type", () => {
+		const cases: [string, string | undefined][] = [
+			["fn f() -> io::Result<()> { HERE }", "Result"],
+			["fn f<'a>() -> &'a str { HERE }", "str"],
+			["fn f() -> impl Iterator<Item = u8> { HERE }", "Iterator"],
+			["fn f() -> (u8, u8) { HERE }", "tuple"],
+			["fn f() { HERE }", "()"],
+			["HERE", undefined],
+		];
+		for (const [source, returnType] of cases) {
+			assert.strictEqual(scopeAtMarker(source).returnType, returnType, source);
+		}
+
+		const scope = scopeAtMarker("fn f() -> Option<u8> { HERE }");
+		assert.strictEqual(matchesScope(scope, { fnReturns: ["Result", "Option"] }), true);
+		assert.strictEqual(matchesScope(scope, { fnReturns: "()" }), false);
+	});
+
+	test("inImplOf matches the last path segment or the full path", () => {
+		const scope = scopeAtMarker(
+			"impl fmt::Display for X { fn fmt(&self) -> fmt::Result { HERE } }",
+		);
+		assert.strictEqual(scope.implTrait, "fmt::Display");
+		assert.strictEqual(matchesScope(scope, { inImplOf: "Display" }), true);
+		assert.strictEqual(matchesScope(scope, { inImplOf: "fmt::Display" }), true);
+		assert.strictEqual(matchesScope(scope, { inImplOf: ["Debug", "Iterator"] }), false);
+
+		const inherent = scopeAtMarker("impl X { fn f() { HERE } }");
+		assert.strictEqual(matchesScope(inherent, { inImplOf: "Display" }), false);
+	});
+
+	test("every predicate has to hold", () => {
+		const scope = scopeAtMarker("#[test]\nfn t() -> Result<(), E> { loop { HERE } }");
+		assert.strictEqual(matchesScope(scope, {}), true);
+		assert.strictEqual(
+			matchesScope(scope, { inLoop: true, inTest: true, fnReturns: "Result" }),
+			true,
+		);
+		assert.strictEqual(matchesScope(scope, { inLoop: true, inTest: false }), false);
+		assert.strictEqual(matchesScope(scope, { inAsyncFn: true }), false);
+	});
+});
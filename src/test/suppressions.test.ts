@@ -0,0 +1,69 @@
+import * as assert from "node:assert";
+import { Suppressions, tokenize } from "../rules/syntax";
+
+function suppressionsFor(source: string): Suppressions {
+	return Suppressions.fromTokens(source, tokenize(source));
+}
+
+suite("Suppressions", () => {
+	test("compass-ignore silences the line it is on", () => {
+		const suppressions = suppressionsFor(
+			"let a = x.unwrap(); // compass-ignore unwrap-usage\nlet b = y.unwrap();\n",
+		);
+		assert.strictEqual(suppressions.isSuppressed("unwrap-usage", 0), true);
+		assert.strictEqual(suppressions.isSuppressed("unwrap-usage", 1), false);
+		assert.strictEqual(suppressions.isSuppressed("excessive-clone", 0), false);
+	});
+
+	test("compass-ignore-next-line skips blank lines and stacked comments", () => {
+		const suppressions = suppressionsFor(
+			[
+				"// compass-ignore-next-line unwrap-usage, excessive-clone -- checked above",
+				"",
+				"// compass-ignore-next-line",
+				"let a = x.unwrap().clone();",
+				"let b = y.unwrap();",
+			].join("\n"),
+		);
+		for (const id of ["unwrap-usage", "excessive-clone", "any-other-rule"]) {
+			assert.strictEqual(suppressions.isSuppressed(id, 3), true, id);
+		}
+		assert.strictEqual(suppressions.isSuppressed("unwrap-usage", 0), false);
+		assert.strictEqual(suppressions.isSuppressed("unwrap-usage", 4), false);
+	});
+
+	test("the reason after -- is not a rule id", () => {
+		const suppressions = suppressionsFor("x.unwrap(); // compass-ignore -- tests only\n");
+		assert.strictEqual(suppressions.isSuppressed("tests", 0), true);
+		assert.strictEqual(suppressions.isSuppressed("unwrap-usage", 0), true);
+
+		const listed = suppressionsFor("x.unwrap(); // compass-ignore unwrap-usage -- only\n");
+		assert.strictEqual(listed.isSuppressed("only", 0), false);
+	});
+
+	test("compass-disable turns rules off for the whole file", () => {
+		const suppressions = suppressionsFor(
+			"//! compass-disable excessive-clone\nfn main() {}\nlet a = b.clone();\n",
+		);
+		assert.strictEqual(suppressions.isDisabled("excessive-clone"), true);
+		assert.strictEqual(suppressions.isSuppressed("excessive-clone", 2), true);
+		assert.strictEqual(suppressions.isDisabled("unwrap-usage"), false);
+
+		const everything = suppressionsFor("// compass-disable\n");
+		assert.strictEqual(everything.isDisabled("unwrap-usage"), true);
+	});
+
+	test("directives only count in line comments", () => {
+		const suppressions = suppressionsFor(
+			[
+				'let s = "// compass-ignore unwrap-usage"; x.unwrap();',
+				"/* compass-disable */",
+				"// not compass-ignore unwrap-usage",
+				"x.unwrap();",
+			].join("\n"),
+		);
+		assert.strictEqual(suppressions.isSuppressed("unwrap-usage", 0), false);
+		assert.strictEqual(suppressions.isDisabled("unwrap-usage"), false);
+		assert.strictEqual(suppressions.isSuppressed("unwrap-usage", 2), false);
+	});
+});
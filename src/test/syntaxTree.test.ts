@@ -0,0 +1,158 @@
+import * as assert from "node:assert";
+import { type SyntaxNode, SyntaxTree } from "../rules/syntax";
+
+/** Header text of each node, e.g. `.map(|v| v.len())` or `fn parse(..) {` */
+function heads(tree: SyntaxTree, nodes: SyntaxNode[] = tree.nodes): string[] {
+	return nodes.map((node) => tree.text.slice(node.start, node.headEnd));
+}
+
+function textOf(tree: SyntaxTree, span: { start: number; end: number } | undefined): string {
+	return span ? tree.text.slice(span.start, span.end) : "";
+}
+
+suite("Syntax tree", () => {
+	test("nested generics ending in >> stay balanced", () => {
+		const tree = SyntaxTree.parse(
+			"fn parse<T: Into<Vec<u8>>>(input: Vec<Vec<T>>) -> Result<Vec<Vec<u8>>, String> {\n" +
+				"    let shifted = input.len() >> 2;\n" +
+				"    Ok(input.into_iter().map(|v| v.into()).collect())\n" +
+				"}\n" +
+				"fn next() {}\n",
+		);
+		const [parse, next] = tree.find({ kind: "function_item" });
+		assert.strictEqual(parse.name, "parse");
+		assert.strictEqual(parse.returnType, "Result<Vec<Vec<u8>>, String>");
+		assert.strictEqual(textOf(tree, parse.parameters), "input: Vec<Vec<T>>");
+		assert.strictEqual(next.name, "next");
+		assert.strictEqual(next.parent, tree.root);
+		assert.deepStrictEqual(
+			tree.find({ kind: "method_call" }).map((n) => n.name),
+			["len", "into_iter", "map", "into", "collect"],
+		);
+	});
+
+	test("closures nest inside the calls that take them", () => {
+		const tree = SyntaxTree.parse(
+			"let total = rows.iter()\n" +
+				"    .map(|row| row.iter().filter(|c| c.is_some()).count())\n" +
+				"    .sum::<usize>();",
+		);
+		const [map] = tree.find({ kind: "method_call", name: "map" });
+		assert.strictEqual(map.argumentCount, 1);
+		assert.deepStrictEqual(heads(tree, map.children), [
+			".iter()",
+			".filter(|c| c.is_some())",
+			".count()",
+		]);
+
+		const [filter] = tree.find({ kind: "method_call", name: "filter" });
+		assert.strictEqual(textOf(tree, filter.receiver), "row.iter()");
+		assert.strictEqual(filter.children[0].name, "is_some");
+
+		const [sum] = tree.find({ kind: "method_call", name: "sum" });
+		assert.strictEqual(sum.parent, tree.root);
+		assert.ok(textOf(tree, sum.receiver).startsWith("rows.iter()\n    .map("));
+	});
+
+	test("trait impls with where clauses", () => {
+		const tree = SyntaxTree.parse(
+			"impl<T: Clone> fmt::Display for Wrapper<T>\n" +
+				"where\n" +
+				"    T: fmt::Debug,\n" +
+				"{\n" +
+				"    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {\n" +
+				'        write!(f, "{:?}", self.0)\n' +
+				"    }\n" +
+				"}\n" +
+				"impl<T> Stack<T> {\n" +
+				"    fn push(&mut self, x: T) { self.items.push(x); }\n" +
+				"}\n",
+		);
+		const [display, stack] = tree.find({ kind: "impl_item" });
+		assert.strictEqual(display.name, "Display");
+		assert.strictEqual(display.traitPath, "fmt::Display");
+		assert.strictEqual(display.selfType, "Wrapper<T>");
+		assert.strictEqual(display.children[0].name, "fmt");
+		assert.strictEqual(display.children[0].returnType, "fmt::Result");
+
+		assert.strictEqual(stack.name, undefined);
+		assert.strictEqual(stack.traitPath, undefined);
+		assert.strictEqual(stack.selfType, "Stack<T>");
+		assert.deepStrictEqual(heads(tree, stack.children), ["fn push(&mut self, x: T) {"]);
+	});
+
+	test("where clauses end the return type", () => {
+		const tree = SyntaxTree.parse(
+			"fn largest<T>(items: &[T]) -> &T\nwhere\n    T: PartialOrd,\n{\n    &items[0]\n}\n",
+		);
+		const [largest] = tree.find({ kind: "function_item" });
+		assert.strictEqual(largest.returnType, "&T");
+		assert.strictEqual(textOf(tree, largest.body), "{\n    &items[0]\n}");
+	});
+
+	test("trait declarations without a body end at the semicolon", () => {
+		const tree = SyntaxTree.parse(
+			"trait Shape {\n" +
+				"    fn area(&self) -> f64;\n" +
+				"    fn name(&self) -> String {\n" +
+				'        String::from("shape")\n' +
+				"    }\n" +
+				"}\n",
+		);
+		const [area, name] = tree.find({ kind: "function_item" });
+		assert.strictEqual(tree.text.slice(area.start, area.end), "fn area(&self) -> f64;");
+		assert.strictEqual(area.body, undefined);
+		assert.strictEqual(name.returnType, "String");
+		assert.strictEqual(name.children[0].kind, "call_expression");
+		assert.deepStrictEqual(heads(tree, name.children), ["String::from("]);
+	});
+
+	test("macros with any delimiter count their arguments", () => {
+		const tree = SyntaxTree.parse(
+			"fn main() {\n" +
+				"    let v = vec![1, 2, 3];\n" +
+				'    println!("{}", v.len());\n' +
+				"    thread_local! { static N: u8 = 0; }\n" +
+				"}\n",
+		);
+		const macros = tree.find({ kind: "macro_invocation" });
+		assert.deepStrictEqual(
+			macros.map((m) => `${m.name} ${m.argumentCount}`),
+			["vec 3", "println 2", "thread_local 1"],
+		);
+		const [twoArguments] = tree.find({ kind: "macro_invocation", argumentCount: 2 });
+		assert.strictEqual(twoArguments.name, "println");
+		assert.strictEqual(tree.find({ kind: "method_call", name: "len" })[0].parent, macros[1]);
+	});
+
+	test("find filters by name, argument count and scrutinee", () => {
+		const tree = SyntaxTree.parse(
+			"fn f(x: Option<u8>, p: Point) {\n" +
+				"    match x { Some(_) => {}, None => {} }\n" +
+				"    match p.x { 0 => {}, _ => {} }\n" +
+				"    match parse(x) { _ => {} }\n" +
+				"    let a = x.unwrap_or(0).max(1);\n" +
+				"}\n",
+		);
+		assert.deepStrictEqual(
+			tree.find({ kind: "match_expression" }).map((m) => m.scrutinee),
+			["identifier", "field", "call"],
+		);
+		assert.strictEqual(tree.find({ kind: "match_expression", scrutinee: "field" }).length, 1);
+		assert.deepStrictEqual(
+			tree.find({ kind: "method_call", name: ["unwrap_or", "max"] }).map((n) => n.name),
+			["unwrap_or", "max"],
+		);
+		assert.strictEqual(tree.find({ kind: "method_call", argumentCount: 2 }).length, 0);
+	});
+
+	test("nodeAt returns the innermost node", () => {
+		const source = "mod util {\n    fn f() {\n        for x in xs { x.len(); }\n    }\n}\n";
+		const tree = SyntaxTree.parse(source);
+		assert.strictEqual(tree.nodeAt(source.indexOf("len")).name, "len");
+		assert.strictEqual(tree.nodeAt(source.indexOf("x.len")).kind, "for_expression");
+		assert.strictEqual(tree.nodeAt(source.indexOf("fn f")).kind, "function_item");
+		assert.strictEqual(tree.nodeAt(source.indexOf("util")).kind, "mod_item");
+		assert.strictEqual(tree.nodeAt(source.length - 1), tree.root);
+	});
+});
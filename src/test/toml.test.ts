@@ -0,0 +1,53 @@
+import * as assert from "node:assert";
+import { parseManifest } from "../services/cargoManifest";
+import { parseToml } from "../services/toml";
+
+suite("TOML", () => {
+	test("keys named like Object.prototype members are ordinary keys", () => {
+		const { root, errors } = parseToml(
+			[
+				"constructor = 1",
+				"toString = 2",
+				"__proto__ = 3",
+				"[hasOwnProperty]",
+				"valueOf.x = true",
+				"[[__proto__s]]",
+			].join("\n"),
+		);
+		assert.deepStrictEqual(errors, []);
+		assert.deepStrictEqual(Object.keys(root), [
+			"constructor",
+			"toString",
+			"__proto__",
+			"hasOwnProperty",
+			"__proto__s",
+		]);
+		assert.strictEqual(root.__proto__, 3);
+		assert.strictEqual(Object.getPrototypeOf(root), null);
+		assert.strictEqual(JSON.stringify(root.hasOwnProperty), '{"valueOf":{"x":true}}');
+	});
+
+	test("duplicate keys are still reported", () => {
+		const { root, errors } = parseToml("a = 1\na = 2\n[t]\nb = 1\n[t]\n");
+		assert.strictEqual(root.a, 1);
+		assert.deepStrictEqual(errors.map((e) => e.line), [1, 4]);
+	});
+
+	test("dependencies and features named after prototype members are kept", () => {
+		const manifest = parseManifest(
+			[
+				"[dependencies]",
+				'constructor = "1"',
+				'toString = { version = "0.1", features = ["__proto__"] }',
+			].join("\n"),
+		);
+		assert.deepStrictEqual(manifest.errors, []);
+		assert.deepStrictEqual(
+			manifest.dependencies.map((d) => [d.name, d.features]),
+			[
+				["constructor", []],
+				["toString", ["__proto__"]],
+			],
+		);
+	});
+});
@@ -0,0 +1,384 @@
+import * as vscode from "vscode";
+import type { Rule } from "../rules";
+import type { EditionFinding, EditionMigration, EditionMigrationReport } from "../services";
+
+/**
+ * Editor tab listing what changes when the workspace's crates move to a new edition
+ */
+export class EditionReportPanel {
+	public static currentPanel: EditionReportPanel | undefined;
+	private static readonly viewType = "rustCompass.editionReport";
+
+	private readonly _panel: vscode.WebviewPanel;
+	private _disposables: vscode.Disposable[] = [];
+	// Findings in the order the page numbers them
+	private _findings: EditionFinding[] = [];
+
+	private constructor(
+		panel: vscode.WebviewPanel,
+		private readonly _migration: EditionMigration,
+		private _report: EditionMigrationReport,
+	) {
+		this._panel = panel;
+
+		this._panel.webview.onDidReceiveMessage(
+			(message) => this._handleMessage(message),
+			null,
+			this._disposables,
+		);
+		this._panel.onDidDispose(() => this.dispose(), null, this._disposables);
+	}
+
+	/**
+	 * Scan the workspace for code that changes in `edition` and show the report
+	 */
+	public static async show(migration: EditionMigration, edition: string) {
+		const report = await EditionReportPanel._scan(migration, edition);
+		if (!report) {
+			return;
+		}
+		if (report.crates.length === 0) {
+			vscode.window.showInformationMessage(
+				report.upToDate.length > 0
+					? `All crates are already on edition ${edition}.`
+					: "No Cargo.toml found in the workspace.",
+			);
+			return;
+		}
+
+		const column = vscode.ViewColumn.Active;
+		if (EditionReportPanel.currentPanel) {
+			EditionReportPanel.currentPanel._panel.reveal(column);
+			EditionReportPanel.currentPanel._update(report);
+			return;
+		}
+
+		const panel = vscode.window.createWebviewPanel(
+			EditionReportPanel.viewType,
+			`🦀 Edition ${edition} Migration`,
+			column,
+			{ enableScripts: true, retainContextWhenHidden: true },
+		);
+		EditionReportPanel.currentPanel = new EditionReportPanel(panel, migration, report);
+		EditionReportPanel.currentPanel._update(report);
+	}
+
+	private static _scan(
+		migration: EditionMigration,
+		edition: string,
+	): Thenable<EditionMigrationReport | undefined> {
+		return vscode.window.withProgress(
+			{
+				location: vscode.ProgressLocation.Notification,
+				title: `Rust Compass: Checking for edition ${edition} changes…`,
+				cancellable: true,
+			},
+			async (progress, token) => {
+				const report = await migration.scan(edition, progress, token);
+				return token.isCancellationRequested ? undefined : report;
+			},
+		);
+	}
+
+	private async _handleMessage(message: { command: string; index?: number; url?: string }) {
+		const finding = message.index !== undefined ? this._findings[message.index] : undefined;
+		switch (message.command) {
+			case "open":
+				if (finding) {
+					const position = new vscode.Position(
+						finding.match.range.line,
+						finding.match.range.character,
+					);
+					await vscode.window.showTextDocument(finding.uri, {
+						selection: new vscode.Range(position, position),
+						viewColumn: vscode.ViewColumn.Beside,
+					});
+				}
+				break;
+			case "fix":
+				if (finding) {
+					await this._applyFixes([finding], false);
+				}
+				break;
+			case "fixAll":
+				await this._applyFixes(this._findings, true);
+				break;
+			case "rescan":
+				await this._rescan();
+				break;
+			case "openExternal":
+				if (message.url) {
+					vscode.env.openExternal(vscode.Uri.parse(message.url));
+				}
+				break;
+		}
+	}
+
+	/**
+	 * Apply the fixes of some findings, then scan again so the report reflects them.
+	 * Fixing everything at once goes through the refactor preview.
+	 */
+	private async _applyFixes(findings: EditionFinding[], preview: boolean) {
+		const { edit, fixes, stale } = await this._migration.createEdit(findings, preview);
+		if (fixes > 0) {
+			await vscode.workspace.applyEdit(edit);
+		}
+		if (stale > 0) {
+			vscode.window.showWarningMessage(
+				`Skipped ${stale} fix(es) in files edited since the scan; re-scan and try again.`,
+			);
+		}
+		await this._rescan();
+	}
+
+	private async _rescan() {
+		const report = await EditionReportPanel._scan(this._migration, this._report.edition);
+		if (report) {
+			this._update(report);
+		}
+	}
+
+	private _update(report: EditionMigrationReport) {
+		this._report = report;
+		this._findings = report.crates.flatMap((c) => c.findings);
+		this._panel.webview.html = this._getHtml(report);
+	}
+
+	private _getHtml(report: EditionMigrationReport): string {
+		const fixable = this._findings.filter((f) => f.fix).length;
+		const crates = report.crates
+			.map((migration) => {
+				const byRule = new Map<string, number[]>();
+				for (const finding of migration.findings) {
+					const index = this._findings.indexOf(finding);
+					const id = finding.match.rule.id;
+					byRule.set(id, [...(byRule.get(id) ?? []), index]);
+				}
+				const rules = [...byRule.values()].map((indices) =>
+					this._renderRule(this._findings[indices[0]].match.rule, indices),
+				);
+				return `
+                <section class="crate">
+                    <h2>${this._escapeHtml(migration.crate.name)}
+                        <span class="edition">edition ${migration.crate.edition} → ${report.edition}</span>
+                    </h2>
+                    ${rules.length > 0 ? rules.join("") : `<p class="clean">Nothing found that changes in edition ${report.edition}.</p>`}
+                </section>`;
+			})
+			.join("");
+		const upToDate =
+			report.upToDate.length > 0
+				? `<p class="up-to-date">Already on edition ${report.edition}: ${report.upToDate.map((n) => this._escapeHtml(n)).join(", ")}</p>`
+				: "";
+
+		return `<!DOCTYPE html>
+        <html lang="en">
+        <head>
+            <meta charset="UTF-8">
+            <meta name="viewport" content="width=device-width, initial-scale=1.0">
+            <title>Edition ${report.edition} Migration</title>
+            ${this._getStyles()}
+        </head>
+        <body>
+            <header>
+                <h1>Edition ${report.edition} Migration</h1>
+                <p class="summary">${this._findings.length} place(s) to review in ${report.crates.length} crate(s), ${fixable} with a mechanical fix</p>
+                <div class="actions">
+                    ${fixable > 0 ? `<button data-command="fixAll">Apply ${fixable} fix(es)…</button>` : ""}
+                    <button class="secondary" data-command="rescan">Re-scan</button>
+                </div>
+            </header>
+            ${crates}
+            ${upToDate}
+            <p class="note">
+                These come from pattern matching, so review each one. When they're handled, set
+                <code>edition = "${report.edition}"</code> in Cargo.toml; <code>cargo fix --edition</code>
+                and a build catch what patterns can't see.
+            </p>
+
+            <script>
+                const vscode = acquireVsCodeApi();
+
+                document.querySelectorAll('[data-command]').forEach(el => {
+                    el.addEventListener('click', (e) => {
+                        e.preventDefault();
+                        const index = el.dataset.index === undefined ? undefined : Number(el.dataset.index);
+                        vscode.postMessage({ command: el.dataset.command, index });
+                    });
+                });
+
+                document.querySelectorAll('.external-link').forEach(link => {
+                    link.addEventListener('click', (e) => {
+                        e.preventDefault();
+                        vscode.postMessage({ command: 'openExternal', url: e.target.closest('a').href });
+                    });
+                });
+            </script>
+        </body>
+        </html>`;
+	}
+
+	private _renderRule(rule: Rule, indices: number[]): string {
+		const locations = indices
+			.map((index) => {
+				const finding = this._findings[index];
+				const file = vscode.workspace.asRelativePath(finding.uri);
+				const line = finding.match.range.line + 1;
+				return `
+                <li>
+                    <a href="#" data-command="open" data-index="${index}">${this._escapeHtml(file)}:${line}</a>
+                    <code>${this._escapeHtml(finding.lineText)}</code>
+                    ${finding.fix ? `<button class="small" data-command="fix" data-index="${index}" title="${this._escapeHtml(finding.fix.description)}">Fix</button>` : ""}
+                </li>`;
+			})
+			.join("");
+		return `
+            <div class="rule">
+                <h3>${this._escapeHtml(rule.title)} <span class="count">${indices.length}</span></h3>
+                <p>${this._escapeHtml(rule.explanation)}</p>
+                ${rule.officialDoc ? `<a class="external-link" href="${this._escapeHtml(rule.officialDoc)}">Edition guide →</a>` : ""}
+                <ul class="findings">${locations}</ul>
+            </div>`;
+	}
+
+	private _escapeHtml(text: string): string {
+		return text
+			.replace(/&/g, "&amp;")
+			.replace(/</g, "&lt;")
+			.replace(/>/g, "&gt;")
+			.replace(/"/g, "&quot;")
+			.replace(/'/g, "&#039;");
+	}
+
+	private _getStyles(): string {
+		return `<style>
+            body {
+                font-family: var(--vscode-font-family, -apple-system, BlinkMacSystemFont, sans-serif);
+                font-size: 14px;
+                line-height: 1.6;
+                color: var(--vscode-editor-foreground);
+                background: var(--vscode-editor-background);
+                padding: 24px;
+                max-width: 900px;
+            }
+
+            header {
+                margin-bottom: 24px;
+                padding-bottom: 16px;
+                border-bottom: 1px solid var(--vscode-widget-border);
+            }
+
+            h1 {
+                font-size: 20px;
+                font-weight: 600;
+            }
+
+            h2 {
+                font-size: 16px;
+                margin: 24px 0 8px;
+            }
+
+            h3 {
+                font-size: 14px;
+                margin-bottom: 4px;
+            }
+
+            .summary, .edition, .up-to-date, .clean, .note {
+                color: var(--vscode-descriptionForeground);
+            }
+
+            .edition {
+                font-size: 12px;
+                font-weight: normal;
+                margin-left: 8px;
+            }
+
+            .actions {
+                display: flex;
+                gap: 8px;
+                margin-top: 12px;
+            }
+
+            button {
+                background: var(--vscode-button-background);
+                color: var(--vscode-button-foreground);
+                border: none;
+                padding: 4px 12px;
+                cursor: pointer;
+            }
+
+            button:hover {
+                background: var(--vscode-button-hoverBackground);
+            }
+
+            button.secondary {
+                background: var(--vscode-button-secondaryBackground);
+                color: var(--vscode-button-secondaryForeground);
+            }
+
+            button.small {
+                padding: 0 8px;
+                font-size: 12px;
+            }
+
+            .rule {
+                margin: 12px 0 20px;
+                padding: 12px 16px;
+                border-left: 3px solid var(--vscode-textLink-foreground);
+                background: var(--vscode-textBlockQuote-background);
+            }
+
+            .count {
+                font-size: 12px;
+                font-weight: normal;
+                padding: 0 6px;
+                border-radius: 8px;
+                background: var(--vscode-badge-background);
+                color: var(--vscode-badge-foreground);
+            }
+
+            a {
+                color: var(--vscode-textLink-foreground);
+            }
+
+            .findings {
+                list-style: none;
+                padding: 0;
+                margin-top: 8px;
+            }
+
+            .findings li {
+                display: flex;
+                gap: 12px;
+                align-items: baseline;
+                padding: 2px 0;
+            }
+
+            code {
+                font-family: var(--vscode-editor-font-family, monospace);
+                font-size: 12px;
+                overflow: hidden;
+                text-overflow: ellipsis;
+                white-space: nowrap;
+            }
+
+            .note {
+                margin-top: 32px;
+                font-size: 12px;
+            }
+        </style>`;
+	}
+
+	public dispose() {
+		EditionReportPanel.currentPanel = undefined;
+
+		this._panel.dispose();
+
+		while (this._disposables.length) {
+			const disposable = this._disposables.pop();
+			if (disposable) {
+				disposable.dispose();
+			}
+		}
+	}
+}
@@ -1 +1,2 @@
+export { EditionReportPanel } from "./editionReport";
 export { LearnPanel } from "./learnPanel";
@@ -1,6 +1,7 @@
 import * as vscode from "vscode";
-import type { Rule } from "../rules";
+import { type Rule, satisfiesVersion } from "../rules";
 import {
+	CargoAnalyzerService,
 	explanationSimplifier,
 	type FetchedDocContent,
 	RustDocsFetcher,
@@ -19,6 +20,9 @@ export class LearnPanel {
 	private readonly _panel: vscode.WebviewPanel;
 	private _disposables: vscode.Disposable[] = [];
 	private _currentRule: Rule | undefined;
+	// File the panel was opened from; docs.rs links follow the version its crate uses
+	private _documentUri: vscode.Uri | undefined;
+	private readonly _cargoAnalyzer = CargoAnalyzerService.getInstance();
 
 	private constructor(panel: vscode.WebviewPanel, _extensionUri: vscode.Uri) {
 		this._panel = panel;
@@ -63,7 +67,10 @@ export class LearnPanel {
 			docUrl = RustDocsFetcher.buildDocUrl(term) || undefined;
 		}
 		if (!docUrl && this._currentRule?.officialDoc) {
-			docUrl = this._currentRule.officialDoc;
+			docUrl = await this._cargoAnalyzer.resolveDocUrl(
+				this._currentRule.officialDoc,
+				this._documentUri,
+			);
 		}
 		if (!docUrl && this._currentRule?.rustTerm) {
 			// Try to build URL from rustTerm
@@ -99,16 +106,24 @@ export class LearnPanel {
 		}
 	}
 
+	/**
+	 * The rule the panel is showing, if it shows one
+	 */
+	public get rule(): Rule | undefined {
+		return this._currentRule;
+	}
+
 	/**
 	 * Create or show the learn panel with content for a specific rule
 	 */
 	public static show(extensionUri: vscode.Uri, rule: Rule) {
 		const column = vscode.ViewColumn.Beside;
+		const documentUri = vscode.window.activeTextEditor?.document.uri;
 
 		// If we already have a panel, show it
 		if (LearnPanel.currentPanel) {
 			LearnPanel.currentPanel._panel.reveal(column);
-			LearnPanel.currentPanel._update(rule);
+			LearnPanel.currentPanel._update(rule, documentUri);
 			return;
 		}
 
@@ -125,7 +140,7 @@ export class LearnPanel {
 		);
 
 		LearnPanel.currentPanel = new LearnPanel(panel, extensionUri);
-		LearnPanel.currentPanel._update(rule);
+		LearnPanel.currentPanel._update(rule, documentUri);
 	}
 
 	/**
@@ -160,19 +175,20 @@ export class LearnPanel {
 		LearnPanel.currentPanel._updateError(errorCode, errorMessage, rules);
 	}
 
-	private async _update(rule: Rule) {
+	private async _update(rule: Rule, documentUri?: vscode.Uri) {
 		this._currentRule = rule;
+		this._documentUri = documentUri ?? this._documentUri;
 		this._panel.title = `🦀 ${rule.title}`;
 
 		// Show loading state immediately
 		this._panel.webview.html = this._getLoadingPageHtml(rule);
 
 		// Fetch official docs
-		const docUrl =
-			rule.officialDoc ||
-			RustDocsFetcher.buildDocUrl(
-				rule.rustTerm.replace(/[():]/g, "").split("::").pop() || "",
-			);
+		const docUrl = rule.officialDoc
+			? await this._cargoAnalyzer.resolveDocUrl(rule.officialDoc, this._documentUri)
+			: RustDocsFetcher.buildDocUrl(
+					rule.rustTerm.replace(/[():]/g, "").split("::").pop() || "",
+				);
 
 		let fetchedDoc: FetchedDocContent | null = null;
 		if (docUrl) {
@@ -301,7 +317,7 @@ export class LearnPanel {
                     
                     <section class="simple-example">
                         <h3>Example</h3>
-                        <pre><code class="language-rust">${this._escapeHtml(rule.example)}</code></pre>
+                        <pre><code class="language-${rule.language === "cargo-toml" ? "toml" : "rust"}">${this._escapeHtml(rule.example)}</code></pre>
                     </section>
                     
                     ${
@@ -414,6 +430,7 @@ export class LearnPanel {
 			current?: boolean;
 			recommended?: boolean;
 			docUrl?: string;
+			minRustVersion?: string; // Left out for crates supporting older Rust
 		}>;
 	} | null {
 		const alternatives: Record<string, ReturnType<typeof this._getAlternatives>> = {
@@ -757,6 +774,7 @@ export class LearnPanel {
 						description: "Match or diverge",
 						useWhen: "Must match, otherwise return/break",
 						docUrl: "https://doc.rust-lang.org/std/keyword.let.html",
+						minRustVersion: "1.65",
 					},
 					{
 						code: "while let",
@@ -774,7 +792,20 @@ export class LearnPanel {
 			},
 		};
 
-		return alternatives[ruleId] || null;
+		const topic = alternatives[ruleId];
+		const rustVersion =
+			this._documentUri &&
+			this._cargoAnalyzer.getProjectDependencies(this._documentUri).rustVersion;
+		if (!topic || !rustVersion) {
+			return topic || null;
+		}
+		return {
+			...topic,
+			options: topic.options.filter(
+				(opt) =>
+					!opt.minRustVersion || satisfiesVersion(rustVersion, `>=${opt.minRustVersion}`),
+			),
+		};
 	}
 
 	/**
@@ -835,7 +866,7 @@ export class LearnPanel {
             <p class="lead">${this._escapeHtml(rule.explanation)}</p>
             
             <h2>Example</h2>
-            <pre><code class="language-rust">${this._escapeHtml(rule.example)}</code></pre>
+            <pre><code class="language-${rule.language === "cargo-toml" ? "toml" : "rust"}">${this._escapeHtml(rule.example)}</code></pre>
             
             ${
 				docUrl
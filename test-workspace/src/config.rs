@@ -0,0 +1,10 @@
+use std::fs;
+
+pub fn read_config(path: &str) -> String {
+    let text = fs::read_to_string(path).unwrap();
+    text.trim().to_string()
+}
+
+pub fn config_len() -> usize {
+    read_config("app.toml").len()
+}
@@ -0,0 +1,10 @@
+pub fn greet(name: Option<&str>) -> String {
+    match name {
+        Some(name) => format!("Hello, {name}!"),
+        None => String::from("Hello!"),
+    }
+}
+
+pub fn port(value: Option<&str>) -> u16 {
+    value.and_then(|v| v.parse().ok()).unwrap_or(8080)
+}
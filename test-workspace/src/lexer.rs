@@ -0,0 +1,15 @@
+/// Splits source text into words and numbers
+pub fn tokenize(source: String) -> Result<Vec<String>, std::num::ParseIntError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars();
+    while let Some(c) = chars.next() {
+        if c.is_ascii_digit() {
+            let value: u32 = c.to_string().parse().unwrap();
+            tokens.push(value.to_string());
+        } else if !c.is_whitespace() {
+            let text = c.to_string().trim().parse::<String>().unwrap();
+            tokens.push(text.clone());
+        }
+    }
+    Ok(tokens)
+}
@@ -0,0 +1,7 @@
+pub fn lengths(words: &Vec<String>) -> Vec<usize> {
+    let mut lengths = Vec::new();
+    for i in 0..words.len() {
+        lengths.push(words[i].len());
+    }
+    lengths
+}
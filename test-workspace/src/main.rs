@@ -1,3 +1,8 @@
+mod config;
+mod greeting;
+mod lexer;
+mod loops;
+
 use std::vec;
 
 struct Token {